@@ -0,0 +1,170 @@
+use std::{
+  hint::black_box,
+  ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rhg_engine_core::{Lanes, Mat4f32, Matrix, Unit, Vec3f32, Vec4f32, Vector, Zero, SIMD_BACKEND};
+
+/// `f32` going through the generic scalar path
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scalar(f32);
+
+impl Lanes for Scalar {}
+
+impl Zero for Scalar {
+  fn zero() -> Self {
+    Self(0.)
+  }
+}
+
+impl Unit for Scalar {
+  fn unit() -> Self {
+    Self(1.)
+  }
+}
+
+macro_rules! scalar_op_impl {
+  ( $( ($op:ident, $fn:ident, $op_assign:ident, $fn_assign:ident) ),* ) => {
+    $(
+    impl $op for Scalar {
+      type Output = Self;
+
+      fn $fn(self, rhs: Self) -> Self {
+        Self(self.0.$fn(rhs.0))
+      }
+    }
+
+    impl $op_assign for Scalar {
+      fn $fn_assign(&mut self, rhs: Self) {
+        self.0.$fn_assign(rhs.0)
+      }
+    }
+  )*
+  };
+}
+
+scalar_op_impl!(
+  (Add, add, AddAssign, add_assign),
+  (Sub, sub, SubAssign, sub_assign),
+  (Mul, mul, MulAssign, mul_assign),
+  (Div, div, DivAssign, div_assign)
+);
+
+fn scalar<const N: usize>(v: Vector<f32, N>) -> Vector<Scalar, N> {
+  v.map(Scalar)
+}
+
+fn pad(v: Vec3f32) -> Vec4f32 {
+  Vec4f32::new(v.x(), v.y(), v.z(), 0.)
+}
+
+fn narrow(v: Vec4f32) -> Vec3f32 {
+  Vec3f32::new(v.x(), v.y(), v.z())
+}
+
+fn scalar_mat(m: Mat4f32) -> Matrix<Scalar, 4, 4> {
+  Matrix::from_columns(m.columns().map(scalar))
+}
+
+fn vectors(c: &mut Criterion) {
+  let (a, b) = (Vec4f32::new(1., 2., 3., 4.), Vec4f32::new(0.5, -1., 2., 8.));
+  let (sa, sb) = (scalar(a), scalar(b));
+
+  let mut group = c.benchmark_group(format!("vec4f32 ({})", SIMD_BACKEND));
+  group.bench_function("add/simd", |bench| {
+    bench.iter(|| black_box(a) + black_box(b))
+  });
+  group.bench_function("add/generic", |bench| {
+    bench.iter(|| black_box(sa) + black_box(sb))
+  });
+  group.bench_function("mul_div/simd", |bench| {
+    bench.iter(|| black_box(a) * black_box(b) / black_box(b))
+  });
+  group.bench_function("mul_div/generic", |bench| {
+    bench.iter(|| black_box(sa) * black_box(sb) / black_box(sb))
+  });
+  group.bench_function("dot/simd", |bench| {
+    bench.iter(|| black_box(a).dot(&black_box(b)))
+  });
+  group.bench_function("dot/generic", |bench| {
+    bench.iter(|| black_box(sa).dot(&black_box(sb)))
+  });
+  group.bench_function("add_batch/simd", |bench| {
+    let vs = vec![a; 1024];
+    bench.iter(|| vs.iter().fold(b, |acc, v| acc + *v))
+  });
+  group.bench_function("add_batch/generic", |bench| {
+    let vs = vec![sa; 1024];
+    bench.iter(|| vs.iter().fold(sb, |acc, v| acc + *v))
+  });
+  group.finish();
+
+  // 3 lanes vectors stay on the scalar path, `padded` is what going through a register costs
+  let (a, b) = (Vec3f32::new(1., 2., 3.), Vec3f32::new(0.5, -1., 2.));
+  let mut group = c.benchmark_group(format!("vec3f32 ({})", SIMD_BACKEND));
+  group.bench_function("add/scalar", |bench| {
+    bench.iter(|| black_box(a) + black_box(b))
+  });
+  group.bench_function("add/padded", |bench| {
+    bench.iter(|| narrow(pad(black_box(a)) + pad(black_box(b))))
+  });
+  group.bench_function("dot/scalar", |bench| {
+    bench.iter(|| black_box(a).dot(&black_box(b)))
+  });
+  group.bench_function("dot/padded", |bench| {
+    bench.iter(|| pad(black_box(a)).dot(&pad(black_box(b))))
+  });
+  group.bench_function("add_batch/scalar", |bench| {
+    let vs = vec![a; 1024];
+    bench.iter(|| vs.iter().fold(b, |acc, v| acc + *v))
+  });
+  group.bench_function("add_batch/padded", |bench| {
+    let vs = vec![a; 1024];
+    bench.iter(|| vs.iter().fold(b, |acc, v| narrow(pad(acc) + pad(*v))))
+  });
+  group.finish();
+}
+
+fn matrices(c: &mut Criterion) {
+  let m = Mat4f32::perspective(1.2, 1.5, 0.1, 100.)
+    * Mat4f32::look_at(Vec3f32::new(1., 2., 3.), Vec3f32::zero(), Vec3f32::unit_y());
+  let n =
+    Mat4f32::rotation(Vec3f32::new(1., 1., 0.), 0.3) * Mat4f32::scale(Vec3f32::new(2., 3., 4.));
+  let v = Vec4f32::new(1., 2., 3., 1.);
+  let (sm, sn, sv) = (scalar_mat(m), scalar_mat(n), scalar(v));
+
+  let mut group = c.benchmark_group(format!("mat4f32 ({})", SIMD_BACKEND));
+  group.bench_function("mul_vec/simd", |bench| {
+    bench.iter(|| black_box(m) * black_box(v))
+  });
+  group.bench_function("mul_vec/generic", |bench| {
+    bench.iter(|| black_box(sm) * black_box(sv))
+  });
+  group.bench_function("mul_vec_batch/simd", |bench| {
+    let vs = vec![v; 1024];
+    bench.iter(|| vs.iter().map(|v| m * *v).fold(v, |acc, v| acc + v))
+  });
+  group.bench_function("mul_vec_batch/generic", |bench| {
+    let vs = vec![sv; 1024];
+    bench.iter(|| vs.iter().map(|v| sm * *v).fold(sv, |acc, v| acc + v))
+  });
+  group.bench_function("mul_mat/simd", |bench| {
+    bench.iter(|| black_box(m) * black_box(n))
+  });
+  group.bench_function("mul_mat/generic", |bench| {
+    bench.iter(|| black_box(sm) * black_box(sn))
+  });
+  group.bench_function("mul_mat_batch/simd", |bench| {
+    let ms = vec![m; 1024];
+    bench.iter(|| ms.iter().fold(n, |acc, m| *m * acc))
+  });
+  group.bench_function("mul_mat_batch/generic", |bench| {
+    let ms = vec![sm; 1024];
+    bench.iter(|| ms.iter().fold(sn, |acc, m| *m * acc))
+  });
+  group.finish();
+}
+
+criterion_group!(benches, vectors, matrices);
+criterion_main!(benches);
@@ -64,14 +64,26 @@ impl ArchiveFile {
     self.created_at.as_ref()
   }
 
+  pub fn created_at_mut(&mut self) -> &mut Option<SystemTime> {
+    &mut self.created_at
+  }
+
   pub fn modified_at(&self) -> Option<&SystemTime> {
     self.modified_at.as_ref()
   }
 
+  pub fn modified_at_mut(&mut self) -> &mut Option<SystemTime> {
+    &mut self.modified_at
+  }
+
   pub fn archived_at(&self) -> Option<&SystemTime> {
     self.archived_at.as_ref()
   }
 
+  pub fn archived_at_mut(&mut self) -> &mut Option<SystemTime> {
+    &mut self.archived_at
+  }
+
   pub fn name(&self) -> Option<String> {
     self
       .path
@@ -119,6 +131,185 @@ impl ArchiveFile {
   }
 }
 
+/// Metadata of an archive entry, known before its content gets written
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArchiveEntry {
+  path: PathBuf,
+  content_len: u64,
+  created_at: Option<SystemTime>,
+  modified_at: Option<SystemTime>,
+}
+
+impl ArchiveEntry {
+  pub fn new<P: AsRef<Path>>(path: P, content_len: u64) -> Self {
+    Self {
+      path: path.as_ref().to_path_buf(),
+      content_len,
+      created_at: None,
+      modified_at: None,
+    }
+  }
+
+  /// Describe a file on disk without reading its content
+  pub fn stat<P: AsRef<Path>>(path: P) -> crate::Result<ArchiveEntry> {
+    let md = std::fs::metadata(&path).map_err(|e| {
+      Error::new(
+        ErrorKind::IO,
+        format!("{}: {}", path.as_ref().display(), e),
+        None,
+        here!(),
+      )
+    })?;
+    let mut e = Self::new(path, md.len());
+    e.modified_at = Some(md.modified()?);
+    e.created_at = Some(md.created()?);
+    Ok(e)
+  }
+
+  pub fn path(&self) -> &PathBuf {
+    &self.path
+  }
+
+  pub fn content_len(&self) -> u64 {
+    self.content_len
+  }
+
+  pub fn created_at(&self) -> Option<&SystemTime> {
+    self.created_at.as_ref()
+  }
+
+  pub fn modified_at(&self) -> Option<&SystemTime> {
+    self.modified_at.as_ref()
+  }
+}
+
+impl From<&ArchiveFile> for ArchiveEntry {
+  fn from(f: &ArchiveFile) -> Self {
+    Self {
+      path: f.path.clone(),
+      content_len: f.content.len() as u64,
+      created_at: f.created_at,
+      modified_at: f.modified_at,
+    }
+  }
+}
+
+fn unix_secs(time: Option<&SystemTime>) -> u64 {
+  time
+    .map(|t| {
+      t.duration_since(SystemTime::UNIX_EPOCH)
+        .expect("invalid epoch")
+        .as_secs()
+    })
+    .unwrap_or_default()
+}
+
+/// Streams an archive: the header is written upfront from the entries metadata,
+/// then each entry content is written in order, without holding the whole archive in memory
+pub struct ArchiveWriter<W: std::io::Write> {
+  w: W,
+  entries: Vec<ArchiveEntry>,
+  offsets: Vec<u64>,
+  archived_at: SystemTime,
+  next: usize,
+}
+
+impl<W: std::io::Write> ArchiveWriter<W> {
+  pub fn new(mut w: W, entries: Vec<ArchiveEntry>) -> crate::Result<Self> {
+    const U64_LEN: u64 = (u64::BITS / 8) as u64;
+    let paths = entries
+      .iter()
+      .map(|e| format!("{}", e.path.display()))
+      .collect::<Vec<_>>();
+    // magic, version len, version, file count
+    let mut offset = U64_LEN * 3 + ARCHIVE_VERSION.len() as u64;
+    // path len, path, content len, offset, created at, modified at, archived at
+    offset += paths
+      .iter()
+      .map(|path| U64_LEN * 6 + path.len() as u64)
+      .sum::<u64>();
+    let archived_at = SystemTime::now();
+    w.write_all(&ARCHIVE_MAGIC_NUMBER.to_le_bytes())?;
+    w.write_all(&(ARCHIVE_VERSION.len() as u64).to_le_bytes())?;
+    w.write_all(ARCHIVE_VERSION.as_bytes())?;
+    w.write_all(&(entries.len() as u64).to_le_bytes())?;
+    let mut offsets = Vec::with_capacity(entries.len());
+    for (e, path) in entries.iter().zip(&paths) {
+      w.write_all(&(path.len() as u64).to_le_bytes())?;
+      w.write_all(path.as_bytes())?;
+      w.write_all(&e.content_len.to_le_bytes())?;
+      w.write_all(&offset.to_le_bytes())?;
+      w.write_all(&unix_secs(e.created_at.as_ref()).to_le_bytes())?;
+      w.write_all(&unix_secs(e.modified_at.as_ref()).to_le_bytes())?;
+      w.write_all(&unix_secs(Some(&archived_at)).to_le_bytes())?;
+      offsets.push(offset);
+      offset += e.content_len;
+    }
+    Ok(Self {
+      w,
+      entries,
+      offsets,
+      archived_at,
+      next: 0,
+    })
+  }
+
+  pub fn entries(&self) -> &[ArchiveEntry] {
+    &self.entries
+  }
+
+  pub fn archived_at(&self) -> SystemTime {
+    self.archived_at
+  }
+
+  /// Entry whose content is expected by the next call to `write_content`
+  pub fn next_entry(&self) -> Option<&ArchiveEntry> {
+    self.entries.get(self.next)
+  }
+
+  pub fn next_offset(&self) -> Option<u64> {
+    self.offsets.get(self.next).copied()
+  }
+
+  pub fn write_content(&mut self, content: &[u8]) -> crate::Result<()> {
+    let e = match self.entries.get(self.next) {
+      Some(e) => e,
+      None => return err!(ErrorKind::InvalidInput, "all archive entries were already written"),
+    };
+    if e.content_len != content.len() as u64 {
+      return err!(
+        ErrorKind::IO,
+        format!(
+          "'{}' changed while packing, expected {}B but got {}B",
+          e.path.display(),
+          e.content_len,
+          content.len()
+        )
+      );
+    }
+    log::debug!(
+      "write '{}' at 0x{:04x}",
+      e.path.display(),
+      self.offsets[self.next]
+    );
+    self.w.write_all(content)?;
+    self.next += 1;
+    Ok(())
+  }
+
+  pub fn finish(mut self) -> crate::Result<W> {
+    if let Some(e) = self.entries.get(self.next) {
+      return err!(
+        ErrorKind::InvalidInput,
+        format!("missing content for '{}'", e.path.display())
+      );
+    }
+    self.w.flush()?;
+    Ok(self.w)
+  }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Archive {
   path: Option<PathBuf>,
@@ -139,10 +330,16 @@ impl Archive {
     return None;
   }
 
+  /// Entry stored at exactly `path`, unlike [`Archive::get_file`] which also matches file names
+  pub fn get_path<P: AsRef<Path>>(&self, path: P) -> Option<&ArchiveFile> {
+    self.files.iter().find(|f| f.path() == path.as_ref())
+  }
+
+  /// Fails if an entry is already stored at the same path
   pub fn add(&mut self, f: ArchiveFile) -> crate::Result<&mut ArchiveFile> {
-    if let Some(_) = self.get_file(f.path()) {
+    if self.get_path(f.path()).is_some() {
       return err!(
-        ErrorKind::IO,
+        ErrorKind::InvalidInput,
         format!("file '{}' already exists", f.path().display())
       );
     }
@@ -167,6 +364,11 @@ impl Archive {
     return None;
   }
 
+  pub fn remove_path<P: AsRef<Path>>(&mut self, path: P) -> Option<ArchiveFile> {
+    let i = self.files.iter().position(|f| f.path() == path.as_ref())?;
+    Some(self.files.remove(i))
+  }
+
   pub fn files(&self) -> &Vec<ArchiveFile> {
     &self.files
   }
@@ -185,70 +387,14 @@ impl Archive {
     if let Some(path) = path {
       self.path = Some(path.to_path_buf());
     }
-    let mut nwritten = w.write(&(ARCHIVE_MAGIC_NUMBER as u64).to_le_bytes())?;
-    nwritten += w.write(&(ARCHIVE_VERSION.len() as u64).to_le_bytes())?;
-    nwritten += w.write(ARCHIVE_VERSION.as_bytes())?;
-    nwritten += w.write(&(self.files.len() as u64).to_le_bytes())?;
-    let mut buf: Vec<u8> = vec![];
-    let mut header_len = nwritten;
-    for f in &self.files {
-      let path = format!("{}", f.path.display()).to_string();
-      // path len
-      header_len += (u64::BITS / 8) as usize;
-      // path
-      header_len += path.as_bytes().len();
-      // content len
-      header_len += (u64::BITS / 8) as usize;
-      // offset
-      header_len += (u64::BITS / 8) as usize;
-      // created at
-      header_len += (u64::BITS / 8) as usize;
-      // modified at
-      header_len += (u64::BITS / 8) as usize;
-      // archived at
-      header_len += (u64::BITS / 8) as usize;
-    }
-    let mut offset: usize = header_len;
+    let entries = self.files.iter().map(ArchiveEntry::from).collect();
+    let mut writer = ArchiveWriter::new(w, entries)?;
     for f in &mut self.files {
-      let path = format!("{}", f.path.display()).to_string();
-      let modified_at = f
-        .modified_at
-        .map(|mt| {
-          mt.duration_since(SystemTime::UNIX_EPOCH)
-            .expect("invalid epoch")
-            .as_secs()
-        })
-        .unwrap_or_default();
-      let created_at = f
-        .created_at
-        .map(|mt| {
-          mt.duration_since(SystemTime::UNIX_EPOCH)
-            .expect("invalid epoch")
-            .as_secs()
-        })
-        .unwrap_or_default();
-      f.archived_at = Some(SystemTime::now());
-      let archived_at = f
-        .archived_at
-        .map(|mt| {
-          mt.duration_since(SystemTime::UNIX_EPOCH)
-            .expect("invalid epoch")
-            .as_secs()
-        })
-        .unwrap_or_default();
-      header_len += w.write(&(path.len() as u64).to_le_bytes())?;
-      header_len += w.write(path.as_bytes())?;
-      header_len += w.write(&(f.content.len() as u64).to_le_bytes())?;
-      header_len += w.write(&(offset as u64).to_le_bytes())?;
-      header_len += w.write(&(created_at as u64).to_le_bytes())?;
-      header_len += w.write(&(modified_at as u64).to_le_bytes())?;
-      header_len += w.write(&(archived_at as u64).to_le_bytes())?;
-      f.offset = offset as u64;
-      buf.extend_from_slice(&f.content);
-      println!("write '{}' at 0x{:04x}", path, offset);
-      offset += f.content.len()
+      f.offset = writer.next_offset().unwrap_or_default();
+      f.archived_at = Some(writer.archived_at());
+      writer.write_content(&f.content)?;
     }
-    let _ = w.write(&buf)?;
+    writer.finish()?;
     Ok(())
   }
 
@@ -271,6 +417,19 @@ impl Archive {
         here!(),
       )
     })?;
+    Self::parse(path.as_ref(), bytes).map_err(|e| match e.kind() {
+      ErrorKind::IO | ErrorKind::Corrupted => Error::new(
+        ErrorKind::Corrupted,
+        format!("corrupted archive '{}'", path.as_ref().display()),
+        Some(e),
+        here!(),
+      ),
+      _ => e,
+    })
+  }
+
+  fn parse(path: &Path, bytes: Vec<u8>) -> crate::Result<Archive> {
+    let total_len = bytes.len() as u64;
     let mut u64_buf: [u8; 8] = [0; 8];
     let mut curs = Cursor::new(bytes);
 
@@ -278,6 +437,9 @@ impl Archive {
     let _magic = u64::from_le_bytes(u64_buf);
     let _ = curs.read(&mut u64_buf)?;
     let _version_len = u64::from_le_bytes(u64_buf);
+    if _version_len > total_len {
+      return err!(ErrorKind::Corrupted, "invalid version length");
+    }
     let mut _version = vec![0; _version_len as usize];
     let _ = curs.read_exact(&mut _version)?;
     let _version_str = String::from_utf8_lossy(&_version);
@@ -286,11 +448,12 @@ impl Archive {
     let num_files = u64::from_le_bytes(u64_buf);
 
     if _magic != ARCHIVE_MAGIC_NUMBER {
-      return err!(ErrorKind::IO, "corrupted archive");
+      return err!(ErrorKind::Corrupted, "invalid magic number");
     } else if !_version_str.eq(ARCHIVE_VERSION) {
-      eprintln!(
-        "\x1b[0;33mwarn\x1b[0m archive created using packer v{} but archiver is v{}",
-        _version_str, ARCHIVE_VERSION
+      log::warn!(
+        "archive created using packer v{} but archiver is v{}",
+        _version_str,
+        ARCHIVE_VERSION
       )
     }
 
@@ -298,11 +461,14 @@ impl Archive {
     let mut f_path: Vec<u8>;
     let mut f_content_len: usize;
     let mut a = Archive::default();
-    a.path = Some(path.as_ref().to_path_buf());
+    a.path = Some(path.to_path_buf());
     for _ in 0..num_files {
       let _ = curs.read_exact(&mut u64_buf)?;
       f_path_len = u64::from_le_bytes(u64_buf) as usize;
 
+      if f_path_len as u64 > total_len {
+        return err!(ErrorKind::Corrupted, "invalid path length");
+      }
       f_path = vec![0; f_path_len];
       let _ = curs.read_exact(&mut f_path)?;
 
@@ -321,6 +487,9 @@ impl Archive {
       let _ = curs.read_exact(&mut u64_buf)?;
       let archived_at = u64::from_le_bytes(u64_buf);
 
+      if offset.checked_add(content_len).is_none_or(|end| end > total_len) {
+        return err!(ErrorKind::Corrupted, "invalid file content range");
+      }
       let mut content: Vec<u8> = vec![0; content_len as usize];
       let prev_pos = curs.stream_position()?;
       curs.seek(std::io::SeekFrom::Start(offset as u64))?;
@@ -346,3 +515,83 @@ impl Archive {
     Ok(a)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::ErrorKind;
+
+  use super::{Archive, ArchiveEntry, ArchiveWriter};
+
+  #[test]
+  fn round_trip() {
+    let mut a = Archive::default();
+    a.add_file("textures/wall.png", b"wall").unwrap();
+    a.add_file("readme.txt", b"hello").unwrap();
+    let mut buf = vec![];
+    a.save(None, &mut buf).unwrap();
+
+    let b = Archive::load("test.pack", &mut buf.as_slice()).unwrap();
+    assert_eq!(b.files().len(), 2);
+    assert_eq!(b.files()[0].path(), a.files()[0].path());
+    assert_eq!(b.files()[1].content(), &b"hello".to_vec());
+  }
+
+  #[test]
+  fn same_name() {
+    let mut a = Archive::default();
+    a.add_file("textures/a.png", b"texture").unwrap();
+    a.add_file("models/a.png", b"model").unwrap();
+    assert!(a.add_file("models/a.png", b"again").is_err());
+    assert_eq!(a.get_path("models/a.png").unwrap().content(), b"model");
+    assert!(a.get_path("a.png").is_none());
+    assert_eq!(a.remove_path("models/a.png").unwrap().content(), b"model");
+    assert_eq!(a.files().len(), 1);
+  }
+
+  #[test]
+  fn corrupted() {
+    let mut a = Archive::default();
+    a.add_file("readme.txt", b"hello").unwrap();
+    let mut buf = vec![];
+    a.save(None, &mut buf).unwrap();
+
+    let e = Archive::load("test.pack", &mut &buf[..buf.len() - 2]).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::Corrupted);
+    let e = Archive::load("test.pack", &mut &b"not an archive"[..]).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::Corrupted);
+  }
+
+  #[test]
+  fn stream() {
+    let entries = vec![ArchiveEntry::new("a.txt", 1), ArchiveEntry::new("b.txt", 2)];
+    let mut w = ArchiveWriter::new(vec![], entries).unwrap();
+    w.write_content(b"a").unwrap();
+    assert!(w.write_content(b"b").is_err());
+    w.write_content(b"bb").unwrap();
+    let buf = w.finish().unwrap();
+
+    let a = Archive::load("test.pack", &mut buf.as_slice()).unwrap();
+    assert_eq!(a.files()[0].content(), &b"a".to_vec());
+    assert_eq!(a.files()[1].content(), &b"bb".to_vec());
+
+    let w = ArchiveWriter::new(vec![], vec![ArchiveEntry::new("a.txt", 1)]).unwrap();
+    assert!(w.finish().is_err());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serde() {
+    let mut a = Archive::default();
+    a.add_file("textures/wall.png", b"wall").unwrap();
+    let entry = ArchiveEntry::from(&a.files()[0]);
+    let json = serde_json::to_string(&entry).unwrap();
+    assert_eq!(serde_json::from_str::<ArchiveEntry>(&json).unwrap(), entry);
+
+    let e = a.add_file("textures/wall.png", b"again").unwrap_err();
+    let json = serde_json::to_string(&e).unwrap();
+    let back = serde_json::from_str::<crate::Error>(&json).unwrap();
+    assert_eq!(back.kind(), e.kind());
+    assert_eq!(back.message(), e.message());
+    assert_eq!(back.location(), e.location());
+  }
+}
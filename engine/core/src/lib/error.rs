@@ -6,13 +6,19 @@ use std::{
 use crate::{here, Location};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ErrorKind {
   IO,
+  NotFound,
+  InvalidInput,
+  Corrupted,
   Rendering,
+  Event,
   Unknown,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Error {
   kind: ErrorKind,
   message: String,
@@ -29,6 +35,22 @@ impl Error {
       location,
     }
   }
+
+  pub fn kind(&self) -> ErrorKind {
+    self.kind
+  }
+
+  pub fn message(&self) -> &String {
+    &self.message
+  }
+
+  pub fn cause(&self) -> Option<&Error> {
+    self.cause.as_deref()
+  }
+
+  pub fn location(&self) -> &Location {
+    &self.location
+  }
 }
 
 impl Display for Error {
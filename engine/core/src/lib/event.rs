@@ -1,36 +1,366 @@
-use std::{any::Any, cell::RefCell, collections::VecDeque, fmt::Debug, rc::Rc, sync::Arc};
+use std::{
+  any::{Any, TypeId},
+  cell::{Cell, RefCell},
+  collections::VecDeque,
+  fmt::Debug,
+  rc::Rc,
+  sync::{
+    mpsc::{channel, Receiver, Sender},
+    Arc,
+  },
+  time::Duration,
+};
+
+use as_any::AsAny;
+
+use crate::{err, here, Clock, EngineClock, Error, ErrorKind};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event {
   EngineInitStarted,
   EngineInitStopped,
-  Custom(Arc<dyn Any>),
+  /// Application defined, cannot be serialized
+  #[cfg_attr(feature = "serde", serde(skip))]
+  Custom(Arc<dyn CustomEvent>),
+}
+
+impl Event {
+  /// Wraps `value` in [`Event::Custom`], unless it already is an [`Event`]
+  pub fn custom<T: CustomEvent>(value: T) -> Self {
+    match (&value as &dyn Any).downcast_ref::<Event>() {
+      Some(e) => e.clone(),
+      None => Self::Custom(Arc::new(value)),
+    }
+  }
+
+  /// Custom payload, or the event itself for `T = Event`
+  pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+    match self {
+      Self::Custom(payload) => payload.as_ref().as_any().downcast_ref(),
+      _ => (self as &dyn Any).downcast_ref(),
+    }
+  }
+
+  /// Type subscribers have to listen to in order to receive this event
+  pub fn payload_type(&self) -> TypeId {
+    match self {
+      Self::Custom(payload) => payload.as_ref().as_any().type_id(),
+      _ => TypeId::of::<Event>(),
+    }
+  }
 }
 
 impl PartialEq for Event {
   fn eq(&self, other: &Self) -> bool {
     match (self, other) {
-      (Self::Custom(l0), Self::Custom(r0)) => true,
+      (Self::Custom(l0), Self::Custom(r0)) => l0.eq_dyn(r0.as_ref()),
       _ => core::mem::discriminant(self) == core::mem::discriminant(other),
     }
   }
 }
 
-unsafe impl Send for Event {}
-unsafe impl Sync for Event {}
+/// Payload of [`Event::Custom`], implemented for every comparable type that can be shared
+/// between threads
+pub trait CustomEvent: AsAny + Debug + Send + Sync {
+  /// Same type and equal value
+  fn eq_dyn(&self, other: &dyn CustomEvent) -> bool;
+}
+
+impl<T: Any + Debug + PartialEq + Send + Sync> CustomEvent for T {
+  fn eq_dyn(&self, other: &dyn CustomEvent) -> bool {
+    other
+      .as_any()
+      .downcast_ref::<T>()
+      .is_some_and(|o| self == o)
+  }
+}
+
+/// Posts events to a bus from any thread, they are dispatched by its next propagation
+#[derive(Debug, Clone)]
+pub struct EventSender(Sender<Event>);
+
+impl EventSender {
+  /// Fails once the bus is dropped
+  pub fn send(&self, e: Event) -> crate::Result<()> {
+    match self.0.send(e) {
+      Ok(()) => Ok(()),
+      Err(_) => err!(ErrorKind::Event, "the event bus was dropped"),
+    }
+  }
+
+  pub fn emit<T: CustomEvent>(&self, value: T) -> crate::Result<()> {
+    self.send(Event::custom(value))
+  }
+}
+
+/// Where a propagated event comes from
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum EventOrigin {
+  /// Queued on the bus or posted by an [`EventSender`]
+  #[default]
+  Input,
+  /// Fired by a timer or deferred to this frame by a listener
+  Scheduled,
+  /// Emitted by a listener during the same frame
+  Emitted,
+}
+
+/// State of an event during its propagation
+#[derive(Debug, Default)]
+pub struct EventContext {
+  handled: bool,
+  depth: usize,
+  origin: EventOrigin,
+  frame: u64,
+  time: Duration,
+  emitted: Vec<Event>,
+  deferred: Vec<Event>,
+  delayed: Vec<(Event, Duration)>,
+}
+
+impl EventContext {
+  /// Stops the propagation to the listeners with a lower priority
+  pub fn set_handled(&mut self) {
+    self.handled = true;
+  }
+
+  pub fn is_handled(&self) -> bool {
+    self.handled
+  }
+
+  /// Number of events that led to this one within the frame, 0 for events queued from outside
+  /// of a listener
+  pub fn depth(&self) -> usize {
+    self.depth
+  }
+
+  pub fn origin(&self) -> EventOrigin {
+    self.origin
+  }
+
+  /// Index of the propagation, see [`EventBus::frame`]
+  pub fn frame(&self) -> u64 {
+    self.frame
+  }
+
+  /// Game time when the propagation started
+  pub fn time(&self) -> Duration {
+    self.time
+  }
+
+  /// Queues `value` after the events already queued for this frame
+  pub fn emit<T: CustomEvent>(&mut self, value: T) {
+    self.emitted.push(Event::custom(value));
+  }
+
+  /// Queues `value` for the next propagation, after the events queued from outside
+  pub fn emit_next_frame<T: CustomEvent>(&mut self, value: T) {
+    self.deferred.push(Event::custom(value));
+  }
+
+  /// Queues `value` once `delay` of game time went by
+  pub fn emit_after<T: CustomEvent>(&mut self, value: T, delay: Duration) {
+    self.delayed.push((Event::custom(value), delay));
+  }
+}
+
+/// Handle to cancel a scheduled event
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Due {
+  Time(Duration),
+  Frame(u64),
+}
+
+#[derive(Debug)]
+struct Timer {
+  id: TimerId,
+  due: Due,
+  period: Option<Duration>,
+  event: Event,
+}
+
+/// Events waiting for a point in game time or for a frame
+#[derive(Debug, Default)]
+pub struct Timers {
+  timers: Vec<Timer>,
+  next_id: u64,
+}
+
+impl Timers {
+  fn schedule(&mut self, due: Due, period: Option<Duration>, event: Event) -> TimerId {
+    let id = TimerId(self.next_id);
+    self.next_id += 1;
+    self.timers.push(Timer {
+      id,
+      due,
+      period,
+      event,
+    });
+    id
+  }
+
+  /// Returns `false` if the timer already fired or was cancelled
+  pub fn cancel(&mut self, id: TimerId) -> bool {
+    let len = self.timers.len();
+    self.timers.retain(|t| t.id != id);
+    self.timers.len() != len
+  }
+
+  pub fn len(&self) -> usize {
+    self.timers.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.timers.is_empty()
+  }
+
+  /// Events due at `now` or `frame`, frame timers first then by due time. Repeating timers
+  /// fire once per elapsed period.
+  fn fire(&mut self, now: Duration, frame: u64) -> Vec<Event> {
+    let mut fired = vec![];
+    self.timers.retain_mut(|t| match t.due {
+      Due::Frame(f) => {
+        if f <= frame {
+          fired.push((Duration::ZERO, t.id, t.event.clone()));
+        }
+        f > frame
+      }
+      Due::Time(mut at) => {
+        while at <= now {
+          fired.push((at, t.id, t.event.clone()));
+          match t.period {
+            Some(period) => at += period,
+            None => return false,
+          }
+        }
+        t.due = Due::Time(at);
+        true
+      }
+    });
+    fired.sort_by_key(|(at, id, _)| (*at, id.0));
+    fired.into_iter().map(|(_, _, e)| e).collect()
+  }
+}
+
+/// What [`EventBus::propagate`] does when a listener fails
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+  /// Returns the error right away, the events left are dispatched by the next propagation
+  #[default]
+  Stop,
+  /// Keeps going and stores the errors, see [`EventBus::take_errors`]
+  Collect,
+  /// Keeps going and logs the errors
+  Log,
+}
+
+impl ErrorPolicy {
+  /// Returns the error when the propagation has to stop
+  fn handle(&self, e: Error, errors: &mut Vec<Error>) -> Option<Error> {
+    match self {
+      Self::Stop => return Some(e),
+      Self::Collect => errors.push(e),
+      Self::Log => log::error!("event listener failed: {}", e),
+    }
+    None
+  }
+}
 
 pub trait EventListener {
-  fn on_event(&mut self, value: &Event) -> crate::Result<()>;
+  fn on_event(&mut self, value: &Event, ctx: &mut EventContext) -> crate::Result<()>;
 }
 
 pub type EventListenerPtr = Rc<RefCell<dyn EventListener>>;
 
+/// Typed subscription, only called with events of the type it was registered for
+pub type EventHandler = Box<dyn FnMut(&Event, &mut EventContext) -> crate::Result<()>>;
+
+/// Keeps a listener registered, it is removed from the bus once this is dropped
+#[must_use = "dropping a subscription unsubscribes right away"]
+#[derive(Debug)]
+pub struct Subscription(Option<Rc<Cell<bool>>>);
+
+impl Subscription {
+  /// Keeps the listener registered for the lifetime of the bus
+  pub fn detach(mut self) {
+    self.0 = None;
+  }
+}
+
+impl Drop for Subscription {
+  fn drop(&mut self) {
+    if let Some(active) = self.0.take() {
+      active.set(false);
+    }
+  }
+}
+
+enum SubscriberTarget {
+  Listener(EventListenerPtr),
+  Handler(TypeId, EventHandler),
+}
+
+/// Registered listener, see [`EventBus::subscribers`]
+pub struct Subscriber {
+  priority: i32,
+  target: SubscriberTarget,
+  active: Rc<Cell<bool>>,
+}
+
+impl Subscriber {
+  fn new(priority: i32, target: SubscriberTarget) -> (Self, Subscription) {
+    let active = Rc::new(Cell::new(true));
+    let ret = Self {
+      priority,
+      target,
+      active: active.clone(),
+    };
+    (ret, Subscription(Some(active)))
+  }
+
+  pub fn priority(&self) -> i32 {
+    self.priority
+  }
+
+  /// Whether its [`Subscription`] is still alive or was detached
+  pub fn is_active(&self) -> bool {
+    self.active.get()
+  }
+
+  fn on_event(&mut self, evt: &Event, ctx: &mut EventContext) -> crate::Result<()> {
+    match &mut self.target {
+      SubscriberTarget::Listener(l) => l.borrow_mut().on_event(evt, ctx),
+      SubscriberTarget::Handler(ty, handler) if *ty == evt.payload_type() => handler(evt, ctx),
+      SubscriberTarget::Handler(..) => Ok(()),
+    }
+  }
+}
+
 pub trait EventBus {
-  fn listeners(&self) -> &VecDeque<EventListenerPtr>;
-  fn listeners_mut(&mut self) -> &mut VecDeque<EventListenerPtr>;
+  /// Listeners sorted by decreasing priority, then registration order
+  fn subscribers(&self) -> &Vec<Subscriber>;
+  fn subscribers_mut(&mut self) -> &mut Vec<Subscriber>;
+
+  fn add_subscriber(&mut self, s: Subscriber) {
+    let subscribers = self.subscribers_mut();
+    let at = subscribers.partition_point(|other| other.priority >= s.priority);
+    subscribers.insert(at, s);
+  }
+
+  /// Receives every event
+  fn add_listener(&mut self, l: EventListenerPtr) -> Subscription {
+    self.add_listener_with_priority(l, 0)
+  }
 
-  fn add_listener(&mut self, l: EventListenerPtr) {
-    self.listeners_mut().push_back(l);
+  /// Listeners with a higher priority receive events first
+  fn add_listener_with_priority(&mut self, l: EventListenerPtr, priority: i32) -> Subscription {
+    let (subscriber, ret) = Subscriber::new(priority, SubscriberTarget::Listener(l));
+    self.add_subscriber(subscriber);
+    ret
   }
 
   fn queued_events(&self) -> &VecDeque<Event>;
@@ -40,30 +370,229 @@ pub trait EventBus {
     self.queued_events_mut().push_back(e);
   }
 
+  /// Handle for other threads to queue events
+  fn sender(&self) -> EventSender;
+  fn receiver(&self) -> &Receiver<Event>;
+
+  /// Moves the events posted by other threads to the queue
+  fn receive(&mut self) {
+    let received: Vec<Event> = self.receiver().try_iter().collect();
+    self.queued_events_mut().extend(received);
+  }
+
+  /// Game time driving delayed events
+  fn clock(&self) -> &EngineClock;
+  fn clock_mut(&mut self) -> &mut EngineClock;
+
+  /// Index of the next propagation
+  fn frame(&self) -> u64;
+  fn frame_mut(&mut self) -> &mut u64;
+
+  fn timers(&self) -> &Timers;
+  fn timers_mut(&mut self) -> &mut Timers;
+
+  /// Queues `e` once `delay` of game time went by
+  fn queue_event_after(&mut self, e: Event, delay: Duration) -> TimerId {
+    let due = self.clock().now() + delay;
+    self.timers_mut().schedule(Due::Time(due), None, e)
+  }
+
+  /// Queues `e` every `period` of game time until cancelled, panics if `period` is zero
+  fn queue_event_every(&mut self, e: Event, period: Duration) -> TimerId {
+    assert!(!period.is_zero(), "repeating events need a period");
+    let due = self.clock().now() + period;
+    self.timers_mut().schedule(Due::Time(due), Some(period), e)
+  }
+
+  /// Queues `e` after `frames` propagations, `0` being the next one
+  fn queue_event_in_frames(&mut self, e: Event, frames: u64) -> TimerId {
+    let due = self.frame() + frames;
+    self.timers_mut().schedule(Due::Frame(due), None, e)
+  }
+
+  fn cancel_timer(&mut self, id: TimerId) -> bool {
+    self.timers_mut().cancel(id)
+  }
+
+  fn error_policy(&self) -> ErrorPolicy;
+
+  /// Deepest chain of events emitted by listeners within a frame, deeper events are dropped
+  /// with an error to break cycles
+  fn max_depth(&self) -> usize;
+
+  fn errors_mut(&mut self) -> &mut Vec<Error>;
+
+  /// Errors stored by [`ErrorPolicy::Collect`]
+  fn take_errors(&mut self) -> Vec<Error> {
+    std::mem::take(self.errors_mut())
+  }
+
+  /// Sends queued and received events to the listeners in priority order, until one of them
+  /// handles it, then the events emitted by the listeners for this frame
+  ///
+  /// Listeners run on the calling thread, which owns the bus.
   fn propagate(&mut self) -> crate::Result<()> {
-    while let Some(evt) = self.queued_events_mut().pop_front() {
-      for lstn in self.listeners_mut() {
-        lstn.borrow_mut().on_event(&evt)?;
+    self.receive();
+    let (now, index) = (self.clock().now(), self.frame());
+    *self.frame_mut() += 1;
+    self.subscribers_mut().retain(|s| s.is_active());
+    let (policy, max_depth) = (self.error_policy(), self.max_depth());
+    let fired = self.timers_mut().fire(now, index);
+    let mut frame: VecDeque<(Event, usize, EventOrigin)> = self
+      .queued_events_mut()
+      .drain(..)
+      .map(|e| (e, 0, EventOrigin::Input))
+      .chain(fired.into_iter().map(|e| (e, 0, EventOrigin::Scheduled)))
+      .collect();
+    let mut errors = vec![];
+    let mut failure = None;
+
+    while let Some((evt, depth, origin)) = frame.pop_front() {
+      let mut ctx = EventContext {
+        depth,
+        origin,
+        frame: index,
+        time: now,
+        ..Default::default()
+      };
+      for subscriber in self.subscribers_mut() {
+        // Subscriptions can be dropped by a previous listener
+        if subscriber.is_active() {
+          if let Err(e) = subscriber.on_event(&evt, &mut ctx) {
+            failure = policy.handle(e, &mut errors);
+          }
+        }
+        if ctx.is_handled() || failure.is_some() {
+          break;
+        }
+      }
+
+      for emitted in ctx.emitted {
+        if depth < max_depth {
+          frame.push_back((emitted, depth + 1, EventOrigin::Emitted));
+        } else if failure.is_none() {
+          let e = Error::new(
+            ErrorKind::Event,
+            format!(
+              "{:?} dropped, more than {} nested events",
+              emitted, max_depth
+            ),
+            None,
+            here!(),
+          );
+          failure = policy.handle(e, &mut errors);
+        }
+      }
+      for e in ctx.deferred {
+        self.timers_mut().schedule(Due::Frame(index + 1), None, e);
+      }
+      for (e, delay) in ctx.delayed {
+        self.timers_mut().schedule(Due::Time(now + delay), None, e);
+      }
+      if failure.is_some() {
+        break;
       }
     }
-    Ok(())
+
+    self.errors_mut().extend(errors);
+    for (e, _, origin) in frame {
+      match origin {
+        EventOrigin::Input => self.queue_event(e),
+        _ => {
+          self.timers_mut().schedule(Due::Frame(index + 1), None, e);
+        }
+      }
+    }
+    match failure {
+      Some(e) => Err(e),
+      None => Ok(()),
+    }
+  }
+}
+
+/// Typed helpers for every [`EventBus`], including `dyn EventBus`
+pub trait EventBusExt: EventBus {
+  /// Calls `handler` for each propagated event of type `T`, use `T = Event` for engine events
+  fn subscribe<T: Any>(
+    &mut self,
+    handler: impl FnMut(&T, &mut EventContext) -> crate::Result<()> + 'static,
+  ) -> Subscription {
+    self.subscribe_with_priority(0, handler)
+  }
+
+  /// Handlers with a higher priority receive events first
+  fn subscribe_with_priority<T: Any>(
+    &mut self,
+    priority: i32,
+    mut handler: impl FnMut(&T, &mut EventContext) -> crate::Result<()> + 'static,
+  ) -> Subscription {
+    let handler: EventHandler = Box::new(move |evt, ctx| match evt.downcast_ref::<T>() {
+      Some(value) => handler(value, ctx),
+      None => Ok(()),
+    });
+    let (subscriber, ret) = Subscriber::new(
+      priority,
+      SubscriberTarget::Handler(TypeId::of::<T>(), handler),
+    );
+    self.add_subscriber(subscriber);
+    ret
+  }
+
+  /// Queues `value` for the next propagation
+  fn emit<T: CustomEvent>(&mut self, value: T) {
+    self.queue_event(Event::custom(value));
+  }
+
+  fn emit_after<T: CustomEvent>(&mut self, value: T, delay: Duration) -> TimerId {
+    self.queue_event_after(Event::custom(value), delay)
+  }
+
+  fn emit_every<T: CustomEvent>(&mut self, value: T, period: Duration) -> TimerId {
+    self.queue_event_every(Event::custom(value), period)
   }
 }
 
+impl<B: EventBus + ?Sized> EventBusExt for B {}
+
 pub type EventBusPtr = Rc<RefCell<dyn EventBus>>;
 
 pub struct StdEventBus {
-  listeners: VecDeque<EventListenerPtr>,
+  subscribers: Vec<Subscriber>,
   event_queue: VecDeque<Event>,
+  sender: Sender<Event>,
+  receiver: Receiver<Event>,
+  clock: EngineClock,
+  frame: u64,
+  timers: Timers,
+  error_policy: ErrorPolicy,
+  max_depth: usize,
+  errors: Vec<Error>,
+}
+
+impl StdEventBus {
+  pub fn with_clock(mut self, clock: EngineClock) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+    self.error_policy = policy;
+    self
+  }
+
+  pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+    self.max_depth = max_depth;
+    self
+  }
 }
 
 impl EventBus for StdEventBus {
-  fn listeners(&self) -> &VecDeque<EventListenerPtr> {
-    &self.listeners
+  fn subscribers(&self) -> &Vec<Subscriber> {
+    &self.subscribers
   }
 
-  fn listeners_mut(&mut self) -> &mut VecDeque<EventListenerPtr> {
-    &mut self.listeners
+  fn subscribers_mut(&mut self) -> &mut Vec<Subscriber> {
+    &mut self.subscribers
   }
 
   fn queued_events(&self) -> &VecDeque<Event> {
@@ -73,13 +602,66 @@ impl EventBus for StdEventBus {
   fn queued_events_mut(&mut self) -> &mut VecDeque<Event> {
     &mut self.event_queue
   }
+
+  fn sender(&self) -> EventSender {
+    EventSender(self.sender.clone())
+  }
+
+  fn receiver(&self) -> &Receiver<Event> {
+    &self.receiver
+  }
+
+  fn clock(&self) -> &EngineClock {
+    &self.clock
+  }
+
+  fn clock_mut(&mut self) -> &mut EngineClock {
+    &mut self.clock
+  }
+
+  fn frame(&self) -> u64 {
+    self.frame
+  }
+
+  fn frame_mut(&mut self) -> &mut u64 {
+    &mut self.frame
+  }
+
+  fn timers(&self) -> &Timers {
+    &self.timers
+  }
+
+  fn timers_mut(&mut self) -> &mut Timers {
+    &mut self.timers
+  }
+
+  fn error_policy(&self) -> ErrorPolicy {
+    self.error_policy
+  }
+
+  fn max_depth(&self) -> usize {
+    self.max_depth
+  }
+
+  fn errors_mut(&mut self) -> &mut Vec<Error> {
+    &mut self.errors
+  }
 }
 
 impl Default for StdEventBus {
   fn default() -> Self {
+    let (sender, receiver) = channel();
     Self {
-      listeners: Default::default(),
+      subscribers: Default::default(),
       event_queue: Default::default(),
+      sender,
+      receiver,
+      clock: Default::default(),
+      frame: 0,
+      timers: Default::default(),
+      error_policy: Default::default(),
+      max_depth: 16,
+      errors: vec![],
     }
   }
 }
@@ -90,9 +672,14 @@ mod tests {
     cell::RefCell,
     ops::{Deref, Index},
     rc::Rc,
+    time::Duration,
   };
 
-  use super::{Event, EventBus, EventListener, StdEventBus};
+  use super::{
+    ErrorPolicy, Event, EventBus, EventBusExt, EventContext, EventListener, EventSender,
+    StdEventBus,
+  };
+  use crate::{err, EngineClock, ErrorKind, FakeClock};
 
   #[derive(Debug)]
   struct TestAccu(Vec<Event>);
@@ -114,7 +701,7 @@ mod tests {
   }
 
   impl EventListener for TestAccu {
-    fn on_event(&mut self, value: &Event) -> crate::Result<()> {
+    fn on_event(&mut self, value: &Event, _: &mut EventContext) -> crate::Result<()> {
       self.0.push(value.clone());
       Ok(())
     }
@@ -125,9 +712,315 @@ mod tests {
     let accu = Rc::new(RefCell::new(TestAccu(vec![])));
     let mut bus = StdEventBus::default();
     bus.queue_event(Event::EngineInitStarted);
-    bus.add_listener(accu.clone());
+    let _listener = bus.add_listener(accu.clone());
     bus.propagate().unwrap();
     assert_eq!(accu.borrow().len(), 1);
     assert_eq!(accu.borrow().get(0), Some(&Event::EngineInitStarted));
   }
+
+  #[derive(Debug, PartialEq)]
+  struct PlayerDied {
+    id: u32,
+  }
+
+  #[derive(Debug, PartialEq)]
+  struct DoorOpened;
+
+  #[test]
+  fn detach() {
+    let mut bus = StdEventBus::default();
+    bus.subscribe::<DoorOpened>(|_, _| Ok(())).detach();
+    let dropped = bus.subscribe::<DoorOpened>(|_, _| Ok(()));
+    drop(dropped);
+    bus.emit(DoorOpened);
+    bus.propagate().unwrap();
+    assert_eq!(bus.subscribers().len(), 1);
+    assert!(bus.subscribers()[0].is_active());
+    // The detached subscription did not leak its flag
+    assert_eq!(Rc::strong_count(&bus.subscribers()[0].active), 1);
+  }
+
+  #[test]
+  fn typed() {
+    let died = Rc::new(RefCell::new(vec![]));
+    let engine = Rc::new(RefCell::new(0));
+    let accu = Rc::new(RefCell::new(TestAccu(vec![])));
+    let mut bus = StdEventBus::default();
+    bus.add_listener(accu.clone()).detach();
+    let d = died.clone();
+    bus
+      .subscribe::<PlayerDied>(move |e, _| {
+        d.borrow_mut().push(e.id);
+        Ok(())
+      })
+      .detach();
+    let e = engine.clone();
+    let _engine = bus.subscribe::<Event>(move |evt, _| {
+      assert_eq!(evt, &Event::EngineInitStopped);
+      *e.borrow_mut() += 1;
+      Ok(())
+    });
+
+    bus.emit(PlayerDied { id: 3 });
+    bus.emit(DoorOpened);
+    bus.emit(Event::EngineInitStopped);
+    bus.emit(PlayerDied { id: 4 });
+    bus.propagate().unwrap();
+    assert_eq!(*died.borrow(), [3, 4]);
+    assert_eq!(*engine.borrow(), 1);
+    assert_eq!(accu.borrow().len(), 4);
+    assert_eq!(accu.borrow()[2], Event::EngineInitStopped);
+
+    // Through the pointer type stored by the engine as well
+    let bus: crate::EventBusPtr = Rc::new(RefCell::new(bus));
+    bus.borrow_mut().emit(PlayerDied { id: 5 });
+    bus.borrow_mut().propagate().unwrap();
+    assert_eq!(*died.borrow(), [3, 4, 5]);
+  }
+
+  #[test]
+  fn custom_eq() {
+    assert_eq!(
+      Event::custom(PlayerDied { id: 1 }),
+      Event::custom(PlayerDied { id: 1 })
+    );
+    assert_ne!(
+      Event::custom(PlayerDied { id: 1 }),
+      Event::custom(PlayerDied { id: 2 })
+    );
+    assert_ne!(
+      Event::custom(DoorOpened),
+      Event::custom(PlayerDied { id: 1 })
+    );
+    assert_ne!(Event::custom(DoorOpened), Event::EngineInitStarted);
+    assert_eq!(
+      Event::custom(PlayerDied { id: 7 }).downcast_ref::<PlayerDied>(),
+      Some(&PlayerDied { id: 7 })
+    );
+    assert_eq!(Event::custom(DoorOpened).downcast_ref::<PlayerDied>(), None);
+  }
+
+  #[test]
+  fn threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Event>();
+    assert_send_sync::<EventSender>();
+
+    let died = Rc::new(RefCell::new(vec![]));
+    let mut bus = StdEventBus::default();
+    let d = died.clone();
+    let _died = bus.subscribe::<PlayerDied>(move |e, _| {
+      d.borrow_mut().push(e.id);
+      Ok(())
+    });
+
+    let workers: Vec<_> = (0..4)
+      .map(|id| {
+        let sender = bus.sender();
+        std::thread::spawn(move || sender.emit(PlayerDied { id }).unwrap())
+      })
+      .collect();
+    for worker in workers {
+      worker.join().unwrap();
+    }
+    bus.emit(PlayerDied { id: 10 });
+    bus.propagate().unwrap();
+    died.borrow_mut().sort();
+    assert_eq!(*died.borrow(), [0, 1, 2, 3, 10]);
+
+    let sender = bus.sender();
+    drop(bus);
+    assert!(sender.emit(DoorOpened).is_err());
+  }
+
+  #[derive(Debug, PartialEq)]
+  struct KeyPressed(char);
+
+  #[test]
+  fn priorities() {
+    let log = Rc::new(RefCell::new(vec![]));
+    let mut bus = StdEventBus::default();
+    let subscribe = |bus: &mut StdEventBus, name: &'static str, priority| {
+      let log = log.clone();
+      bus.subscribe_with_priority::<KeyPressed>(priority, move |key, ctx| {
+        log.borrow_mut().push((name, key.0));
+        // The UI swallows escape
+        if name == "ui" && key.0 == '\x1b' {
+          ctx.set_handled();
+        }
+        Ok(())
+      })
+    };
+    let gameplay = subscribe(&mut bus, "gameplay", 0);
+    let ui = subscribe(&mut bus, "ui", 10);
+    let _debug = subscribe(&mut bus, "debug", 0);
+
+    bus.emit(KeyPressed('w'));
+    bus.emit(KeyPressed('\x1b'));
+    bus.propagate().unwrap();
+    assert_eq!(
+      *log.borrow(),
+      [
+        ("ui", 'w'),
+        ("gameplay", 'w'),
+        ("debug", 'w'),
+        ("ui", '\x1b')
+      ]
+    );
+
+    log.borrow_mut().clear();
+    drop(ui);
+    drop(gameplay);
+    bus.emit(KeyPressed('\x1b'));
+    bus.propagate().unwrap();
+    assert_eq!(*log.borrow(), [("debug", '\x1b')]);
+    assert_eq!(bus.subscribers().len(), 1);
+  }
+
+  fn failing_bus(policy: ErrorPolicy, seen: &Rc<RefCell<Vec<u32>>>) -> StdEventBus {
+    let mut bus = StdEventBus::default().with_error_policy(policy);
+    bus
+      .subscribe_with_priority::<PlayerDied>(1, |e, _| match e.id {
+        0 => err!(ErrorKind::Unknown, "boom"),
+        _ => Ok(()),
+      })
+      .detach();
+    let seen = seen.clone();
+    bus
+      .subscribe::<PlayerDied>(move |e, _| {
+        seen.borrow_mut().push(e.id);
+        Ok(())
+      })
+      .detach();
+    for id in [1, 0, 2] {
+      bus.emit(PlayerDied { id });
+    }
+    bus
+  }
+
+  #[test]
+  fn error_policies() {
+    let seen = Rc::new(RefCell::new(vec![]));
+    let mut bus = failing_bus(ErrorPolicy::Stop, &seen);
+    assert!(bus.propagate().is_err());
+    assert_eq!(*seen.borrow(), [1]);
+    assert_eq!(bus.queued_events().len(), 1);
+    bus.propagate().unwrap();
+    assert_eq!(*seen.borrow(), [1, 2]);
+
+    seen.borrow_mut().clear();
+    let mut bus = failing_bus(ErrorPolicy::Collect, &seen);
+    bus.propagate().unwrap();
+    assert_eq!(*seen.borrow(), [1, 0, 2]);
+    let errors = bus.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "boom");
+    assert!(bus.take_errors().is_empty());
+
+    seen.borrow_mut().clear();
+    let mut bus = failing_bus(ErrorPolicy::Log, &seen);
+    bus.propagate().unwrap();
+    assert_eq!(*seen.borrow(), [1, 0, 2]);
+    assert!(bus.take_errors().is_empty());
+  }
+
+  #[test]
+  fn reentrant() {
+    let log = Rc::new(RefCell::new(vec![]));
+    let mut bus = StdEventBus::default().with_error_policy(ErrorPolicy::Collect);
+    let l = log.clone();
+    let _died = bus.subscribe::<PlayerDied>(move |e, ctx| {
+      l.borrow_mut()
+        .push(format!("died {} at {}", e.id, ctx.depth()));
+      match e.id {
+        0 => ctx.emit(DoorOpened),
+        1 => ctx.emit_next_frame(DoorOpened),
+        // Dies again and again
+        _ => ctx.emit(PlayerDied { id: e.id }),
+      }
+      Ok(())
+    });
+    let l = log.clone();
+    let _door = bus.subscribe::<DoorOpened>(move |_, ctx| {
+      l.borrow_mut().push(format!("door at {}", ctx.depth()));
+      Ok(())
+    });
+
+    bus.emit(PlayerDied { id: 0 });
+    bus.emit(PlayerDied { id: 1 });
+    bus.propagate().unwrap();
+    assert_eq!(*log.borrow(), ["died 0 at 0", "died 1 at 0", "door at 1"]);
+    assert_eq!(bus.timers().len(), 1);
+    bus.propagate().unwrap();
+    assert_eq!(log.borrow().last().unwrap(), "door at 0");
+
+    log.borrow_mut().clear();
+    let mut bus = bus.with_max_depth(3);
+    bus.emit(PlayerDied { id: 2 });
+    bus.propagate().unwrap();
+    assert_eq!(log.borrow().len(), 4);
+    let errors = bus.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind(), ErrorKind::Event);
+  }
+
+  #[test]
+  fn timers() {
+    let fake = FakeClock::new();
+    let mut bus = StdEventBus::default().with_clock(EngineClock::from_source(fake.clone()));
+    let log = Rc::new(RefCell::new(vec![]));
+    let l = log.clone();
+    let _died = bus.subscribe::<PlayerDied>(move |e, ctx| {
+      l.borrow_mut().push(e.id);
+      if e.id == 0 {
+        ctx.emit_after(PlayerDied { id: 1 }, Duration::from_secs(1));
+      }
+      Ok(())
+    });
+
+    bus.emit_after(PlayerDied { id: 0 }, Duration::from_secs(2));
+    let every = bus.emit_every(PlayerDied { id: 2 }, Duration::from_secs(3));
+    bus.queue_event_in_frames(Event::custom(PlayerDied { id: 3 }), 1);
+    bus.propagate().unwrap();
+    assert!(log.borrow().is_empty());
+    bus.propagate().unwrap();
+    assert_eq!(*log.borrow(), [3]);
+
+    fake.advance(Duration::from_secs(2));
+    bus.propagate().unwrap();
+    assert_eq!(*log.borrow(), [3, 0]);
+
+    // Paused time does not count
+    bus.clock_mut().pause();
+    fake.advance(Duration::from_secs(5));
+    bus.propagate().unwrap();
+    assert_eq!(log.borrow().len(), 2);
+    bus.clock_mut().resume();
+
+    // Both are due at the same time, the one scheduled first goes first
+    fake.advance(Duration::from_secs(1));
+    bus.propagate().unwrap();
+    assert_eq!(*log.borrow(), [3, 0, 2, 1]);
+    fake.advance(Duration::from_secs(6));
+    bus.propagate().unwrap();
+    assert_eq!(*log.borrow(), [3, 0, 2, 1, 2, 2]);
+
+    assert!(bus.cancel_timer(every));
+    assert!(!bus.cancel_timer(every));
+    assert!(bus.timers().is_empty());
+    assert_eq!(bus.frame(), 6);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serde() {
+    let json = serde_json::to_string(&Event::EngineInitStarted).unwrap();
+    assert_eq!(json, "\"EngineInitStarted\"");
+    assert_eq!(
+      serde_json::from_str::<Event>(&json).unwrap(),
+      Event::EngineInitStarted
+    );
+    assert!(serde_json::to_string(&Event::Custom(std::sync::Arc::new(1))).is_err());
+    assert!(serde_json::from_str::<Event>("\"Custom\"").is_err());
+  }
 }
@@ -2,6 +2,7 @@ use std::{fmt::Display, path::{Path, PathBuf}};
 
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
     path: PathBuf,
     line: usize,
@@ -0,0 +1,620 @@
+use crate::{Matrix, Vector};
+
+/// Half-line starting at `origin`, `direction` is kept normalized
+#[derive(Debug, Clone, Copy)]
+pub struct Ray<T> {
+  origin: Vector<T, 3>,
+  direction: Vector<T, 3>,
+}
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb<T> {
+  min: Vector<T, 3>,
+  max: Vector<T, 3>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sphere<T> {
+  center: Vector<T, 3>,
+  radius: T,
+}
+
+/// Points `p` such as `normal.dot(p) + distance == 0`, `normal` is kept normalized
+#[derive(Debug, Clone, Copy)]
+pub struct Plane<T> {
+  normal: Vector<T, 3>,
+  distance: T,
+}
+
+/// Planes bounding a view volume, normals pointing inside
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum<T> {
+  planes: [Plane<T>; 6],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Triangle<T> {
+  points: [Vector<T, 3>; 3],
+}
+
+impl<T: Copy> Ray<T> {
+  pub fn origin(&self) -> Vector<T, 3> {
+    self.origin
+  }
+
+  pub fn direction(&self) -> Vector<T, 3> {
+    self.direction
+  }
+}
+
+impl<T: Copy> Aabb<T> {
+  pub fn min(&self) -> Vector<T, 3> {
+    self.min
+  }
+
+  pub fn max(&self) -> Vector<T, 3> {
+    self.max
+  }
+}
+
+impl<T: Copy> Sphere<T> {
+  pub fn new(center: Vector<T, 3>, radius: T) -> Self {
+    Self { center, radius }
+  }
+
+  pub fn center(&self) -> Vector<T, 3> {
+    self.center
+  }
+
+  pub fn radius(&self) -> T {
+    self.radius
+  }
+}
+
+impl<T: Copy> Plane<T> {
+  pub fn normal(&self) -> Vector<T, 3> {
+    self.normal
+  }
+
+  pub fn distance(&self) -> T {
+    self.distance
+  }
+}
+
+impl<T: Copy> Frustum<T> {
+  pub fn new(planes: [Plane<T>; 6]) -> Self {
+    Self { planes }
+  }
+
+  /// Left, right, bottom, top, near and far planes
+  pub fn planes(&self) -> &[Plane<T>; 6] {
+    &self.planes
+  }
+}
+
+impl<T: Copy> Triangle<T> {
+  pub fn new(a: Vector<T, 3>, b: Vector<T, 3>, c: Vector<T, 3>) -> Self {
+    Self { points: [a, b, c] }
+  }
+
+  pub fn points(&self) -> &[Vector<T, 3>; 3] {
+    &self.points
+  }
+}
+
+macro_rules! geometry_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Ray<$typ> {
+      pub fn new(origin: Vector<$typ, 3>, direction: Vector<$typ, 3>) -> Self {
+        Self {
+          origin,
+          direction: direction.normalized(),
+        }
+      }
+
+      /// Point at `t` along the ray
+      pub fn at(&self, t: $typ) -> Vector<$typ, 3> {
+        self.origin + self.direction * t
+      }
+
+      pub fn closest_point(&self, p: Vector<$typ, 3>) -> Vector<$typ, 3> {
+        self.at((p - self.origin).dot(&self.direction).max(0.))
+      }
+
+      /// Distance to the entry point, 0 when starting inside the box
+      pub fn intersect_aabb(&self, aabb: &Aabb<$typ>) -> Option<$typ> {
+        let (mut near, mut far) = (0 as $typ, <$typ>::INFINITY);
+        for i in 0..3 {
+          let (o, d) = (self.origin[i], self.direction[i]);
+          if d.abs() < <$typ>::EPSILON {
+            if o < aabb.min[i] || o > aabb.max[i] {
+              return None;
+            }
+            continue;
+          }
+          let (mut t0, mut t1) = ((aabb.min[i] - o) / d, (aabb.max[i] - o) / d);
+          if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+          }
+          near = near.max(t0);
+          far = far.min(t1);
+          if near > far {
+            return None;
+          }
+        }
+        Some(near)
+      }
+
+      /// Distance to the hit point, triangles are hit from both sides
+      pub fn intersect_triangle(&self, triangle: &Triangle<$typ>) -> Option<$typ> {
+        let [a, b, c] = triangle.points;
+        let (e1, e2) = (b - a, c - a);
+        let p = self.direction.cross(&e2);
+        let det = e1.dot(&p);
+        if det.abs() < <$typ>::EPSILON {
+          return None;
+        }
+        let s = self.origin - a;
+        let u = s.dot(&p) / det;
+        if !(0. ..=1.).contains(&u) {
+          return None;
+        }
+        let q = s.cross(&e1);
+        let v = self.direction.dot(&q) / det;
+        if v < 0. || u + v > 1. {
+          return None;
+        }
+        let t = e2.dot(&q) / det;
+        (t >= 0.).then_some(t)
+      }
+
+      /// Distance to the entry point, 0 when starting inside the sphere
+      pub fn intersect_sphere(&self, sphere: &Sphere<$typ>) -> Option<$typ> {
+        let m = self.origin - sphere.center;
+        let b = m.dot(&self.direction);
+        let c = m.sq_magnitude() - sphere.radius * sphere.radius;
+        if c > 0. && b > 0. {
+          return None;
+        }
+        let discr = b * b - c;
+        if discr < 0. {
+          return None;
+        }
+        Some((-b - discr.sqrt()).max(0.))
+      }
+
+      pub fn intersect_plane(&self, plane: &Plane<$typ>) -> Option<$typ> {
+        let denom = plane.normal.dot(&self.direction);
+        if denom.abs() < <$typ>::EPSILON {
+          return None;
+        }
+        let t = -plane.signed_distance(self.origin) / denom;
+        (t >= 0.).then_some(t)
+      }
+    }
+
+    impl Aabb<$typ> {
+      pub fn new(a: Vector<$typ, 3>, b: Vector<$typ, 3>) -> Self {
+        Self {
+          min: a.min(&b),
+          max: a.max(&b),
+        }
+      }
+
+      pub fn from_center(center: Vector<$typ, 3>, half_extents: Vector<$typ, 3>) -> Self {
+        Self::new(center - half_extents, center + half_extents)
+      }
+
+      /// Smallest box containing all the points, `None` when there are none
+      pub fn from_points<I: IntoIterator<Item = Vector<$typ, 3>>>(points: I) -> Option<Self> {
+        let mut it = points.into_iter();
+        let first = it.next()?;
+        Some(it.fold(Self::new(first, first), |aabb, p| aabb.expanded(p)))
+      }
+
+      pub fn center(&self) -> Vector<$typ, 3> {
+        (self.min + self.max) * 0.5
+      }
+
+      pub fn half_extents(&self) -> Vector<$typ, 3> {
+        (self.max - self.min) * 0.5
+      }
+
+      pub fn size(&self) -> Vector<$typ, 3> {
+        self.max - self.min
+      }
+
+      pub fn expanded(&self, p: Vector<$typ, 3>) -> Self {
+        Self {
+          min: self.min.min(&p),
+          max: self.max.max(&p),
+        }
+      }
+
+      pub fn union(&self, rhs: &Self) -> Self {
+        Self {
+          min: self.min.min(&rhs.min),
+          max: self.max.max(&rhs.max),
+        }
+      }
+
+      pub fn contains(&self, p: Vector<$typ, 3>) -> bool {
+        (0..3).all(|i| self.min[i] <= p[i] && p[i] <= self.max[i])
+      }
+
+      pub fn intersects(&self, rhs: &Self) -> bool {
+        (0..3).all(|i| self.min[i] <= rhs.max[i] && rhs.min[i] <= self.max[i])
+      }
+
+      pub fn intersects_sphere(&self, sphere: &Sphere<$typ>) -> bool {
+        sphere.intersects_aabb(self)
+      }
+
+      pub fn closest_point(&self, p: Vector<$typ, 3>) -> Vector<$typ, 3> {
+        p.clamp(&self.min, &self.max)
+      }
+
+      pub fn corners(&self) -> [Vector<$typ, 3>; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+          Vector::<$typ, 3>::new(a[0], a[1], a[2]),
+          Vector::<$typ, 3>::new(b[0], a[1], a[2]),
+          Vector::<$typ, 3>::new(a[0], b[1], a[2]),
+          Vector::<$typ, 3>::new(b[0], b[1], a[2]),
+          Vector::<$typ, 3>::new(a[0], a[1], b[2]),
+          Vector::<$typ, 3>::new(b[0], a[1], b[2]),
+          Vector::<$typ, 3>::new(a[0], b[1], b[2]),
+          Vector::<$typ, 3>::new(b[0], b[1], b[2]),
+        ]
+      }
+    }
+
+    impl Sphere<$typ> {
+      pub fn contains(&self, p: Vector<$typ, 3>) -> bool {
+        self.center.sq_distance(&p) <= self.radius * self.radius
+      }
+
+      pub fn intersects(&self, rhs: &Self) -> bool {
+        let r = self.radius + rhs.radius;
+        self.center.sq_distance(&rhs.center) <= r * r
+      }
+
+      pub fn intersects_aabb(&self, aabb: &Aabb<$typ>) -> bool {
+        self.contains(aabb.closest_point(self.center))
+      }
+
+      pub fn closest_point(&self, p: Vector<$typ, 3>) -> Vector<$typ, 3> {
+        let d = p - self.center;
+        let m = d.magnitude();
+        if m <= self.radius {
+          p
+        } else {
+          self.center + d * (self.radius / m)
+        }
+      }
+    }
+
+    impl Plane<$typ> {
+      pub fn new(normal: Vector<$typ, 3>, distance: $typ) -> Self {
+        let m = normal.magnitude();
+        Self {
+          normal: normal / m,
+          distance: distance / m,
+        }
+      }
+
+      pub fn from_point_normal(p: Vector<$typ, 3>, normal: Vector<$typ, 3>) -> Self {
+        let normal = normal.normalized();
+        Self {
+          normal,
+          distance: -normal.dot(&p),
+        }
+      }
+
+      /// Plane through three points, facing the side they are counter-clockwise from
+      pub fn from_points(a: Vector<$typ, 3>, b: Vector<$typ, 3>, c: Vector<$typ, 3>) -> Self {
+        Self::from_point_normal(a, (b - a).cross(&(c - a)))
+      }
+
+      /// Positive in front of the plane, negative behind
+      pub fn signed_distance(&self, p: Vector<$typ, 3>) -> $typ {
+        self.normal.dot(&p) + self.distance
+      }
+
+      pub fn closest_point(&self, p: Vector<$typ, 3>) -> Vector<$typ, 3> {
+        p - self.normal * self.signed_distance(p)
+      }
+    }
+
+    impl Frustum<$typ> {
+      /// Planes of a projection or view-projection matrix, in OpenGL clip space
+      pub fn from_matrix(m: &Matrix<$typ, 4, 4>) -> Self {
+        let (r0, r1, r2, r3) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        let plane = |v: Vector<$typ, 4>| Plane::<$typ>::new(v.xyz(), v[3]);
+        Self::new([
+          plane(r3 + r0),
+          plane(r3 - r0),
+          plane(r3 + r1),
+          plane(r3 - r1),
+          plane(r3 + r2),
+          plane(r3 - r2),
+        ])
+      }
+
+      pub fn contains(&self, p: Vector<$typ, 3>) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(p) >= 0.)
+      }
+
+      pub fn intersects_sphere(&self, sphere: &Sphere<$typ>) -> bool {
+        self
+          .planes
+          .iter()
+          .all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+      }
+
+      /// Conservative test, some boxes near the corners of the frustum are reported intersecting
+      pub fn intersects_aabb(&self, aabb: &Aabb<$typ>) -> bool {
+        self.planes.iter().all(|plane| {
+          // Corner of the box the furthest along the normal
+          let mut p = aabb.min;
+          for i in 0..3 {
+            if plane.normal[i] >= 0. {
+              p[i] = aabb.max[i];
+            }
+          }
+          plane.signed_distance(p) >= 0.
+        })
+      }
+    }
+
+    impl Triangle<$typ> {
+      /// Normal of the counter-clockwise face
+      pub fn normal(&self) -> Vector<$typ, 3> {
+        let [a, b, c] = self.points;
+        (b - a).cross(&(c - a)).normalized()
+      }
+
+      pub fn area(&self) -> $typ {
+        let [a, b, c] = self.points;
+        (b - a).cross(&(c - a)).magnitude() * 0.5
+      }
+
+      pub fn closest_point(&self, p: Vector<$typ, 3>) -> Vector<$typ, 3> {
+        let [a, b, c] = self.points;
+        let (ab, ac, ap) = (b - a, c - a, p - a);
+        let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+        if d1 <= 0. && d2 <= 0. {
+          return a;
+        }
+        let bp = p - b;
+        let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+        if d3 >= 0. && d4 <= d3 {
+          return b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0. && d1 >= 0. && d3 <= 0. {
+          return a + ab * (d1 / (d1 - d3));
+        }
+        let cp = p - c;
+        let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+        if d6 >= 0. && d5 <= d6 {
+          return c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0. && d2 >= 0. && d6 <= 0. {
+          return a + ac * (d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+          return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+        let denom = 1. / (va + vb + vc);
+        a + ab * (vb * denom) + ac * (vc * denom)
+      }
+    }
+  )*
+  };
+}
+
+geometry_impl!(f32, f64);
+
+macro_rules! decl_geometry_types {
+  ($( ($ty:ty, $ray:ident, $aabb:ident, $sphere:ident, $plane:ident, $frustum:ident, $triangle:ident) ),*) => {
+    $(
+      pub type $ray = Ray<$ty>;
+      pub type $aabb = Aabb<$ty>;
+      pub type $sphere = Sphere<$ty>;
+      pub type $plane = Plane<$ty>;
+      pub type $frustum = Frustum<$ty>;
+      pub type $triangle = Triangle<$ty>;
+    )*
+  };
+}
+
+decl_geometry_types!(
+  (
+    f32,
+    Rayf32,
+    Aabbf32,
+    Spheref32,
+    Planef32,
+    Frustumf32,
+    Trianglef32
+  ),
+  (
+    f64,
+    Rayf64,
+    Aabbf64,
+    Spheref64,
+    Planef64,
+    Frustumf64,
+    Trianglef64
+  )
+);
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::FRAC_PI_2;
+
+  use crate::{Aabbf32, Frustumf32, Mat4f32, Planef32, Rayf32, Spheref32, Trianglef32, Vec3f32};
+
+  fn v(x: f32, y: f32, z: f32) -> Vec3f32 {
+    Vec3f32::new(x, y, z)
+  }
+
+  fn assert_vec_near(a: Vec3f32, b: Vec3f32) {
+    assert!((a - b).magnitude() < 1e-5, "{:?} != {:?}", a, b);
+  }
+
+  fn unit_box() -> Aabbf32 {
+    Aabbf32::new(v(-1., -1., -1.), v(1., 1., 1.))
+  }
+
+  #[test]
+  fn ray_aabb() {
+    let aabb = unit_box();
+    let ray = Rayf32::new(v(-5., 0., 0.), v(2., 0., 0.));
+    assert_eq!(ray.intersect_aabb(&aabb), Some(4.));
+    assert_eq!(
+      Rayf32::new(v(0., 0., 0.), v(0., 1., 0.)).intersect_aabb(&aabb),
+      Some(0.)
+    );
+    assert_eq!(
+      Rayf32::new(v(-5., 2., 0.), v(1., 0., 0.)).intersect_aabb(&aabb),
+      None
+    );
+    assert_eq!(
+      Rayf32::new(v(5., 0., 0.), v(1., 0., 0.)).intersect_aabb(&aabb),
+      None
+    );
+    let t = Rayf32::new(v(-3., -3., 0.), v(1., 1., 0.))
+      .intersect_aabb(&aabb)
+      .unwrap();
+    assert!((t - 2. * 2f32.sqrt()).abs() < 1e-5);
+    assert_vec_near(ray.at(4.), v(-1., 0., 0.));
+  }
+
+  #[test]
+  fn ray_triangle() {
+    let tri = Trianglef32::new(v(0., 0., 0.), v(1., 0., 0.), v(0., 1., 0.));
+    let hit = Rayf32::new(v(0.25, 0.25, 2.), v(0., 0., -1.));
+    assert_eq!(hit.intersect_triangle(&tri), Some(2.));
+    let behind = Rayf32::new(v(0.25, 0.25, -1.), v(0., 0., 1.));
+    assert_eq!(behind.intersect_triangle(&tri), Some(1.));
+    let away = Rayf32::new(v(0.25, 0.25, 2.), v(0., 0., 1.));
+    assert_eq!(away.intersect_triangle(&tri), None);
+    let outside = Rayf32::new(v(0.75, 0.75, 2.), v(0., 0., -1.));
+    assert_eq!(outside.intersect_triangle(&tri), None);
+    let parallel = Rayf32::new(v(0., 0., 1.), v(1., 0., 0.));
+    assert_eq!(parallel.intersect_triangle(&tri), None);
+    assert_vec_near(tri.normal(), v(0., 0., 1.));
+    assert_eq!(tri.area(), 0.5);
+  }
+
+  #[test]
+  fn ray_sphere_plane() {
+    let sphere = Spheref32::new(v(0., 0., -5.), 1.);
+    let ray = Rayf32::new(v(0., 0., 0.), v(0., 0., -1.));
+    assert_eq!(ray.intersect_sphere(&sphere), Some(4.));
+    assert_eq!(
+      Rayf32::new(v(0., 0., 0.), v(0., 0., 1.)).intersect_sphere(&sphere),
+      None
+    );
+    assert_eq!(
+      Rayf32::new(v(0., 0., -5.), v(0., 0., 1.)).intersect_sphere(&sphere),
+      Some(0.)
+    );
+
+    let plane = Planef32::from_point_normal(v(0., 2., 0.), v(0., 1., 0.));
+    assert_eq!(
+      Rayf32::new(v(0., 0., 0.), v(0., 1., 0.)).intersect_plane(&plane),
+      Some(2.)
+    );
+    assert_eq!(
+      Rayf32::new(v(0., 0., 0.), v(0., -1., 0.)).intersect_plane(&plane),
+      None
+    );
+    assert_eq!(
+      Rayf32::new(v(0., 0., 0.), v(1., 0., 0.)).intersect_plane(&plane),
+      None
+    );
+  }
+
+  #[test]
+  fn sphere_aabb() {
+    let aabb = unit_box();
+    assert!(Spheref32::new(v(0., 0., 0.), 0.1).intersects_aabb(&aabb));
+    assert!(Spheref32::new(v(2., 0., 0.), 1.).intersects_aabb(&aabb));
+    assert!(!Spheref32::new(v(2., 2., 0.), 1.).intersects_aabb(&aabb));
+    assert!(aabb.intersects_sphere(&Spheref32::new(v(1.5, 1.5, 0.), 1.)));
+    assert!(Spheref32::new(v(0., 0., 0.), 1.).intersects(&Spheref32::new(v(1.5, 0., 0.), 0.5)));
+    assert!(!Spheref32::new(v(0., 0., 0.), 1.).intersects(&Spheref32::new(v(1.6, 0., 0.), 0.5)));
+  }
+
+  #[test]
+  fn aabb() {
+    let aabb = Aabbf32::from_points([v(1., 0., -2.), v(-1., 3., 0.), v(0., 1., 1.)]).unwrap();
+    assert_eq!(aabb.min(), v(-1., 0., -2.));
+    assert_eq!(aabb.max(), v(1., 3., 1.));
+    assert_eq!(aabb.size(), v(2., 3., 3.));
+    assert!(aabb.contains(v(0., 0., 0.)));
+    assert!(!aabb.contains(v(0., -0.1, 0.)));
+    assert!(aabb.intersects(&unit_box()));
+    assert!(!aabb.intersects(&Aabbf32::from_center(v(5., 0., 0.), v(1., 1., 1.))));
+    assert!(Aabbf32::from_points([]).is_none());
+    assert_eq!(aabb.corners().len(), 8);
+  }
+
+  #[test]
+  fn closest_points() {
+    assert_eq!(unit_box().closest_point(v(3., 0.5, -4.)), v(1., 0.5, -1.));
+    assert_eq!(unit_box().closest_point(v(0.5, 0., 0.)), v(0.5, 0., 0.));
+
+    let sphere = Spheref32::new(v(1., 0., 0.), 2.);
+    assert_vec_near(sphere.closest_point(v(5., 0., 0.)), v(3., 0., 0.));
+    assert_vec_near(sphere.closest_point(v(1., 1., 0.)), v(1., 1., 0.));
+
+    let plane = Planef32::from_points(v(0., 0., 1.), v(1., 0., 1.), v(0., 1., 1.));
+    assert_vec_near(plane.closest_point(v(3., 4., 5.)), v(3., 4., 1.));
+    assert_eq!(plane.signed_distance(v(0., 0., 3.)), 2.);
+
+    let ray = Rayf32::new(v(0., 0., 0.), v(1., 0., 0.));
+    assert_vec_near(ray.closest_point(v(3., 2., 0.)), v(3., 0., 0.));
+    assert_vec_near(ray.closest_point(v(-3., 2., 0.)), v(0., 0., 0.));
+
+    let tri = Trianglef32::new(v(0., 0., 0.), v(2., 0., 0.), v(0., 2., 0.));
+    // Face, edges and vertices regions
+    assert_vec_near(tri.closest_point(v(0.5, 0.5, 3.)), v(0.5, 0.5, 0.));
+    assert_vec_near(tri.closest_point(v(1., -1., 0.)), v(1., 0., 0.));
+    assert_vec_near(tri.closest_point(v(-1., 1., 0.)), v(0., 1., 0.));
+    assert_vec_near(tri.closest_point(v(2., 2., 0.)), v(1., 1., 0.));
+    assert_vec_near(tri.closest_point(v(-1., -1., 1.)), v(0., 0., 0.));
+    assert_vec_near(tri.closest_point(v(3., -1., 0.)), v(2., 0., 0.));
+    assert_vec_near(tri.closest_point(v(-1., 3., 0.)), v(0., 2., 0.));
+  }
+
+  #[test]
+  fn frustum() {
+    let view = Mat4f32::look_at(v(0., 0., 0.), v(0., 0., -1.), v(0., 1., 0.));
+    let proj = Mat4f32::perspective(FRAC_PI_2, 1., 1., 100.);
+    let frustum = Frustumf32::from_matrix(&(proj * view));
+    assert!(frustum.contains(v(0., 0., -10.)));
+    assert!(!frustum.contains(v(0., 0., 10.)));
+    assert!(!frustum.contains(v(0., 0., -0.5)));
+    assert!(!frustum.contains(v(0., 0., -101.)));
+    assert!(!frustum.contains(v(11., 0., -10.)));
+
+    let aabb = |c: Vec3f32| Aabbf32::from_center(c, v(1., 1., 1.));
+    assert!(frustum.intersects_aabb(&aabb(v(0., 0., -10.))));
+    // Straddling the left plane
+    assert!(frustum.intersects_aabb(&aabb(v(-10.5, 0., -10.))));
+    assert!(!frustum.intersects_aabb(&aabb(v(-13., 0., -10.))));
+    assert!(!frustum.intersects_aabb(&aabb(v(0., 0., 5.))));
+    assert!(!frustum.intersects_aabb(&aabb(v(0., 0., -110.))));
+
+    assert!(frustum.intersects_sphere(&Spheref32::new(v(0., 0., 0.), 1.5)));
+    assert!(!frustum.intersects_sphere(&Spheref32::new(v(0., 0., 5.), 1.)));
+  }
+}
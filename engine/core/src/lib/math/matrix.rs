@@ -0,0 +1,663 @@
+use std::ops::{Add, AddAssign, Div, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
+
+use crate::{ApproxEq, Lanes, Unit, Vector, Zero};
+
+/// Matrix of `R` rows and `C` columns, stored column-major like OpenGL expects uniforms
+#[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(
+    transparent,
+    bound(
+      serialize = "T: serde::Serialize",
+      deserialize = "T: serde::Deserialize<'de>"
+    )
+  )
+)]
+pub struct Matrix<T, const R: usize, const C: usize>(
+  #[cfg_attr(feature = "serde", serde(with = "crate::math::serde_array"))] [Vector<T, R>; C],
+);
+
+impl<T, const R: usize, const C: usize> Matrix<T, R, C> {
+  pub const ROWS: usize = R;
+  pub const COLUMNS: usize = C;
+
+  pub fn from_columns(columns: [Vector<T, R>; C]) -> Self {
+    Self(columns)
+  }
+
+  pub fn columns(&self) -> &[Vector<T, R>; C] {
+    &self.0
+  }
+
+  pub fn columns_mut(&mut self) -> &mut [Vector<T, R>; C] {
+    &mut self.0
+  }
+
+  /// Pointer to the first element, followed by the others column after column
+  pub fn as_ptr(&self) -> *const T {
+    self.0.as_ptr() as *const T
+  }
+}
+
+impl<T: Clone, const R: usize, const C: usize> Clone for Matrix<T, R, C> {
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+impl<T: Copy, const R: usize, const C: usize> Copy for Matrix<T, R, C> {}
+
+impl<T: std::fmt::Debug, const R: usize, const C: usize> std::fmt::Debug for Matrix<T, R, C> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("Matrix").field(&self.0).finish()
+  }
+}
+
+impl<T: PartialEq + Copy, const R: usize, const C: usize> PartialEq for Matrix<T, R, C> {
+  fn eq(&self, other: &Self) -> bool {
+    self.0 == other.0
+  }
+}
+
+impl<T: ApproxEq, const R: usize, const C: usize> ApproxEq for Matrix<T, R, C> {
+  type Epsilon = T::Epsilon;
+
+  fn default_epsilon() -> Self::Epsilon {
+    T::default_epsilon()
+  }
+
+  fn approx_eq_eps(&self, other: &Self, abs: Self::Epsilon, rel: Self::Epsilon) -> bool {
+    self
+      .0
+      .iter()
+      .zip(other.0.iter())
+      .all(|(a, b)| a.approx_eq_eps(b, abs, rel))
+  }
+}
+
+impl<T: Copy + Zero, const R: usize, const C: usize> Matrix<T, R, C> {
+  pub fn from_rows(rows: [[T; C]; R]) -> Self {
+    let mut ret = Self::zero();
+    for (r, row) in rows.iter().enumerate() {
+      for (c, value) in row.iter().enumerate() {
+        ret[(r, c)] = *value;
+      }
+    }
+    ret
+  }
+
+  pub fn column(&self, c: usize) -> Vector<T, R> {
+    self.0[c]
+  }
+
+  pub fn row(&self, r: usize) -> Vector<T, C> {
+    let mut ret = Vector::<T, C>::zero();
+    for c in 0..C {
+      ret[c] = self.0[c][r];
+    }
+    ret
+  }
+
+  pub fn transpose(&self) -> Matrix<T, C, R> {
+    let mut ret = Matrix::<T, C, R>::zero();
+    for r in 0..R {
+      for c in 0..C {
+        ret[(c, r)] = self[(r, c)];
+      }
+    }
+    ret
+  }
+}
+
+impl<T: Copy + Zero, const R: usize, const C: usize> Zero for Matrix<T, R, C> {
+  fn zero() -> Self {
+    Self([Vector::zero(); C])
+  }
+}
+
+impl<T: Copy + Zero + Unit, const N: usize> Matrix<T, N, N> {
+  pub fn identity() -> Self {
+    let mut ret = Self::zero();
+    for i in 0..N {
+      ret[(i, i)] = T::unit();
+    }
+    ret
+  }
+}
+
+impl<T: Copy + Zero + Unit, const N: usize> Default for Matrix<T, N, N> {
+  fn default() -> Self {
+    Self::identity()
+  }
+}
+
+/// Element at `(row, column)`
+impl<T, const R: usize, const C: usize> Index<(usize, usize)> for Matrix<T, R, C> {
+  type Output = T;
+
+  fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+    &self.0[c][r]
+  }
+}
+
+impl<T, const R: usize, const C: usize> IndexMut<(usize, usize)> for Matrix<T, R, C> {
+  fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+    &mut self.0[c][r]
+  }
+}
+
+impl<T: AddAssign + Lanes, const R: usize, const C: usize> AddAssign for Matrix<T, R, C> {
+  fn add_assign(&mut self, rhs: Self) {
+    for c in 0..C {
+      self.0[c] += rhs.0[c];
+    }
+  }
+}
+
+impl<T: AddAssign + Lanes, const R: usize, const C: usize> Add for Matrix<T, R, C> {
+  type Output = Self;
+
+  fn add(mut self, rhs: Self) -> Self::Output {
+    self += rhs;
+    self
+  }
+}
+
+impl<T: SubAssign + Lanes, const R: usize, const C: usize> SubAssign for Matrix<T, R, C> {
+  fn sub_assign(&mut self, rhs: Self) {
+    for c in 0..C {
+      self.0[c] -= rhs.0[c];
+    }
+  }
+}
+
+impl<T: SubAssign + Lanes, const R: usize, const C: usize> Sub for Matrix<T, R, C> {
+  type Output = Self;
+
+  fn sub(mut self, rhs: Self) -> Self::Output {
+    self -= rhs;
+    self
+  }
+}
+
+impl<T: MulAssign + Lanes, const R: usize, const C: usize> MulAssign<T> for Matrix<T, R, C> {
+  fn mul_assign(&mut self, rhs: T) {
+    for c in 0..C {
+      self.0[c] *= rhs;
+    }
+  }
+}
+
+impl<T: MulAssign + Lanes, const R: usize, const C: usize> Mul<T> for Matrix<T, R, C> {
+  type Output = Self;
+
+  fn mul(mut self, rhs: T) -> Self::Output {
+    self *= rhs;
+    self
+  }
+}
+
+impl<T: Lanes + Zero + AddAssign + Mul<T, Output = T>, const R: usize, const C: usize>
+  Mul<Vector<T, C>> for Matrix<T, R, C>
+{
+  type Output = Vector<T, R>;
+
+  fn mul(self, rhs: Vector<T, C>) -> Self::Output {
+    let columns = std::array::from_fn(|c| self.0[c].parts());
+    Vector::from_parts(T::mul_columns_lanes(columns, rhs.parts()))
+  }
+}
+
+impl<
+    T: Lanes + Zero + AddAssign + Mul<T, Output = T>,
+    const R: usize,
+    const C: usize,
+    const K: usize,
+  > Mul<Matrix<T, C, K>> for Matrix<T, R, C>
+{
+  type Output = Matrix<T, R, K>;
+
+  fn mul(self, rhs: Matrix<T, C, K>) -> Self::Output {
+    let mut ret = Matrix::<T, R, K>::zero();
+    for k in 0..K {
+      ret.0[k] = self * rhs.0[k];
+    }
+    ret
+  }
+}
+
+impl<T: Lanes + Zero + AddAssign + Mul<T, Output = T>, const N: usize> MulAssign
+  for Matrix<T, N, N>
+{
+  fn mul_assign(&mut self, rhs: Self) {
+    *self = *self * rhs;
+  }
+}
+
+impl<
+    T: Copy
+      + Zero
+      + Unit
+      + PartialEq
+      + Add<T, Output = T>
+      + Sub<T, Output = T>
+      + Mul<T, Output = T>
+      + Div<T, Output = T>,
+  > Matrix<T, 2, 2>
+{
+  pub fn determinant(&self) -> T {
+    self[(0, 0)] * self[(1, 1)] - self[(0, 1)] * self[(1, 0)]
+  }
+
+  /// Inverse matrix, `None` when the matrix is singular
+  pub fn inverse(&self) -> Option<Self> {
+    let det = self.determinant();
+    if det == T::zero() {
+      return None;
+    }
+    let a = |r, c| self[(r, c)] / det;
+    Some(Self::from_rows([
+      [a(1, 1), T::zero() - a(0, 1)],
+      [T::zero() - a(1, 0), a(0, 0)],
+    ]))
+  }
+}
+
+impl<
+    T: Copy
+      + Zero
+      + Unit
+      + PartialEq
+      + Add<T, Output = T>
+      + Sub<T, Output = T>
+      + Mul<T, Output = T>
+      + Div<T, Output = T>,
+  > Matrix<T, 3, 3>
+{
+  pub fn determinant(&self) -> T {
+    let a = |r, c| self[(r, c)];
+    a(0, 0) * (a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1))
+      - a(0, 1) * (a(1, 0) * a(2, 2) - a(1, 2) * a(2, 0))
+      + a(0, 2) * (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0))
+  }
+
+  /// Inverse matrix, `None` when the matrix is singular
+  pub fn inverse(&self) -> Option<Self> {
+    let det = self.determinant();
+    if det == T::zero() {
+      return None;
+    }
+    let a = |r, c| self[(r, c)];
+    Some(Self::from_rows([
+      [
+        (a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1)) / det,
+        (a(0, 2) * a(2, 1) - a(0, 1) * a(2, 2)) / det,
+        (a(0, 1) * a(1, 2) - a(0, 2) * a(1, 1)) / det,
+      ],
+      [
+        (a(1, 2) * a(2, 0) - a(1, 0) * a(2, 2)) / det,
+        (a(0, 0) * a(2, 2) - a(0, 2) * a(2, 0)) / det,
+        (a(0, 2) * a(1, 0) - a(0, 0) * a(1, 2)) / det,
+      ],
+      [
+        (a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0)) / det,
+        (a(0, 1) * a(2, 0) - a(0, 0) * a(2, 1)) / det,
+        (a(0, 0) * a(1, 1) - a(0, 1) * a(1, 0)) / det,
+      ],
+    ]))
+  }
+}
+
+impl<
+    T: Copy
+      + Zero
+      + Unit
+      + PartialEq
+      + Add<T, Output = T>
+      + Sub<T, Output = T>
+      + Mul<T, Output = T>
+      + Div<T, Output = T>,
+  > Matrix<T, 4, 4>
+{
+  /// 2x2 sub-determinants of the two upper rows (`s`) and of the two lower rows (`c`)
+  fn sub_determinants(&self) -> ([T; 6], [T; 6]) {
+    let a = |r, c| self[(r, c)];
+    (
+      [
+        a(0, 0) * a(1, 1) - a(1, 0) * a(0, 1),
+        a(0, 0) * a(1, 2) - a(1, 0) * a(0, 2),
+        a(0, 0) * a(1, 3) - a(1, 0) * a(0, 3),
+        a(0, 1) * a(1, 2) - a(1, 1) * a(0, 2),
+        a(0, 1) * a(1, 3) - a(1, 1) * a(0, 3),
+        a(0, 2) * a(1, 3) - a(1, 2) * a(0, 3),
+      ],
+      [
+        a(2, 0) * a(3, 1) - a(3, 0) * a(2, 1),
+        a(2, 0) * a(3, 2) - a(3, 0) * a(2, 2),
+        a(2, 0) * a(3, 3) - a(3, 0) * a(2, 3),
+        a(2, 1) * a(3, 2) - a(3, 1) * a(2, 2),
+        a(2, 1) * a(3, 3) - a(3, 1) * a(2, 3),
+        a(2, 2) * a(3, 3) - a(3, 2) * a(2, 3),
+      ],
+    )
+  }
+
+  pub fn determinant(&self) -> T {
+    let (s, c) = self.sub_determinants();
+    s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+  }
+
+  /// Inverse matrix, `None` when the matrix is singular
+  pub fn inverse(&self) -> Option<Self> {
+    let (s, c) = self.sub_determinants();
+    let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+    if det == T::zero() {
+      return None;
+    }
+    let a = |r, c| self[(r, c)];
+    Some(Self::from_rows([
+      [
+        (a(1, 1) * c[5] - a(1, 2) * c[4] + a(1, 3) * c[3]) / det,
+        (a(0, 2) * c[4] - a(0, 1) * c[5] - a(0, 3) * c[3]) / det,
+        (a(3, 1) * s[5] - a(3, 2) * s[4] + a(3, 3) * s[3]) / det,
+        (a(2, 2) * s[4] - a(2, 1) * s[5] - a(2, 3) * s[3]) / det,
+      ],
+      [
+        (a(1, 2) * c[2] - a(1, 0) * c[5] - a(1, 3) * c[1]) / det,
+        (a(0, 0) * c[5] - a(0, 2) * c[2] + a(0, 3) * c[1]) / det,
+        (a(3, 2) * s[2] - a(3, 0) * s[5] - a(3, 3) * s[1]) / det,
+        (a(2, 0) * s[5] - a(2, 2) * s[2] + a(2, 3) * s[1]) / det,
+      ],
+      [
+        (a(1, 0) * c[4] - a(1, 1) * c[2] + a(1, 3) * c[0]) / det,
+        (a(0, 1) * c[2] - a(0, 0) * c[4] - a(0, 3) * c[0]) / det,
+        (a(3, 0) * s[4] - a(3, 1) * s[2] + a(3, 3) * s[0]) / det,
+        (a(2, 1) * s[2] - a(2, 0) * s[4] - a(2, 3) * s[0]) / det,
+      ],
+      [
+        (a(1, 1) * c[1] - a(1, 0) * c[3] - a(1, 2) * c[0]) / det,
+        (a(0, 0) * c[3] - a(0, 1) * c[1] + a(0, 2) * c[0]) / det,
+        (a(3, 1) * s[1] - a(3, 0) * s[3] - a(3, 2) * s[0]) / det,
+        (a(2, 0) * s[3] - a(2, 1) * s[1] + a(2, 2) * s[0]) / det,
+      ],
+    ]))
+  }
+}
+
+macro_rules! transform_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Matrix<$typ, 2, 2> {
+      /// Counter-clockwise rotation, in radians
+      pub fn rotation(angle: $typ) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::from_rows([[c, -s], [s, c]])
+      }
+
+      pub fn scale(v: Vector<$typ, 2>) -> Self {
+        Self::from_rows([[v[0], 0.], [0., v[1]]])
+      }
+    }
+
+    impl Matrix<$typ, 3, 3> {
+      /// Rotation of `angle` radians around `axis`
+      pub fn rotation(axis: Vector<$typ, 3>, angle: $typ) -> Self {
+        let axis = axis.normalized();
+        let (x, y, z) = (axis[0], axis[1], axis[2]);
+        let (s, c) = angle.sin_cos();
+        let t = 1. - c;
+        Self::from_rows([
+          [t * x * x + c, t * x * y - s * z, t * x * z + s * y],
+          [t * x * y + s * z, t * y * y + c, t * y * z - s * x],
+          [t * x * z - s * y, t * y * z + s * x, t * z * z + c],
+        ])
+      }
+
+      pub fn rotation_x(angle: $typ) -> Self {
+        Self::rotation(Vector::<$typ, 3>::unit_x(), angle)
+      }
+
+      pub fn rotation_y(angle: $typ) -> Self {
+        Self::rotation(Vector::<$typ, 3>::unit_y(), angle)
+      }
+
+      pub fn rotation_z(angle: $typ) -> Self {
+        Self::rotation(Vector::<$typ, 3>::unit_z(), angle)
+      }
+
+      pub fn scale(v: Vector<$typ, 3>) -> Self {
+        Self::from_rows([[v[0], 0., 0.], [0., v[1], 0.], [0., 0., v[2]]])
+      }
+    }
+
+    impl Matrix<$typ, 4, 4> {
+      /// Embed a 3x3 linear transform
+      pub fn from_mat3(m: Matrix<$typ, 3, 3>) -> Self {
+        let mut ret = Self::identity();
+        for c in 0..3 {
+          for r in 0..3 {
+            ret[(r, c)] = m[(r, c)];
+          }
+        }
+        ret
+      }
+
+      /// Upper-left 3x3 linear part
+      pub fn to_mat3(&self) -> Matrix<$typ, 3, 3> {
+        Matrix::from_columns([self.0[0].xyz(), self.0[1].xyz(), self.0[2].xyz()])
+      }
+
+      pub fn translation(v: Vector<$typ, 3>) -> Self {
+        let mut ret = Self::identity();
+        ret.0[3] = v.extend(1.);
+        ret
+      }
+
+      pub fn rotation(axis: Vector<$typ, 3>, angle: $typ) -> Self {
+        Self::from_mat3(Matrix::<$typ, 3, 3>::rotation(axis, angle))
+      }
+
+      pub fn rotation_x(angle: $typ) -> Self {
+        Self::from_mat3(Matrix::<$typ, 3, 3>::rotation_x(angle))
+      }
+
+      pub fn rotation_y(angle: $typ) -> Self {
+        Self::from_mat3(Matrix::<$typ, 3, 3>::rotation_y(angle))
+      }
+
+      pub fn rotation_z(angle: $typ) -> Self {
+        Self::from_mat3(Matrix::<$typ, 3, 3>::rotation_z(angle))
+      }
+
+      pub fn scale(v: Vector<$typ, 3>) -> Self {
+        Self::from_mat3(Matrix::<$typ, 3, 3>::scale(v))
+      }
+
+      /// Right-handed view matrix of a camera at `eye` looking at `target`
+      pub fn look_at(eye: Vector<$typ, 3>, target: Vector<$typ, 3>, up: Vector<$typ, 3>) -> Self {
+        let f = (target - eye).normalized();
+        let s = f.cross(&up).normalized();
+        let u = s.cross(&f);
+        Self::from_rows([
+          [s[0], s[1], s[2], -s.dot(&eye)],
+          [u[0], u[1], u[2], -u.dot(&eye)],
+          [-f[0], -f[1], -f[2], f.dot(&eye)],
+          [0., 0., 0., 1.],
+        ])
+      }
+
+      /// Right-handed perspective projection to OpenGL clip space, `fov_y` in radians
+      pub fn perspective(fov_y: $typ, aspect: $typ, near: $typ, far: $typ) -> Self {
+        let f = 1. / (fov_y / 2.).tan();
+        Self::from_rows([
+          [f / aspect, 0., 0., 0.],
+          [0., f, 0., 0.],
+          [0., 0., (far + near) / (near - far), 2. * far * near / (near - far)],
+          [0., 0., -1., 0.],
+        ])
+      }
+
+      /// Right-handed orthographic projection to OpenGL clip space
+      pub fn orthographic(
+        left: $typ,
+        right: $typ,
+        bottom: $typ,
+        top: $typ,
+        near: $typ,
+        far: $typ,
+      ) -> Self {
+        Self::from_rows([
+          [2. / (right - left), 0., 0., -(right + left) / (right - left)],
+          [0., 2. / (top - bottom), 0., -(top + bottom) / (top - bottom)],
+          [0., 0., -2. / (far - near), -(far + near) / (far - near)],
+          [0., 0., 0., 1.],
+        ])
+      }
+    }
+  )*
+  };
+}
+
+transform_impl!(f32, f64);
+
+macro_rules! decl_mat_types {
+  ($( ($ty:ty, $rows:expr, $cols:expr, $name: ident) ),*) => {
+    $(
+      pub type $name = Matrix<$ty, $rows, $cols>;
+    )*
+  };
+}
+
+decl_mat_types!(
+  (f32, 2, 2, Mat2f32),
+  (f32, 3, 3, Mat3f32),
+  (f32, 4, 4, Mat4f32),
+  (f64, 2, 2, Mat2f64),
+  (f64, 3, 3, Mat3f64),
+  (f64, 4, 4, Mat4f64),
+  (i32, 2, 2, Mat2i32),
+  (i32, 3, 3, Mat3i32),
+  (i32, 4, 4, Mat4i32)
+);
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::FRAC_PI_2;
+
+  use crate::{Mat2f32, Mat3f32, Mat3i32, Mat4f32, Matrix, Vec3f32, Vec4f32, Zero};
+
+  fn assert_near<const R: usize, const C: usize>(a: Matrix<f32, R, C>, b: Matrix<f32, R, C>) {
+    for r in 0..R {
+      for c in 0..C {
+        assert!(
+          (a[(r, c)] - b[(r, c)]).abs() < 1e-5,
+          "{:?} != {:?} at ({}, {})",
+          a,
+          b,
+          r,
+          c
+        );
+      }
+    }
+  }
+
+  fn assert_vec_near(a: Vec3f32, b: Vec3f32) {
+    assert!((a - b).magnitude() < 1e-5, "{:?} != {:?}", a, b);
+  }
+
+  #[test]
+  fn layout() {
+    let m = Mat2f32::from_rows([[1., 2.], [3., 4.]]);
+    let data = unsafe { std::slice::from_raw_parts(m.as_ptr(), 4) };
+    assert_eq!(data, &[1., 3., 2., 4.]);
+    assert_eq!(std::mem::size_of::<Mat4f32>(), 16 * 4);
+    assert_eq!(m.row(1), crate::Vec2f32::new(3., 4.));
+    assert_eq!(m.column(1), crate::Vec2f32::new(2., 4.));
+  }
+
+  #[test]
+  fn mul() {
+    let a = Matrix::<i32, 2, 3>::from_rows([[1, 2, 3], [4, 5, 6]]);
+    let b = Matrix::<i32, 3, 2>::from_rows([[7, 8], [9, 10], [11, 12]]);
+    assert_eq!(
+      a * b,
+      Matrix::<i32, 2, 2>::from_rows([[58, 64], [139, 154]])
+    );
+    assert_eq!(
+      a.transpose(),
+      Matrix::<i32, 3, 2>::from_rows([[1, 4], [2, 5], [3, 6]])
+    );
+    assert_eq!(
+      a * crate::Vec3i32::new(1, 0, -1),
+      crate::Vec2i32::new(-2, -2)
+    );
+    assert_eq!(
+      Mat3i32::identity() * Mat3i32::identity(),
+      Mat3i32::identity()
+    );
+  }
+
+  #[test]
+  fn inverse() {
+    let m = Mat3f32::from_rows([[2., 0., 1.], [1., 3., 2.], [1., 1., 2.]]);
+    assert_eq!(m.determinant(), 6.);
+    assert_near(m * m.inverse().unwrap(), Mat3f32::identity());
+
+    let m = Mat4f32::translation(Vec3f32::new(1., 2., 3.))
+      * Mat4f32::rotation(Vec3f32::new(1., 1., 0.), 0.7)
+      * Mat4f32::scale(Vec3f32::new(2., 3., 4.));
+    assert!((m.determinant() - 24.).abs() < 1e-4);
+    assert_near(m * m.inverse().unwrap(), Mat4f32::identity());
+    assert_near(m.inverse().unwrap() * m, Mat4f32::identity());
+
+    let m = Mat2f32::from_rows([[1., 2.], [3., 4.]]);
+    assert_near(m * m.inverse().unwrap(), Mat2f32::identity());
+    assert!(Mat2f32::from_rows([[1., 2.], [2., 4.]]).inverse().is_none());
+  }
+
+  #[test]
+  fn transforms() {
+    let p = Vec4f32::new(1., 0., 0., 1.);
+    assert_vec_near(
+      (Mat4f32::rotation_z(FRAC_PI_2) * p).xyz(),
+      Vec3f32::new(0., 1., 0.),
+    );
+    assert_vec_near(
+      (Mat4f32::translation(Vec3f32::new(1., 2., 3.)) * p).xyz(),
+      Vec3f32::new(2., 2., 3.),
+    );
+    assert_vec_near(
+      (Mat4f32::scale(Vec3f32::new(2., 3., 4.)) * p).xyz(),
+      Vec3f32::new(2., 0., 0.),
+    );
+    assert_vec_near(
+      Mat3f32::rotation_x(FRAC_PI_2) * Vec3f32::new(0., 1., 0.),
+      Vec3f32::new(0., 0., 1.),
+    );
+  }
+
+  #[test]
+  fn camera() {
+    let eye = Vec3f32::new(0., 0., 5.);
+    let view = Mat4f32::look_at(eye, Vec3f32::zero(), Vec3f32::unit_y());
+    // The target ends up in front of the camera, down the negative z axis
+    assert_vec_near(
+      (view * Vec4f32::new(0., 0., 0., 1.)).xyz(),
+      Vec3f32::new(0., 0., -5.),
+    );
+
+    let proj = Mat4f32::perspective(FRAC_PI_2, 1., 1., 10.);
+    let near = proj * Vec4f32::new(0., 0., -1., 1.);
+    let far = proj * Vec4f32::new(0., 0., -10., 1.);
+    assert!((near.z() / near.w() + 1.).abs() < 1e-5);
+    assert!((far.z() / far.w() - 1.).abs() < 1e-5);
+
+    let ortho = Mat4f32::orthographic(-2., 2., -1., 1., 0., 10.);
+    assert_vec_near(
+      (ortho * Vec4f32::new(2., 1., -10., 1.)).xyz(),
+      Vec3f32::new(1., 1., 1.),
+    );
+  }
+}
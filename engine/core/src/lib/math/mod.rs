@@ -1,4 +1,16 @@
+pub mod geometry;
+pub mod matrix;
 pub mod num_traits;
+pub mod quaternion;
+#[cfg(feature = "serde")]
+mod serde_array;
+pub mod simd;
+pub mod transform;
 pub mod vector;
+pub use geometry::*;
+pub use matrix::*;
 pub use num_traits::*;
+pub use quaternion::*;
+pub use simd::*;
+pub use transform::*;
 pub use vector::*;
@@ -1,3 +1,7 @@
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::Lanes;
+
 pub trait Zero {
   fn zero() -> Self;
 }
@@ -55,11 +59,228 @@ macro_rules! sqrt_impl {
     $(
     impl Sqrt for $typ {
       fn sqrt(&self) -> Self {
-        f64::sqrt(*self as f64) as $typ
+        <$typ>::sqrt(*self)
+      }
+    }
+  )*
+  };
+}
+
+sqrt_impl!(f32, f64);
+
+/// Integer square root, rounded down. Panics on negative values
+macro_rules! isqrt_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Sqrt for $typ {
+      fn sqrt(&self) -> Self {
+        <$typ>::isqrt(*self)
+      }
+    }
+  )*
+  };
+}
+
+isqrt_impl!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+pub trait Abs {
+  fn abs(&self) -> Self;
+}
+
+macro_rules! abs_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Abs for $typ {
+      fn abs(&self) -> Self {
+        <$typ>::abs(*self)
       }
     }
   )*
   };
 }
 
-sqrt_impl!(f32, f64, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+abs_impl!(f32, f64, i8, i16, i32, i64, i128, isize);
+
+macro_rules! unsigned_abs_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Abs for $typ {
+      fn abs(&self) -> Self {
+        *self
+      }
+    }
+  )*
+  };
+}
+
+unsigned_abs_impl!(u8, u16, u32, u64, u128, usize);
+
+/// Floating point numbers, angles are in radians
+pub trait Float:
+  Lanes
+  + PartialOrd
+  + Zero
+  + Unit
+  + Sqrt
+  + Abs
+  + Add<Self, Output = Self>
+  + Sub<Self, Output = Self>
+  + Mul<Self, Output = Self>
+  + Div<Self, Output = Self>
+  + Neg<Output = Self>
+  + AddAssign
+  + SubAssign
+  + MulAssign
+  + DivAssign
+{
+  /// Difference between 1 and the next representable number
+  fn epsilon() -> Self;
+  fn sin(&self) -> Self;
+  fn cos(&self) -> Self;
+  fn tan(&self) -> Self;
+  fn asin(&self) -> Self;
+  fn acos(&self) -> Self;
+  fn atan(&self) -> Self;
+  fn atan2(&self, x: Self) -> Self;
+  fn floor(&self) -> Self;
+  fn ceil(&self) -> Self;
+  fn round(&self) -> Self;
+  fn powf(&self, n: Self) -> Self;
+  fn is_nan(&self) -> bool;
+}
+
+macro_rules! float_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Float for $typ {
+      fn epsilon() -> Self {
+        <$typ>::EPSILON
+      }
+
+      fn sin(&self) -> Self {
+        <$typ>::sin(*self)
+      }
+
+      fn cos(&self) -> Self {
+        <$typ>::cos(*self)
+      }
+
+      fn tan(&self) -> Self {
+        <$typ>::tan(*self)
+      }
+
+      fn asin(&self) -> Self {
+        <$typ>::asin(*self)
+      }
+
+      fn acos(&self) -> Self {
+        <$typ>::acos(*self)
+      }
+
+      fn atan(&self) -> Self {
+        <$typ>::atan(*self)
+      }
+
+      fn atan2(&self, x: Self) -> Self {
+        <$typ>::atan2(*self, x)
+      }
+
+      fn floor(&self) -> Self {
+        <$typ>::floor(*self)
+      }
+
+      fn ceil(&self) -> Self {
+        <$typ>::ceil(*self)
+      }
+
+      fn round(&self) -> Self {
+        <$typ>::round(*self)
+      }
+
+      fn powf(&self, n: Self) -> Self {
+        <$typ>::powf(*self, n)
+      }
+
+      fn is_nan(&self) -> bool {
+        <$typ>::is_nan(*self)
+      }
+    }
+  )*
+  };
+}
+
+float_impl!(f32, f64);
+
+/// Equality tolerating rounding errors
+pub trait ApproxEq {
+  type Epsilon: Copy;
+
+  fn default_epsilon() -> Self::Epsilon;
+
+  /// Equal when the difference is at most `abs`, or at most `rel` times the largest magnitude
+  fn approx_eq_eps(&self, other: &Self, abs: Self::Epsilon, rel: Self::Epsilon) -> bool;
+
+  fn approx_eq(&self, other: &Self) -> bool {
+    let eps = Self::default_epsilon();
+    self.approx_eq_eps(other, eps, eps)
+  }
+}
+
+macro_rules! approx_eq_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl ApproxEq for $typ {
+      type Epsilon = $typ;
+
+      fn default_epsilon() -> Self::Epsilon {
+        <$typ>::EPSILON * 4.
+      }
+
+      fn approx_eq_eps(&self, other: &Self, abs: Self::Epsilon, rel: Self::Epsilon) -> bool {
+        if self == other {
+          return true;
+        }
+        let diff = (self - other).abs();
+        diff <= abs || diff <= rel * self.abs().max(other.abs())
+      }
+    }
+  )*
+  };
+}
+
+approx_eq_impl!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+  use crate::{ApproxEq, Float, Sqrt};
+
+  #[test]
+  fn sqrt() {
+    assert_eq!(15u8.sqrt(), 3);
+    assert_eq!(16i32.sqrt(), 4);
+    assert_eq!(u128::MAX.sqrt(), u64::MAX as u128);
+    assert_eq!(Sqrt::sqrt(&2f64), std::f64::consts::SQRT_2);
+  }
+
+  #[test]
+  fn float() {
+    fn hypot<T: Float>(a: T, b: T) -> T {
+      (a * a + b * b).sqrt()
+    }
+    assert_eq!(hypot(3f32, 4.), 5.);
+    assert_eq!(Float::atan2(&1f64, 1.), std::f64::consts::FRAC_PI_4);
+    assert!(Float::is_nan(&f32::NAN));
+    assert_eq!(<f32 as Float>::epsilon(), f32::EPSILON);
+  }
+
+  #[test]
+  fn approx_eq() {
+    assert!((0.1f64 + 0.2).approx_eq(&0.3));
+    assert!(!(0.1f32).approx_eq(&0.1001));
+    assert!(1e10f32.approx_eq_eps(&(1e10 + 1e3), 0., 1e-6));
+    assert!(!1e10f32.approx_eq_eps(&(1e10 + 1e6), 0., 1e-6));
+    assert!(1e-9f64.approx_eq_eps(&0., 1e-8, 0.));
+    assert!(!f32::NAN.approx_eq(&f32::NAN));
+    assert!(f32::INFINITY.approx_eq(&f32::INFINITY));
+  }
+}
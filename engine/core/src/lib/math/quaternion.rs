@@ -0,0 +1,323 @@
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub};
+
+use crate::{ApproxEq, Lanes, Matrix, Unit, Vector, Zero};
+
+/// Rotation quaternion, stored as `x, y, z, w` with `w` the real part
+#[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Quaternion<T>(Vector<T, 4>);
+
+impl<T: Copy> Quaternion<T> {
+  pub fn new(x: T, y: T, z: T, w: T) -> Self {
+    Self(Vector::<T, 4>::new(x, y, z, w))
+  }
+
+  pub fn from_vector(v: Vector<T, 3>, w: T) -> Self {
+    Self(v.extend(w))
+  }
+
+  pub fn x(&self) -> T {
+    self.0[0]
+  }
+
+  pub fn y(&self) -> T {
+    self.0[1]
+  }
+
+  pub fn z(&self) -> T {
+    self.0[2]
+  }
+
+  pub fn w(&self) -> T {
+    self.0[3]
+  }
+
+  /// Imaginary part
+  pub fn vector(&self) -> Vector<T, 3> {
+    self.0.xyz()
+  }
+
+  pub fn as_vector(&self) -> &Vector<T, 4> {
+    &self.0
+  }
+}
+
+impl<T: Copy + Zero + Unit> Quaternion<T> {
+  pub fn identity() -> Self {
+    Self(Vector::unit_w())
+  }
+}
+
+impl<T: Copy + Zero + Unit> Default for Quaternion<T> {
+  fn default() -> Self {
+    Self::identity()
+  }
+}
+
+impl<T: Clone> Clone for Quaternion<T> {
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+impl<T: Copy> Copy for Quaternion<T> {}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Quaternion<T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("Quaternion").field(&self.0).finish()
+  }
+}
+
+impl<T: PartialEq + Copy> PartialEq for Quaternion<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.0 == other.0
+  }
+}
+
+impl<T: ApproxEq> ApproxEq for Quaternion<T> {
+  type Epsilon = T::Epsilon;
+
+  fn default_epsilon() -> Self::Epsilon {
+    T::default_epsilon()
+  }
+
+  fn approx_eq_eps(&self, other: &Self, abs: Self::Epsilon, rel: Self::Epsilon) -> bool {
+    self.0.approx_eq_eps(&other.0, abs, rel)
+  }
+}
+
+impl<T: Copy + Neg<Output = T>> Quaternion<T> {
+  pub fn conjugate(&self) -> Self {
+    Self::new(-self.x(), -self.y(), -self.z(), self.w())
+  }
+}
+
+impl<T: Lanes + Zero + AddAssign + Mul<T, Output = T>> Quaternion<T> {
+  pub fn dot(&self, rhs: &Self) -> T {
+    self.0.dot(&rhs.0)
+  }
+
+  pub fn sq_magnitude(&self) -> T {
+    self.0.sq_magnitude()
+  }
+}
+
+impl<
+    T: Lanes + Zero + Unit + AddAssign + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>,
+  > Quaternion<T>
+{
+  /// Rotate `v`, the quaternion has to be normalized
+  pub fn rotate(&self, v: Vector<T, 3>) -> Vector<T, 3> {
+    let q = self.vector();
+    let two = T::unit() + T::unit();
+    let t = q.cross(&v).map(|p| p * two);
+    v + t.map(|p| p * self.w()) + q.cross(&t)
+  }
+
+  /// Rotation matrix of a normalized quaternion
+  pub fn to_mat3(&self) -> Matrix<T, 3, 3> {
+    let (x, y, z, w) = (self.x(), self.y(), self.z(), self.w());
+    let one = T::unit();
+    let two = one + one;
+    Matrix::from_rows([
+      [
+        one - two * (y * y + z * z),
+        two * (x * y - w * z),
+        two * (x * z + w * y),
+      ],
+      [
+        two * (x * y + w * z),
+        one - two * (x * x + z * z),
+        two * (y * z - w * x),
+      ],
+      [
+        two * (x * z - w * y),
+        two * (y * z + w * x),
+        one - two * (x * x + y * y),
+      ],
+    ])
+  }
+
+  pub fn to_mat4(&self) -> Matrix<T, 4, 4> {
+    let m = self.to_mat3();
+    let mut ret = Matrix::identity();
+    for c in 0..3 {
+      for r in 0..3 {
+        ret[(r, c)] = m[(r, c)];
+      }
+    }
+    ret
+  }
+}
+
+/// Hamilton product, `a * b` rotates by `b` then by `a`
+impl<T: Lanes + Zero + AddAssign + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>> Mul
+  for Quaternion<T>
+{
+  type Output = Self;
+
+  fn mul(self, rhs: Self) -> Self::Output {
+    let (a, b) = (self.vector(), rhs.vector());
+    let v = b.map(|p| p * self.w()) + a.map(|p| p * rhs.w()) + a.cross(&b);
+    Self::from_vector(v, self.w() * rhs.w() - a.dot(&b))
+  }
+}
+
+impl<T: Lanes + Zero + AddAssign + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>>
+  MulAssign for Quaternion<T>
+{
+  fn mul_assign(&mut self, rhs: Self) {
+    *self = *self * rhs;
+  }
+}
+
+impl<
+    T: Lanes + Zero + Unit + AddAssign + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>,
+  > Mul<Vector<T, 3>> for Quaternion<T>
+{
+  type Output = Vector<T, 3>;
+
+  fn mul(self, rhs: Vector<T, 3>) -> Self::Output {
+    self.rotate(rhs)
+  }
+}
+
+macro_rules! quaternion_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Quaternion<$typ> {
+      /// Rotation of `angle` radians around `axis`
+      pub fn from_axis_angle(axis: Vector<$typ, 3>, angle: $typ) -> Self {
+        let (s, c) = (angle / 2.).sin_cos();
+        Self::from_vector(axis.normalized() * s, c)
+      }
+
+      /// Rotation around the x axis, then the y axis, then the z axis, in radians
+      pub fn from_euler(x: $typ, y: $typ, z: $typ) -> Self {
+        Self::from_axis_angle(Vector::<$typ, 3>::unit_z(), z)
+          * Self::from_axis_angle(Vector::<$typ, 3>::unit_y(), y)
+          * Self::from_axis_angle(Vector::<$typ, 3>::unit_x(), x)
+      }
+
+      /// Axis and angle in radians, the axis is arbitrary for the identity
+      pub fn to_axis_angle(&self) -> (Vector<$typ, 3>, $typ) {
+        let q = self.normalized();
+        let s = (1. - q.w() * q.w()).max(0.).sqrt();
+        let angle = 2. * q.w().clamp(-1., 1.).acos();
+        if s < <$typ>::EPSILON {
+          (Vector::<$typ, 3>::unit_x(), angle)
+        } else {
+          (q.vector() / s, angle)
+        }
+      }
+
+      pub fn magnitude(&self) -> $typ {
+        self.sq_magnitude().sqrt()
+      }
+
+      pub fn normalize(&mut self) -> $typ {
+        let m = self.magnitude();
+        self.0 /= m;
+        m
+      }
+
+      pub fn normalized(&self) -> Self {
+        let mut ret = *self;
+        ret.normalize();
+        ret
+      }
+
+      pub fn inverse(&self) -> Self {
+        let conj = self.conjugate();
+        Self(conj.0 / self.sq_magnitude())
+      }
+
+      /// Spherical interpolation along the shortest path between two normalized quaternions
+      pub fn slerp(&self, rhs: &Self, t: $typ) -> Self {
+        let mut rhs = *rhs;
+        let mut cos = self.dot(&rhs);
+        if cos < 0. {
+          rhs.0 = -rhs.0;
+          cos = -cos;
+        }
+        if cos > 0.9995 {
+          // Too close for the angle to be accurate, fall back to a linear interpolation
+          return Self(self.0.lerp(&rhs.0, t)).normalized();
+        }
+        let theta = cos.acos();
+        let sin = theta.sin();
+        let a = ((1. - t) * theta).sin() / sin;
+        let b = (t * theta).sin() / sin;
+        Self(self.0 * a + rhs.0 * b)
+      }
+    }
+  )*
+  };
+}
+
+quaternion_impl!(f32, f64);
+
+pub type Quatf32 = Quaternion<f32>;
+pub type Quatf64 = Quaternion<f64>;
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::{FRAC_PI_2, PI};
+
+  use crate::{Mat3f32, Quatf32, Vec3f32};
+
+  fn assert_vec_near(a: Vec3f32, b: Vec3f32) {
+    assert!((a - b).magnitude() < 1e-5, "{:?} != {:?}", a, b);
+  }
+
+  #[test]
+  fn rotate() {
+    let q = Quatf32::from_axis_angle(Vec3f32::unit_z(), FRAC_PI_2);
+    assert_vec_near(q * Vec3f32::unit_x(), Vec3f32::unit_y());
+    assert_vec_near(
+      Quatf32::identity() * Vec3f32::new(1., 2., 3.),
+      Vec3f32::new(1., 2., 3.),
+    );
+
+    let (axis, angle) = q.to_axis_angle();
+    assert_vec_near(axis, Vec3f32::unit_z());
+    assert!((angle - FRAC_PI_2).abs() < 1e-5);
+
+    let v = Vec3f32::new(1., -2., 0.5);
+    let q = Quatf32::from_axis_angle(Vec3f32::new(1., 2., 3.), 0.8);
+    assert_vec_near(q.to_mat3() * v, q * v);
+    assert_vec_near(
+      q.to_mat3() * v,
+      Mat3f32::rotation(Vec3f32::new(1., 2., 3.), 0.8) * v,
+    );
+    assert_vec_near(q.inverse() * (q * v), v);
+  }
+
+  #[test]
+  fn euler() {
+    let q = Quatf32::from_euler(FRAC_PI_2, FRAC_PI_2, 0.);
+    // x then y: the y axis goes to z, then to x
+    assert_vec_near(q * Vec3f32::unit_y(), Vec3f32::unit_x());
+    let m = Mat3f32::rotation_y(FRAC_PI_2) * Mat3f32::rotation_x(FRAC_PI_2);
+    assert_vec_near(q * Vec3f32::new(1., 2., 3.), m * Vec3f32::new(1., 2., 3.));
+  }
+
+  #[test]
+  fn slerp() {
+    let a = Quatf32::identity();
+    let b = Quatf32::from_axis_angle(Vec3f32::unit_z(), PI / 2.);
+    let half = a.slerp(&b, 0.5);
+    assert_vec_near(
+      half * Vec3f32::unit_x(),
+      Vec3f32::new(0.5f32.sqrt(), 0.5f32.sqrt(), 0.),
+    );
+    assert!((half.magnitude() - 1.).abs() < 1e-5);
+    assert_vec_near(a.slerp(&b, 1.) * Vec3f32::unit_x(), Vec3f32::unit_y());
+    assert_eq!(a.slerp(&a, 0.3), a);
+
+    let mut q = Quatf32::new(0., 0., 3., 4.);
+    assert_eq!(q.normalize(), 5.);
+    assert_eq!(q, Quatf32::new(0., 0., 0.6, 0.8));
+  }
+}
@@ -0,0 +1,50 @@
+//! `#[serde(with = ...)]` helpers for arrays of any length, serialized as tuples
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+  de::{Error, SeqAccess, Visitor},
+  ser::SerializeTuple,
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+
+pub fn serialize<S: Serializer, T: Serialize, const N: usize>(
+  value: &[T; N],
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  let mut tuple = serializer.serialize_tuple(N)?;
+  for part in value {
+    tuple.serialize_element(part)?;
+  }
+  tuple.end()
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+  type Value = [T; N];
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "an array of {} elements", N)
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut parts = Vec::with_capacity(N);
+    while let Some(part) = seq.next_element()? {
+      if parts.len() == N {
+        return Err(A::Error::invalid_length(N + 1, &self));
+      }
+      parts.push(part);
+    }
+    let len = parts.len();
+    parts
+      .try_into()
+      .map_err(|_| A::Error::invalid_length(len, &self))
+  }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+  deserializer: D,
+) -> Result<[T; N], D::Error> {
+  deserializer.deserialize_tuple(N, ArrayVisitor::<T, N>(PhantomData))
+}
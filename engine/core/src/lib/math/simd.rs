@@ -0,0 +1,500 @@
+use std::ops::{AddAssign, DivAssign, Mul, MulAssign, SubAssign};
+
+use crate::Zero;
+
+/// Name of the instruction set accelerating 4 lanes vectors and matrices in this build
+pub const SIMD_BACKEND: &str = if cfg!(all(target_arch = "x86_64", target_feature = "avx")) {
+  "avx"
+} else if cfg!(all(target_arch = "x86_64", target_feature = "sse2")) {
+  "sse2"
+} else if cfg!(all(target_arch = "aarch64", target_feature = "neon")) {
+  "neon"
+} else {
+  "scalar"
+};
+
+/// Component-wise kernels behind `Vector` and `Matrix` arithmetic.
+/// The default methods are the scalar path, floats override them with SIMD when the target allows it.
+pub trait Lanes: Copy {
+  #[inline]
+  fn add_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N])
+  where
+    Self: AddAssign,
+  {
+    for i in 0..N {
+      lhs[i] += rhs[i];
+    }
+  }
+
+  #[inline]
+  fn sub_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N])
+  where
+    Self: SubAssign,
+  {
+    for i in 0..N {
+      lhs[i] -= rhs[i];
+    }
+  }
+
+  #[inline]
+  fn mul_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N])
+  where
+    Self: MulAssign,
+  {
+    for i in 0..N {
+      lhs[i] *= rhs[i];
+    }
+  }
+
+  #[inline]
+  fn div_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N])
+  where
+    Self: DivAssign,
+  {
+    for i in 0..N {
+      lhs[i] /= rhs[i];
+    }
+  }
+
+  #[inline]
+  fn scale_lanes<const N: usize>(lhs: &mut [Self; N], rhs: Self)
+  where
+    Self: MulAssign,
+  {
+    for part in lhs.iter_mut() {
+      *part *= rhs;
+    }
+  }
+
+  /// `acc += lhs * rhs`, the building block of matrix products
+  #[inline]
+  fn mul_add_lanes<const N: usize>(acc: &mut [Self; N], lhs: &[Self; N], rhs: Self)
+  where
+    Self: AddAssign + Mul<Self, Output = Self>,
+  {
+    for i in 0..N {
+      acc[i] += lhs[i] * rhs;
+    }
+  }
+
+  #[inline]
+  fn dot_lanes<const N: usize>(lhs: &[Self; N], rhs: &[Self; N]) -> Self
+  where
+    Self: Zero + AddAssign + Mul<Self, Output = Self>,
+  {
+    let mut ret = Self::zero();
+    for i in 0..N {
+      ret += lhs[i] * rhs[i];
+    }
+    ret
+  }
+
+  /// `columns[0] * weights[0] + columns[1] * weights[1] + ...`, the matrix vector product
+  #[inline]
+  fn mul_columns_lanes<const R: usize, const C: usize>(
+    columns: [&[Self; R]; C],
+    weights: &[Self; C],
+  ) -> [Self; R]
+  where
+    Self: Zero + AddAssign + Mul<Self, Output = Self>,
+  {
+    let mut ret = [Self::zero(); R];
+    for c in 0..C {
+      Self::mul_add_lanes(&mut ret, columns[c], weights[c]);
+    }
+    ret
+  }
+}
+
+macro_rules! scalar_lanes_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Lanes for $typ {}
+  )*
+  };
+}
+
+scalar_lanes_impl!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool);
+
+/// Four lanes register of a SIMD instruction set.
+/// Implementations call the intrinsics in `unsafe` blocks, which is sound because each one is
+/// only compiled when its instruction set is enabled for the whole build.
+#[allow(dead_code)]
+trait Quad: Copy {
+  type Reg: Copy;
+
+  fn load(v: &[Self; 4]) -> Self::Reg;
+  fn store(r: Self::Reg) -> [Self; 4];
+  fn splat(v: Self) -> Self::Reg;
+  fn add(a: Self::Reg, b: Self::Reg) -> Self::Reg;
+  fn sub(a: Self::Reg, b: Self::Reg) -> Self::Reg;
+  fn mul(a: Self::Reg, b: Self::Reg) -> Self::Reg;
+  fn div(a: Self::Reg, b: Self::Reg) -> Self::Reg;
+  /// Sum of the lanes
+  fn sum(r: Self::Reg) -> Self;
+}
+
+#[allow(dead_code)]
+#[inline(always)]
+fn load<T: Quad, const N: usize>(v: &[T; N]) -> T::Reg {
+  T::load(v.first_chunk::<4>().expect("4 lanes"))
+}
+
+#[allow(dead_code)]
+#[inline(always)]
+fn store<T: Quad, const N: usize>(r: T::Reg, v: &mut [T; N]) {
+  *v.first_chunk_mut::<4>().expect("4 lanes") = T::store(r);
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod sse2 {
+  use std::arch::x86_64::*;
+
+  use super::Quad;
+
+  impl Quad for f32 {
+    type Reg = __m128;
+
+    #[inline(always)]
+    fn load(v: &[Self; 4]) -> Self::Reg {
+      unsafe { _mm_loadu_ps(v.as_ptr()) }
+    }
+
+    #[inline(always)]
+    fn store(r: Self::Reg) -> [Self; 4] {
+      let mut ret = [0.; 4];
+      unsafe { _mm_storeu_ps(ret.as_mut_ptr(), r) };
+      ret
+    }
+
+    #[inline(always)]
+    fn splat(v: Self) -> Self::Reg {
+      unsafe { _mm_set1_ps(v) }
+    }
+
+    #[inline(always)]
+    fn add(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm_add_ps(a, b) }
+    }
+
+    #[inline(always)]
+    fn sub(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm_sub_ps(a, b) }
+    }
+
+    #[inline(always)]
+    fn mul(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm_mul_ps(a, b) }
+    }
+
+    #[inline(always)]
+    fn div(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm_div_ps(a, b) }
+    }
+
+    #[inline(always)]
+    fn sum(r: Self::Reg) -> Self {
+      unsafe {
+        let shuf = _mm_shuffle_ps::<0b10_11_00_01>(r, r);
+        let sums = _mm_add_ps(r, shuf);
+        let shuf = _mm_movehl_ps(shuf, sums);
+        _mm_cvtss_f32(_mm_add_ss(sums, shuf))
+      }
+    }
+  }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+mod avx {
+  use std::arch::x86_64::*;
+
+  use super::Quad;
+
+  impl Quad for f64 {
+    type Reg = __m256d;
+
+    #[inline(always)]
+    fn load(v: &[Self; 4]) -> Self::Reg {
+      unsafe { _mm256_loadu_pd(v.as_ptr()) }
+    }
+
+    #[inline(always)]
+    fn store(r: Self::Reg) -> [Self; 4] {
+      let mut ret = [0.; 4];
+      unsafe { _mm256_storeu_pd(ret.as_mut_ptr(), r) };
+      ret
+    }
+
+    #[inline(always)]
+    fn splat(v: Self) -> Self::Reg {
+      unsafe { _mm256_set1_pd(v) }
+    }
+
+    #[inline(always)]
+    fn add(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm256_add_pd(a, b) }
+    }
+
+    #[inline(always)]
+    fn sub(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm256_sub_pd(a, b) }
+    }
+
+    #[inline(always)]
+    fn mul(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm256_mul_pd(a, b) }
+    }
+
+    #[inline(always)]
+    fn div(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { _mm256_div_pd(a, b) }
+    }
+
+    #[inline(always)]
+    fn sum(r: Self::Reg) -> Self {
+      unsafe {
+        let pair = _mm_add_pd(_mm256_castpd256_pd128(r), _mm256_extractf128_pd::<1>(r));
+        _mm_cvtsd_f64(_mm_add_sd(pair, _mm_unpackhi_pd(pair, pair)))
+      }
+    }
+  }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod neon {
+  use std::arch::aarch64::*;
+
+  use super::Quad;
+
+  impl Quad for f32 {
+    type Reg = float32x4_t;
+
+    #[inline(always)]
+    fn load(v: &[Self; 4]) -> Self::Reg {
+      unsafe { vld1q_f32(v.as_ptr()) }
+    }
+
+    #[inline(always)]
+    fn store(r: Self::Reg) -> [Self; 4] {
+      let mut ret = [0.; 4];
+      unsafe { vst1q_f32(ret.as_mut_ptr(), r) };
+      ret
+    }
+
+    #[inline(always)]
+    fn splat(v: Self) -> Self::Reg {
+      unsafe { vdupq_n_f32(v) }
+    }
+
+    #[inline(always)]
+    fn add(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { vaddq_f32(a, b) }
+    }
+
+    #[inline(always)]
+    fn sub(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { vsubq_f32(a, b) }
+    }
+
+    #[inline(always)]
+    fn mul(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { vmulq_f32(a, b) }
+    }
+
+    #[inline(always)]
+    fn div(a: Self::Reg, b: Self::Reg) -> Self::Reg {
+      unsafe { vdivq_f32(a, b) }
+    }
+
+    #[inline(always)]
+    fn sum(r: Self::Reg) -> Self {
+      unsafe { vaddvq_f32(r) }
+    }
+  }
+}
+
+/// Run 4 lanes operations on SIMD registers, other sizes stay scalar.
+/// Padding 3 lanes vectors to a register is slower than the scalar loop, see the `vec3f32`
+/// group of the `math` benchmark.
+#[allow(unused_macros)]
+macro_rules! quad_lanes_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Lanes for $typ {
+      #[inline]
+      fn add_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N]) {
+        if N == 4 {
+          store(<$typ as Quad>::add(load(lhs), load(rhs)), lhs);
+        } else {
+          for i in 0..N {
+            lhs[i] += rhs[i];
+          }
+        }
+      }
+
+      #[inline]
+      fn sub_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N]) {
+        if N == 4 {
+          store(<$typ as Quad>::sub(load(lhs), load(rhs)), lhs);
+        } else {
+          for i in 0..N {
+            lhs[i] -= rhs[i];
+          }
+        }
+      }
+
+      #[inline]
+      fn mul_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N]) {
+        if N == 4 {
+          store(<$typ as Quad>::mul(load(lhs), load(rhs)), lhs);
+        } else {
+          for i in 0..N {
+            lhs[i] *= rhs[i];
+          }
+        }
+      }
+
+      #[inline]
+      fn div_lanes<const N: usize>(lhs: &mut [Self; N], rhs: &[Self; N]) {
+        if N == 4 {
+          store(<$typ as Quad>::div(load(lhs), load(rhs)), lhs);
+        } else {
+          for i in 0..N {
+            lhs[i] /= rhs[i];
+          }
+        }
+      }
+
+      #[inline]
+      fn scale_lanes<const N: usize>(lhs: &mut [Self; N], rhs: Self) {
+        if N == 4 {
+          store(<$typ as Quad>::mul(load(lhs), <$typ as Quad>::splat(rhs)), lhs);
+        } else {
+          for part in lhs.iter_mut() {
+            *part *= rhs;
+          }
+        }
+      }
+
+      #[inline]
+      fn mul_add_lanes<const N: usize>(acc: &mut [Self; N], lhs: &[Self; N], rhs: Self) {
+        if N == 4 {
+          let prod = <$typ as Quad>::mul(load(lhs), <$typ as Quad>::splat(rhs));
+          store(<$typ as Quad>::add(load(acc), prod), acc);
+        } else {
+          for i in 0..N {
+            acc[i] += lhs[i] * rhs;
+          }
+        }
+      }
+
+      #[inline]
+      fn dot_lanes<const N: usize>(lhs: &[Self; N], rhs: &[Self; N]) -> Self {
+        if N == 4 {
+          <$typ as Quad>::sum(<$typ as Quad>::mul(load(lhs), load(rhs)))
+        } else {
+          let mut ret = 0.;
+          for i in 0..N {
+            ret += lhs[i] * rhs[i];
+          }
+          ret
+        }
+      }
+
+      #[inline]
+      fn mul_columns_lanes<const R: usize, const C: usize>(
+        columns: [&[Self; R]; C],
+        weights: &[Self; C],
+      ) -> [Self; R] {
+        let mut ret = [0.; R];
+        if R == 4 {
+          // The sum stays in a register across the columns
+          let mut acc = <$typ as Quad>::splat(0.);
+          for c in 0..C {
+            let prod = <$typ as Quad>::mul(load(columns[c]), <$typ as Quad>::splat(weights[c]));
+            acc = <$typ as Quad>::add(acc, prod);
+          }
+          store(acc, &mut ret);
+        } else {
+          for c in 0..C {
+            for i in 0..R {
+              ret[i] += columns[c][i] * weights[c];
+            }
+          }
+        }
+        ret
+      }
+    }
+  )*
+  };
+}
+
+#[cfg(any(
+  all(target_arch = "x86_64", target_feature = "sse2"),
+  all(target_arch = "aarch64", target_feature = "neon")
+))]
+quad_lanes_impl!(f32);
+
+#[cfg(not(any(
+  all(target_arch = "x86_64", target_feature = "sse2"),
+  all(target_arch = "aarch64", target_feature = "neon")
+)))]
+scalar_lanes_impl!(f32);
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx"))]
+quad_lanes_impl!(f64);
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx")))]
+scalar_lanes_impl!(f64);
+
+#[cfg(test)]
+mod tests {
+  use super::Lanes;
+
+  #[test]
+  fn matches_scalar_path() {
+    let a = [1.5f32, -2., 3.25, 4.];
+    let b = [0.5f32, 4., -1., 8.];
+    let check = |f: &dyn Fn(&mut [f32; 4], &[f32; 4]), g: &dyn Fn(&mut [f32; 4], &[f32; 4])| {
+      let (mut x, mut y) = (a, a);
+      f(&mut x, &b);
+      g(&mut y, &b);
+      assert_eq!(x, y);
+    };
+    check(&|l, r| f32::add_lanes(l, r), &|l, r| {
+      l.iter_mut().zip(r).for_each(|(l, r)| *l += r)
+    });
+    check(&|l, r| f32::sub_lanes(l, r), &|l, r| {
+      l.iter_mut().zip(r).for_each(|(l, r)| *l -= r)
+    });
+    check(&|l, r| f32::mul_lanes(l, r), &|l, r| {
+      l.iter_mut().zip(r).for_each(|(l, r)| *l *= r)
+    });
+    check(&|l, r| f32::div_lanes(l, r), &|l, r| {
+      l.iter_mut().zip(r).for_each(|(l, r)| *l /= r)
+    });
+    check(&|l, r| f32::mul_add_lanes(l, r, 2.), &|l, r| {
+      l.iter_mut().zip(r).for_each(|(l, r)| *l += r * 2.)
+    });
+    assert_eq!(f32::dot_lanes(&a, &b), 0.75 - 8. - 3.25 + 32.);
+
+    // Sizes going through the scalar path
+    let mut c = [1f32, 2., 3.];
+    f32::div_lanes(&mut c, &[2., 4., 0.5]);
+    assert_eq!(c, [0.5, 0.5, 6.]);
+    f32::scale_lanes(&mut c, 2.);
+    assert_eq!(c, [1., 1., 12.]);
+    assert_eq!(f32::dot_lanes(&c, &[1., 2., 3.]), 39.);
+    let mut d = [1f64, 2., 3., 4., 5.];
+    f64::add_lanes(&mut d, &[1., 1., 1., 1., 1.]);
+    assert_eq!(d, [2., 3., 4., 5., 6.]);
+    let mut e = [1f64, 2., 3., 4.];
+    f64::mul_add_lanes(&mut e, &[1., 2., 3., 4.], 0.5);
+    assert_eq!(e, [1.5, 3., 4.5, 6.]);
+    assert_eq!(
+      f32::mul_columns_lanes([&a, &b], &[2., -1.]),
+      [2.5, -8., 7.5, 0.]
+    );
+    assert_eq!(f32::mul_columns_lanes([&c, &c], &[1., 2.]), [3., 3., 36.]);
+  }
+}
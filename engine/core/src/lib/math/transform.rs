@@ -0,0 +1,183 @@
+use crate::{Matrix, Quaternion, Unit, Vector, Zero};
+
+/// Scale, then rotation, then translation
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform<T> {
+  translation: Vector<T, 3>,
+  rotation: Quaternion<T>,
+  scale: Vector<T, 3>,
+}
+
+impl<T> Transform<T> {
+  pub fn new(translation: Vector<T, 3>, rotation: Quaternion<T>, scale: Vector<T, 3>) -> Self {
+    Self {
+      translation,
+      rotation,
+      scale,
+    }
+  }
+
+  pub fn translation(&self) -> &Vector<T, 3> {
+    &self.translation
+  }
+
+  pub fn translation_mut(&mut self) -> &mut Vector<T, 3> {
+    &mut self.translation
+  }
+
+  pub fn rotation(&self) -> &Quaternion<T> {
+    &self.rotation
+  }
+
+  pub fn rotation_mut(&mut self) -> &mut Quaternion<T> {
+    &mut self.rotation
+  }
+
+  pub fn scale(&self) -> &Vector<T, 3> {
+    &self.scale
+  }
+
+  pub fn scale_mut(&mut self) -> &mut Vector<T, 3> {
+    &mut self.scale
+  }
+
+  pub fn with_translation(mut self, translation: Vector<T, 3>) -> Self {
+    self.translation = translation;
+    self
+  }
+
+  pub fn with_rotation(mut self, rotation: Quaternion<T>) -> Self {
+    self.rotation = rotation;
+    self
+  }
+
+  pub fn with_scale(mut self, scale: Vector<T, 3>) -> Self {
+    self.scale = scale;
+    self
+  }
+}
+
+impl<T: PartialEq + Copy> PartialEq for Transform<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.translation == other.translation
+      && self.rotation == other.rotation
+      && self.scale == other.scale
+  }
+}
+
+macro_rules! transform_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl Transform<$typ> {
+      pub fn identity() -> Self {
+        Self::new(Vector::zero(), Quaternion::identity(), Vector::unit())
+      }
+
+      pub fn transform_point(&self, p: Vector<$typ, 3>) -> Vector<$typ, 3> {
+        self.rotation * (p * self.scale) + self.translation
+      }
+
+      /// Like `transform_point`, ignoring the translation
+      pub fn transform_vector(&self, v: Vector<$typ, 3>) -> Vector<$typ, 3> {
+        self.rotation * (v * self.scale)
+      }
+
+      /// Transform applying `rhs` first, then `self`.
+      /// Exact when `self` has a uniform scale, a scale cannot be sheared otherwise.
+      pub fn compose(&self, rhs: &Self) -> Self {
+        Self::new(
+          self.transform_point(rhs.translation),
+          (self.rotation * rhs.rotation).normalized(),
+          self.scale * rhs.scale,
+        )
+      }
+
+      /// Transform undoing `self`, exact when the scale is uniform
+      pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let scale = Vector::<$typ, 3>::unit() / self.scale;
+        Self::new(rotation * (-self.translation) * scale, rotation, scale)
+      }
+
+      pub fn to_mat4(&self) -> Matrix<$typ, 4, 4> {
+        Matrix::<$typ, 4, 4>::translation(self.translation)
+          * self.rotation.to_mat4()
+          * Matrix::<$typ, 4, 4>::scale(self.scale)
+      }
+    }
+
+    impl Default for Transform<$typ> {
+      fn default() -> Self {
+        Self::identity()
+      }
+    }
+
+    impl std::ops::Mul for Transform<$typ> {
+      type Output = Self;
+
+      fn mul(self, rhs: Self) -> Self::Output {
+        self.compose(&rhs)
+      }
+    }
+
+    impl From<Transform<$typ>> for Matrix<$typ, 4, 4> {
+      fn from(value: Transform<$typ>) -> Self {
+        value.to_mat4()
+      }
+    }
+  )*
+  };
+}
+
+transform_impl!(f32, f64);
+
+pub type Transformf32 = Transform<f32>;
+pub type Transformf64 = Transform<f64>;
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::FRAC_PI_2;
+
+  use crate::{Quatf32, Transformf32, Vec3f32};
+
+  fn assert_vec_near(a: Vec3f32, b: Vec3f32) {
+    assert!((a - b).magnitude() < 1e-5, "{:?} != {:?}", a, b);
+  }
+
+  fn sample() -> Transformf32 {
+    Transformf32::identity()
+      .with_translation(Vec3f32::new(1., 2., 3.))
+      .with_rotation(Quatf32::from_axis_angle(Vec3f32::unit_z(), FRAC_PI_2))
+      .with_scale(Vec3f32::new(2., 2., 2.))
+  }
+
+  #[test]
+  fn transform() {
+    let t = sample();
+    let p = Vec3f32::new(1., 0., 0.);
+    assert_vec_near(t.transform_point(p), Vec3f32::new(1., 4., 3.));
+    assert_vec_near(t.transform_vector(p), Vec3f32::new(0., 2., 0.));
+    assert_vec_near((t.to_mat4() * p.extend(1.)).xyz(), t.transform_point(p));
+  }
+
+  #[test]
+  fn compose_invert() {
+    let a = sample();
+    let b = Transformf32::identity()
+      .with_translation(Vec3f32::new(-1., 0., 5.))
+      .with_rotation(Quatf32::from_axis_angle(Vec3f32::unit_x(), 0.3));
+    let p = Vec3f32::new(0.5, -1., 2.);
+    assert_vec_near(
+      (a * b).transform_point(p),
+      a.transform_point(b.transform_point(p)),
+    );
+    assert_vec_near(a.inverse().transform_point(a.transform_point(p)), p);
+    assert_vec_near((a * a.inverse()).transform_point(p), p);
+    let m = (a * b).to_mat4() * a.inverse().to_mat4();
+    assert_vec_near(
+      (m * p.extend(1.)).xyz(),
+      (a * b * a.inverse()).transform_point(p),
+    );
+  }
+}
@@ -1,9 +1,24 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+use std::ops::{
+  Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
-use crate::{Sqrt, Unit, Zero};
+use crate::{Abs, ApproxEq, Float, Lanes, Unit, Zero};
 
 #[repr(C)]
-pub struct Vector<T, const N: usize>([T; N]);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(
+    transparent,
+    bound(
+      serialize = "T: serde::Serialize",
+      deserialize = "T: serde::Deserialize<'de>"
+    )
+  )
+)]
+pub struct Vector<T, const N: usize>(
+  #[cfg_attr(feature = "serde", serde(with = "crate::math::serde_array"))] [T; N],
+);
 
 impl<T, const N: usize> Vector<T, N> {
   pub const PARTS: usize = N;
@@ -15,6 +30,80 @@ impl<T, const N: usize> Vector<T, N> {
   pub fn parts(&self) -> &[T; N] {
     &self.0
   }
+
+  pub fn parts_mut(&mut self) -> &mut [T; N] {
+    &mut self.0
+  }
+
+  pub fn into_parts(self) -> [T; N] {
+    self.0
+  }
+
+  pub fn iter(&self) -> std::slice::Iter<'_, T> {
+    self.0.iter()
+  }
+
+  pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+    self.0.iter_mut()
+  }
+
+  /// Apply `f` to every component
+  pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Vector<U, N> {
+    Vector(self.0.map(f))
+  }
+}
+
+impl<T, const N: usize> Index<usize> for Vector<T, N> {
+  type Output = T;
+
+  fn index(&self, index: usize) -> &Self::Output {
+    &self.0[index]
+  }
+}
+
+impl<T, const N: usize> IndexMut<usize> for Vector<T, N> {
+  fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+    &mut self.0[index]
+  }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vector<T, N> {
+  fn from(parts: [T; N]) -> Self {
+    Self(parts)
+  }
+}
+
+impl<T, const N: usize> From<Vector<T, N>> for [T; N] {
+  fn from(v: Vector<T, N>) -> Self {
+    v.0
+  }
+}
+
+impl<T, const N: usize> IntoIterator for Vector<T, N> {
+  type Item = T;
+  type IntoIter = std::array::IntoIter<T, N>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.0.into_iter()
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a Vector<T, N> {
+  type Item = &'a T;
+  type IntoIter = std::slice::Iter<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.0.iter()
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut Vector<T, N> {
+  type Item = &'a mut T;
+  type IntoIter = std::slice::IterMut<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.0.iter_mut()
+  }
 }
 
 impl<T: Clone, const N: usize> Clone for Vector<T, N> {
@@ -25,35 +114,30 @@ impl<T: Clone, const N: usize> Clone for Vector<T, N> {
 
 impl<T: Copy, const N: usize> Copy for Vector<T, N> {}
 
-impl<T: Copy + Zero + AddAssign + Mul<T, Output = T>, const N: usize> Vector<T, N> {
+impl<T: Lanes + Zero + AddAssign + Mul<T, Output = T>, const N: usize> Vector<T, N> {
   pub fn sq_magnitude(&self) -> T {
-    let mut ret = T::zero();
-    for i in 0..N {
-      ret += self.0[i] * self.0[i];
-    }
-    ret
+    T::dot_lanes(&self.0, &self.0)
   }
 
   pub fn dot(&self, rhs: &Self) -> T {
-    let mut ret = T::zero();
-    for i in 0..N {
-      ret += self.0[i] * rhs.0[i];
-    }
-    ret
+    T::dot_lanes(&self.0, &rhs.0)
+  }
+
+  /// `self += rhs * s`
+  pub fn mul_add(&mut self, rhs: &Self, s: T) {
+    T::mul_add_lanes(&mut self.0, &rhs.0, s)
   }
 }
 
-impl<T: Copy + Zero + AddAssign + Mul<T, Output = T> + Sqrt, const N: usize> Vector<T, N> {
+impl<T: Float, const N: usize> Vector<T, N> {
   pub fn magnitude(&self) -> T {
     self.sq_magnitude().sqrt()
   }
-}
 
-impl<
-    T: Copy + Zero + PartialEq + DivAssign + AddAssign + Mul<T, Output = T> + Sqrt,
-    const N: usize,
-  > Vector<T, N>
-{
+  pub fn distance(&self, rhs: &Self) -> T {
+    self.sq_distance(rhs).sqrt()
+  }
+
   pub fn normalize(&mut self) -> T {
     let mag = self.magnitude();
     if mag != T::zero() {
@@ -71,6 +155,97 @@ impl<
   }
 }
 
+impl<
+    T: Lanes + Zero + PartialOrd + AddAssign + Sub<T, Output = T> + Mul<T, Output = T>,
+    const N: usize,
+  > Vector<T, N>
+{
+  /// Components are subtracted largest first, so unsigned vectors do not overflow
+  pub fn sq_distance(&self, rhs: &Self) -> T {
+    let mut diff = *self;
+    for i in 0..N {
+      diff.0[i] = match self.0[i] > rhs.0[i] {
+        true => self.0[i] - rhs.0[i],
+        false => rhs.0[i] - self.0[i],
+      };
+    }
+    diff.sq_magnitude()
+  }
+}
+
+impl<T: Copy + Add<T, Output = T> + Sub<T, Output = T> + Mul<T, Output = T>, const N: usize>
+  Vector<T, N>
+{
+  /// Linear interpolation, `self` at `t = 0` and `rhs` at `t = 1`
+  pub fn lerp(&self, rhs: &Self, t: T) -> Self {
+    let mut ret = *self;
+    for i in 0..N {
+      ret.0[i] = self.0[i] + (rhs.0[i] - self.0[i]) * t;
+    }
+    ret
+  }
+}
+
+impl<T: Copy + PartialOrd, const N: usize> Vector<T, N> {
+  /// Component-wise minimum
+  pub fn min(&self, rhs: &Self) -> Self {
+    let mut ret = *self;
+    for i in 0..N {
+      if rhs.0[i] < ret.0[i] {
+        ret.0[i] = rhs.0[i];
+      }
+    }
+    ret
+  }
+
+  /// Component-wise maximum
+  pub fn max(&self, rhs: &Self) -> Self {
+    let mut ret = *self;
+    for i in 0..N {
+      if rhs.0[i] > ret.0[i] {
+        ret.0[i] = rhs.0[i];
+      }
+    }
+    ret
+  }
+
+  /// Component-wise clamp between `lo` and `hi`
+  pub fn clamp(&self, lo: &Self, hi: &Self) -> Self {
+    self.max(lo).min(hi)
+  }
+
+  /// Panics if `N` is 0
+  pub fn min_part(&self) -> T {
+    assert!(N > 0, "empty vector");
+    let mut ret = self.0[0];
+    for part in &self.0[1..] {
+      if *part < ret {
+        ret = *part;
+      }
+    }
+    ret
+  }
+
+  /// Panics if `N` is 0
+  pub fn max_part(&self) -> T {
+    assert!(N > 0, "empty vector");
+    let mut ret = self.0[0];
+    for part in &self.0[1..] {
+      if *part > ret {
+        ret = *part;
+      }
+    }
+    ret
+  }
+}
+
+impl<T: Copy + Abs, const N: usize> Vector<T, N> {
+  /// Component-wise absolute value
+  pub fn abs(&self) -> Self {
+    self.map(|part| part.abs())
+  }
+}
+
 impl<T: std::fmt::Debug, const N: usize> std::fmt::Debug for Vector<T, N> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     f.debug_tuple("Vector").field(&self.0).finish()
@@ -116,6 +291,22 @@ impl<T: PartialEq + Copy, const N: usize> PartialEq for Vector<T, N> {
   }
 }
 
+impl<T: ApproxEq, const N: usize> ApproxEq for Vector<T, N> {
+  type Epsilon = T::Epsilon;
+
+  fn default_epsilon() -> Self::Epsilon {
+    T::default_epsilon()
+  }
+
+  fn approx_eq_eps(&self, other: &Self, abs: Self::Epsilon, rel: Self::Epsilon) -> bool {
+    self
+      .0
+      .iter()
+      .zip(other.0.iter())
+      .all(|(a, b)| a.approx_eq_eps(b, abs, rel))
+  }
+}
+
 impl<T: PartialOrd + Copy, const N: usize> PartialOrd for Vector<T, N> {
   fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
     self.0.partial_cmp(&other.0)
@@ -128,15 +319,13 @@ impl<T: std::hash::Hash, const N: usize> std::hash::Hash for Vector<T, N> {
   }
 }
 
-impl<T: AddAssign + Copy, const N: usize> AddAssign<Vector<T, N>> for Vector<T, N> {
+impl<T: AddAssign + Lanes, const N: usize> AddAssign<Vector<T, N>> for Vector<T, N> {
   fn add_assign(&mut self, rhs: Vector<T, N>) {
-    for i in 0..N {
-      self.0[i] += rhs.0[i];
-    }
+    T::add_lanes(&mut self.0, &rhs.0)
   }
 }
 
-impl<T: AddAssign + Copy, const N: usize> Add for Vector<T, N> {
+impl<T: AddAssign + Lanes, const N: usize> Add for Vector<T, N> {
   type Output = Self;
 
   fn add(mut self, rhs: Self) -> Self::Output {
@@ -145,15 +334,13 @@ impl<T: AddAssign + Copy, const N: usize> Add for Vector<T, N> {
   }
 }
 
-impl<T: SubAssign + Copy, const N: usize> SubAssign<Vector<T, N>> for Vector<T, N> {
+impl<T: SubAssign + Lanes, const N: usize> SubAssign<Vector<T, N>> for Vector<T, N> {
   fn sub_assign(&mut self, rhs: Vector<T, N>) {
-    for i in 0..N {
-      self.0[i] -= rhs.0[i];
-    }
+    T::sub_lanes(&mut self.0, &rhs.0)
   }
 }
 
-impl<T: SubAssign + Copy, const N: usize> Sub for Vector<T, N> {
+impl<T: SubAssign + Lanes, const N: usize> Sub for Vector<T, N> {
   type Output = Self;
 
   fn sub(mut self, rhs: Self) -> Self::Output {
@@ -162,15 +349,13 @@ impl<T: SubAssign + Copy, const N: usize> Sub for Vector<T, N> {
   }
 }
 
-impl<T: MulAssign + Copy, const N: usize> MulAssign<Vector<T, N>> for Vector<T, N> {
+impl<T: MulAssign + Lanes, const N: usize> MulAssign<Vector<T, N>> for Vector<T, N> {
   fn mul_assign(&mut self, rhs: Vector<T, N>) {
-    for i in 0..N {
-      self.0[i] *= rhs.0[i];
-    }
+    T::mul_lanes(&mut self.0, &rhs.0)
   }
 }
 
-impl<T: MulAssign + Copy, const N: usize> Mul for Vector<T, N> {
+impl<T: MulAssign + Lanes, const N: usize> Mul for Vector<T, N> {
   type Output = Self;
 
   fn mul(mut self, rhs: Self) -> Self::Output {
@@ -179,23 +364,77 @@ impl<T: MulAssign + Copy, const N: usize> Mul for Vector<T, N> {
   }
 }
 
-impl<T: DivAssign + Copy, const N: usize> DivAssign<Vector<T, N>> for Vector<T, N> {
+impl<T: DivAssign + Lanes, const N: usize> DivAssign<Vector<T, N>> for Vector<T, N> {
   fn div_assign(&mut self, rhs: Vector<T, N>) {
+    T::div_lanes(&mut self.0, &rhs.0)
+  }
+}
+
+impl<T: DivAssign + Lanes, const N: usize> Div for Vector<T, N> {
+  type Output = Self;
+
+  fn div(mut self, rhs: Self) -> Self::Output {
+    self.div_assign(rhs);
+    self
+  }
+}
+
+impl<T: Neg<Output = T> + Copy, const N: usize> Neg for Vector<T, N> {
+  type Output = Self;
+
+  fn neg(self) -> Self::Output {
+    self.map(|part| -part)
+  }
+}
+
+impl<T: MulAssign + Lanes, const N: usize> MulAssign<T> for Vector<T, N> {
+  fn mul_assign(&mut self, rhs: T) {
+    T::scale_lanes(&mut self.0, rhs)
+  }
+}
+
+impl<T: MulAssign + Lanes, const N: usize> Mul<T> for Vector<T, N> {
+  type Output = Self;
+
+  fn mul(mut self, rhs: T) -> Self::Output {
+    self.mul_assign(rhs);
+    self
+  }
+}
+
+impl<T: DivAssign + Copy, const N: usize> DivAssign<T> for Vector<T, N> {
+  fn div_assign(&mut self, rhs: T) {
     for i in 0..N {
-      self.0[i] /= rhs.0[i];
+      self.0[i] /= rhs;
     }
   }
 }
 
-impl<T: DivAssign + Copy, const N: usize> Div for Vector<T, N> {
+impl<T: DivAssign + Copy, const N: usize> Div<T> for Vector<T, N> {
   type Output = Self;
 
-  fn div(mut self, rhs: Self) -> Self::Output {
+  fn div(mut self, rhs: T) -> Self::Output {
     self.div_assign(rhs);
     self
   }
 }
 
+macro_rules! scalar_mul_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl<const N: usize> Mul<Vector<$typ, N>> for $typ {
+      type Output = Vector<$typ, N>;
+
+      fn mul(self, rhs: Vector<$typ, N>) -> Self::Output {
+        rhs * self
+      }
+    }
+  )*
+  };
+}
+
+scalar_mul_impl!(f32, f64, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
 impl<T: Copy> Vector<T, 1> {
   pub fn new(x: T) -> Self {
     Self::from_parts([x])
@@ -326,6 +565,101 @@ impl<T: Copy + Zero + Add<T, Output = T> + Mul<T, Output = T> + Sub<T, Output =
   }
 }
 
+impl<T> From<(T, T)> for Vector<T, 2> {
+  fn from((x, y): (T, T)) -> Self {
+    Self([x, y])
+  }
+}
+
+impl<T> From<Vector<T, 2>> for (T, T) {
+  fn from(v: Vector<T, 2>) -> Self {
+    let [x, y] = v.0;
+    (x, y)
+  }
+}
+
+impl<T> From<(T, T, T)> for Vector<T, 3> {
+  fn from((x, y, z): (T, T, T)) -> Self {
+    Self([x, y, z])
+  }
+}
+
+impl<T> From<Vector<T, 3>> for (T, T, T) {
+  fn from(v: Vector<T, 3>) -> Self {
+    let [x, y, z] = v.0;
+    (x, y, z)
+  }
+}
+
+impl<T> From<(T, T, T, T)> for Vector<T, 4> {
+  fn from((x, y, z, w): (T, T, T, T)) -> Self {
+    Self([x, y, z, w])
+  }
+}
+
+impl<T> From<Vector<T, 4>> for (T, T, T, T) {
+  fn from(v: Vector<T, 4>) -> Self {
+    let [x, y, z, w] = v.0;
+    (x, y, z, w)
+  }
+}
+
+impl<T: Copy> Vector<T, 1> {
+  pub fn extend(&self, y: T) -> Vector<T, 2> {
+    Vector::<T, 2>::new(self.0[0], y)
+  }
+}
+
+impl<T: Copy> Vector<T, 2> {
+  pub fn yx(&self) -> Self {
+    Self::new(self.0[1], self.0[0])
+  }
+
+  pub fn extend(&self, z: T) -> Vector<T, 3> {
+    Vector::<T, 3>::new(self.0[0], self.0[1], z)
+  }
+
+  pub fn truncate(&self) -> Vector<T, 1> {
+    Vector::<T, 1>::new(self.0[0])
+  }
+}
+
+impl<T: Copy> Vector<T, 3> {
+  pub fn xy(&self) -> Vector<T, 2> {
+    Vector::<T, 2>::new(self.0[0], self.0[1])
+  }
+
+  pub fn xz(&self) -> Vector<T, 2> {
+    Vector::<T, 2>::new(self.0[0], self.0[2])
+  }
+
+  pub fn yz(&self) -> Vector<T, 2> {
+    Vector::<T, 2>::new(self.0[1], self.0[2])
+  }
+
+  pub fn extend(&self, w: T) -> Vector<T, 4> {
+    Vector::<T, 4>::new(self.0[0], self.0[1], self.0[2], w)
+  }
+
+  pub fn truncate(&self) -> Vector<T, 2> {
+    self.xy()
+  }
+}
+
+impl<T: Copy> Vector<T, 4> {
+  pub fn xy(&self) -> Vector<T, 2> {
+    Vector::<T, 2>::new(self.0[0], self.0[1])
+  }
+
+  pub fn xyz(&self) -> Vector<T, 3> {
+    Vector::<T, 3>::new(self.0[0], self.0[1], self.0[2])
+  }
+
+  pub fn truncate(&self) -> Vector<T, 3> {
+    self.xyz()
+  }
+}
+
 macro_rules! decl_vec_types {
   ($( ($ty:ty, $size:expr, $name: ident) ),*) => {
     $(
@@ -399,9 +733,9 @@ decl_vec_types!(
 
 #[cfg(test)]
 mod tests {
-  use crate::{Sqrt, Unit, Vec2i8, Vec4u8, Zero};
+  use crate::{ApproxEq, Unit, Vec2i8, Vec3f32, Vec3i32, Vec4f32, Vec4u8, Zero};
 
-  use super::{Vec2f32, Vec2u8};
+  use super::{Vec2f32, Vec2u32, Vec2u8, Vector};
 
   #[test]
   fn zero() {
@@ -458,22 +792,26 @@ mod tests {
 
   #[test]
   fn magnitude() {
-    assert_eq!(
-      Vec2u8::new(2, 3).magnitude(),
-      ((2 * 2 + 3 * 3) as u8).sqrt()
-    )
+    assert_eq!(Vec2f32::new(3., 4.).magnitude(), 5.);
+    assert_eq!(Vec2f32::new(1., 1.).distance(&Vec2f32::new(4., 5.)), 5.);
   }
 
   #[test]
   fn normalized() {
-    let expected_mag = ((2 * 2 + 3 * 3) as u8).sqrt();
-    let mut v = Vec2u8::new(2, 3);
+    let mut v = Vec2f32::new(3., 4.);
     let mag = v.normalize();
-    assert_eq!(mag, expected_mag);
-    assert_eq!(
-      v,
-      Vec2u8::new(2, 3) / Vec2u8::new(expected_mag, expected_mag)
-    )
+    assert_eq!(mag, 5.);
+    assert_eq!(v, Vec2f32::new(0.6, 0.8));
+    assert_eq!(Vec2f32::zero().normalized(), Vec2f32::zero());
+  }
+
+  #[test]
+  fn approx_eq() {
+    let v = Vec3f32::new(0.1, 0.2, 0.3) * 3.;
+    assert_ne!(v, Vec3f32::new(0.3, 0.6, 0.9));
+    assert!(v.approx_eq(&Vec3f32::new(0.3, 0.6, 0.9)));
+    assert!(!v.approx_eq(&Vec3f32::new(0.3, 0.6, 0.91)));
+    assert!(v.approx_eq_eps(&Vec3f32::new(0.3, 0.6, 0.91), 0.02, 0.));
   }
 
   #[test]
@@ -485,4 +823,92 @@ mod tests {
   fn cross() {
     assert_eq!(Vec2i8::new(2, 3).cross(&Vec2i8::new(4, 5)), -2);
   }
+
+  #[test]
+  fn scalar() {
+    assert_eq!(Vec2u8::new(1, 2) * 3, Vec2u8::new(3, 6));
+    assert_eq!(3 * Vec2u8::new(1, 2), Vec2u8::new(3, 6));
+    assert_eq!(Vec2u8::new(3, 6) / 3, Vec2u8::new(1, 2));
+    let mut v = Vec3f32::new(1., 2., 3.);
+    v *= 2.;
+    v /= 4.;
+    assert_eq!(v, Vec3f32::new(0.5, 1., 1.5));
+    assert_eq!(-Vec2i8::new(1, -2), Vec2i8::new(-1, 2));
+  }
+
+  #[test]
+  fn conversions() {
+    let mut v = Vec3i32::from([1, 2, 3]);
+    v[1] = 5;
+    assert_eq!(v[1], 5);
+    assert_eq!(<[i32; 3]>::from(v), [1, 5, 3]);
+    assert_eq!(Vec3i32::from((1, 5, 3)), v);
+    assert_eq!(<(i32, i32, i32)>::from(v), (1, 5, 3));
+    assert_eq!(v.iter().sum::<i32>(), 9);
+    for part in &mut v {
+      *part *= 2;
+    }
+    assert_eq!(v.into_iter().collect::<Vec<_>>(), vec![2, 10, 6]);
+    assert_eq!(v.map(|p| p as f32 / 2.), Vec3f32::new(1., 5., 3.));
+  }
+
+  #[test]
+  fn swizzles() {
+    let v = Vec4f32::new(1., 2., 3., 4.);
+    assert_eq!(v.xyz(), Vec3f32::new(1., 2., 3.));
+    assert_eq!(v.xy(), Vec2f32::new(1., 2.));
+    assert_eq!(v.xyz().xz(), Vec2f32::new(1., 3.));
+    assert_eq!(v.xy().yx(), Vec2f32::new(2., 1.));
+    assert_eq!(v.truncate().extend(4.), v);
+    assert_eq!(Vec2f32::new(1., 2.).truncate().x(), 1.);
+  }
+
+  #[test]
+  fn interpolation() {
+    let a = Vec2f32::new(0., 10.);
+    let b = Vec2f32::new(10., 20.);
+    assert_eq!(a.lerp(&b, 0.5), Vec2f32::new(5., 15.));
+    assert_eq!(a.lerp(&b, 0.), a);
+    assert_eq!(a.lerp(&b, 1.), b);
+    assert_eq!(Vec2f32::new(0., 0.).distance(&Vec2f32::new(3., 4.)), 5.);
+    assert_eq!(Vec2f32::new(1., 1.).sq_distance(&Vec2f32::new(3., 4.)), 13.);
+    assert_eq!(Vec2u32::new(1, 5).sq_distance(&Vec2u32::new(3, 4)), 5);
+  }
+
+  #[test]
+  fn component_wise() {
+    let a = Vec3i32::new(1, -5, 3);
+    let b = Vec3i32::new(2, -6, 0);
+    assert_eq!(a.min(&b), Vec3i32::new(1, -6, 0));
+    assert_eq!(a.max(&b), Vec3i32::new(2, -5, 3));
+    assert_eq!(
+      a.clamp(&Vec3i32::new(0, 0, 0), &Vec3i32::new(2, 2, 2)),
+      Vec3i32::new(1, 0, 2)
+    );
+    assert_eq!(a.abs(), Vec3i32::new(1, 5, 3));
+    assert_eq!(a.min_part(), -5);
+    assert_eq!(a.max_part(), 3);
+  }
+
+  #[test]
+  #[should_panic(expected = "empty vector")]
+  fn empty_min_part() {
+    Vector::<i32, 0>::from_parts([]).min_part();
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serde() {
+    let v = Vec3i32::new(1, -2, 3);
+    let json = serde_json::to_string(&v).unwrap();
+    assert_eq!(json, "[1,-2,3]");
+    assert_eq!(serde_json::from_str::<Vec3i32>(&json).unwrap(), v);
+    assert!(serde_json::from_str::<Vec3i32>("[1,2]").is_err());
+    assert!(serde_json::from_str::<Vec3i32>("[1,2,3,4]").is_err());
+
+    let m = crate::Mat2f32::from_rows([[1., 2.], [3., 4.]]);
+    let json = serde_json::to_string(&m).unwrap();
+    assert_eq!(json, "[[1.0,3.0],[2.0,4.0]]");
+    assert_eq!(serde_json::from_str::<crate::Mat2f32>(&json).unwrap(), m);
+  }
 }
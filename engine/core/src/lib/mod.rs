@@ -9,8 +9,12 @@ pub mod event;
 pub mod generic;
 pub mod location;
 pub mod math;
+pub mod procgen;
 pub mod ptr;
+pub mod random;
 pub mod render;
+pub mod replay;
+pub mod time;
 
 pub use archive::*;
 pub use engine::*;
@@ -19,5 +23,9 @@ pub use event::*;
 pub use generic::*;
 pub use location::*;
 pub use math::*;
+pub use procgen::*;
 pub use ptr::*;
+pub use random::*;
 pub use render::*;
+pub use replay::*;
+pub use time::*;
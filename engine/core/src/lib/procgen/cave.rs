@@ -0,0 +1,181 @@
+use crate::{err, l_path, Connection, ErrorKind, Grid, Level, Rect, Rng, Seed, Tile};
+
+/// Caverns grown by a cellular automaton from random noise, small pockets are filled and the
+/// remaining caverns are linked by tunnels
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaveGenerator {
+  width: usize,
+  height: usize,
+  fill: f64,
+  steps: u32,
+  min_region: usize,
+}
+
+impl Default for CaveGenerator {
+  fn default() -> Self {
+    Self {
+      width: 64,
+      height: 48,
+      fill: 0.45,
+      steps: 5,
+      min_region: 16,
+    }
+  }
+}
+
+impl CaveGenerator {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_size(mut self, width: usize, height: usize) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// Initial ratio of walls
+  pub fn with_fill(mut self, fill: f64) -> Self {
+    self.fill = fill;
+    self
+  }
+
+  /// Number of smoothing passes of the automaton
+  pub fn with_steps(mut self, steps: u32) -> Self {
+    self.steps = steps;
+    self
+  }
+
+  /// Caverns with fewer tiles are filled with walls
+  pub fn with_min_region(mut self, min_region: usize) -> Self {
+    self.min_region = min_region;
+    self
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// Fails when no cavern is left, which only happens with a high fill ratio or small maps
+  pub fn generate(&self, seed: Seed) -> crate::Result<Level> {
+    if self.width < 3 || self.height < 3 {
+      return err!(ErrorKind::InvalidInput, "cave needs at least 3x3 tiles");
+    }
+    if !(0. ..=1.).contains(&self.fill) {
+      return err!(ErrorKind::InvalidInput, "fill ratio outside of [0, 1]");
+    }
+
+    let mut rng = Rng::new(seed);
+    let inside = Rect::new(1, 1, self.width - 2, self.height - 2);
+    let mut grid = Grid::new(self.width, self.height);
+    for (x, y) in inside.tiles() {
+      if !rng.chance(self.fill) {
+        grid.set(x, y, Tile::Floor);
+      }
+    }
+    for _ in 0..self.steps {
+      let mut next = grid.clone();
+      for (x, y) in inside.tiles() {
+        match grid.walls_around(x, y) {
+          0..=3 => next.set(x, y, Tile::Floor),
+          5.. => next.set(x, y, Tile::Wall),
+          _ => (),
+        }
+      }
+      grid = next;
+    }
+
+    let mut regions = grid.regions();
+    regions.retain(|region| {
+      if region.len() < self.min_region {
+        for (x, y) in region {
+          grid.set(*x, *y, Tile::Wall);
+        }
+        false
+      } else {
+        true
+      }
+    });
+    if regions.is_empty() {
+      return err!(
+        ErrorKind::InvalidInput,
+        format!("no cavern left with seed {}", seed)
+      );
+    }
+
+    let areas: Vec<Rect> = regions.iter().map(|r| bounds(r)).collect();
+    let anchors: Vec<(usize, usize)> = regions.iter().map(|r| anchor(r)).collect();
+    let mut connections = vec![];
+    for i in 1..regions.len() {
+      let nearest = (0..i)
+        .min_by_key(|j| anchors[i].0.abs_diff(anchors[*j].0) + anchors[i].1.abs_diff(anchors[*j].1))
+        .unwrap();
+      let path = l_path(anchors[nearest], anchors[i], rng.random());
+      for (x, y) in &path {
+        if grid.get(*x, *y) == Some(Tile::Wall) {
+          grid.set(*x, *y, Tile::Corridor);
+        }
+      }
+      connections.push(Connection::new(nearest, i, path));
+    }
+
+    Ok(Level::new(seed, grid, areas, connections, vec![]))
+  }
+}
+
+fn bounds(region: &[(usize, usize)]) -> Rect {
+  let min_x = region.iter().map(|p| p.0).min().unwrap_or_default();
+  let max_x = region.iter().map(|p| p.0).max().unwrap_or_default();
+  let min_y = region.iter().map(|p| p.1).min().unwrap_or_default();
+  let max_y = region.iter().map(|p| p.1).max().unwrap_or_default();
+  Rect::new(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
+}
+
+/// Tile of the region closest to its centroid, tunnels start there
+fn anchor(region: &[(usize, usize)]) -> (usize, usize) {
+  let n = region.len().max(1);
+  let cx = region.iter().map(|p| p.0).sum::<usize>() / n;
+  let cy = region.iter().map(|p| p.1).sum::<usize>() / n;
+  region
+    .iter()
+    .copied()
+    .min_by_key(|p| p.0.abs_diff(cx) + p.1.abs_diff(cy))
+    .unwrap_or((cx, cy))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::CaveGenerator;
+  use crate::{Seed, Tile};
+
+  #[test]
+  fn caves() {
+    let generator = CaveGenerator::new().with_size(60, 40);
+    for seed in 0..20 {
+      let level = generator.generate(Seed::new(seed)).unwrap();
+      assert_eq!(level, generator.generate(Seed::new(seed)).unwrap());
+      let grid = level.grid();
+      assert!(grid.is_connected(), "seed {}\n{}", seed, grid);
+      assert!(
+        grid.count(Tile::Floor) > 60 * 40 / 4,
+        "seed {}\n{}",
+        seed,
+        grid
+      );
+      assert_eq!(level.connections().len(), level.areas().len() - 1);
+      for x in 0..grid.width() {
+        assert_eq!(grid.get(x, 0), Some(Tile::Wall));
+        assert_eq!(grid.get(x, grid.height() - 1), Some(Tile::Wall));
+      }
+    }
+    assert!(CaveGenerator::new()
+      .with_fill(1.)
+      .generate(Seed::new(0))
+      .is_err());
+  }
+}
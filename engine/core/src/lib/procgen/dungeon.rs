@@ -0,0 +1,302 @@
+use crate::{err, l_path, Connection, ErrorKind, Grid, Level, Rect, Rng, Seed, Tile};
+
+/// Rooms in a binary space partition of the map, linked by corridors with doors, plus dead end
+/// corridors branching off them
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DungeonGenerator {
+  width: usize,
+  height: usize,
+  min_leaf: usize,
+  min_room: usize,
+  loop_chance: f64,
+  dead_ends: usize,
+  dead_end_length: usize,
+}
+
+impl Default for DungeonGenerator {
+  fn default() -> Self {
+    Self {
+      width: 64,
+      height: 48,
+      min_leaf: 10,
+      min_room: 4,
+      loop_chance: 0.15,
+      dead_ends: 4,
+      dead_end_length: 6,
+    }
+  }
+}
+
+impl DungeonGenerator {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_size(mut self, width: usize, height: usize) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// Smallest side of a partition, each partition holds one room
+  pub fn with_min_leaf(mut self, min_leaf: usize) -> Self {
+    self.min_leaf = min_leaf;
+    self
+  }
+
+  pub fn with_min_room(mut self, min_room: usize) -> Self {
+    self.min_room = min_room;
+    self
+  }
+
+  /// Chance for each room to get an extra corridor to its nearest room, creating loops
+  pub fn with_loop_chance(mut self, loop_chance: f64) -> Self {
+    self.loop_chance = loop_chance;
+    self
+  }
+
+  pub fn with_dead_ends(mut self, count: usize, max_length: usize) -> Self {
+    self.dead_ends = count;
+    self.dead_end_length = max_length;
+    self
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn generate(&self, seed: Seed) -> crate::Result<Level> {
+    if self.min_room == 0 || self.min_leaf < self.min_room + 2 {
+      return err!(
+        ErrorKind::InvalidInput,
+        "partitions need room for the smallest room and its walls"
+      );
+    }
+    if self.width < self.min_leaf || self.height < self.min_leaf {
+      return err!(ErrorKind::InvalidInput, "dungeon smaller than a partition");
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut rooms = vec![];
+    let mut links = vec![];
+    self.partition(
+      &mut rng,
+      Rect::new(0, 0, self.width, self.height),
+      &mut rooms,
+      &mut links,
+    );
+    for i in 0..rooms.len() {
+      if !rng.chance(self.loop_chance) {
+        continue;
+      }
+      let nearest = (0..rooms.len())
+        .filter(|j| *j != i && !links.contains(&(i, *j)) && !links.contains(&(*j, i)))
+        .min_by_key(|j| distance(rooms[i].center(), rooms[*j].center()));
+      if let Some(j) = nearest {
+        links.push((i, j));
+      }
+    }
+
+    let mut grid = Grid::new(self.width, self.height);
+    for room in &rooms {
+      for (x, y) in room.tiles() {
+        grid.set(x, y, Tile::Floor);
+      }
+    }
+    let mut connections = vec![];
+    for (a, b) in links {
+      let path = l_path(rooms[a].center(), rooms[b].center(), rng.random());
+      carve_corridor(&mut grid, &rooms, &path);
+      connections.push(Connection::new(a, b, path));
+    }
+    let dead_ends = self.dig_dead_ends(&mut rng, &mut grid);
+
+    Ok(Level::new(seed, grid, rooms, connections, dead_ends))
+  }
+
+  /// Splits `area` until its parts are too small, places a room in each part and links the
+  /// closest rooms of both halves of every split. Returns the rooms placed in `area`.
+  fn partition(
+    &self,
+    rng: &mut Rng,
+    area: Rect,
+    rooms: &mut Vec<Rect>,
+    links: &mut Vec<(usize, usize)>,
+  ) -> Vec<usize> {
+    let split_x = area.width() >= 2 * self.min_leaf;
+    let split_y = area.height() >= 2 * self.min_leaf;
+    if !split_x && !split_y {
+      // One tile of padding keeps rooms of neighbouring partitions apart
+      let width = rng.range(self.min_room..=area.width() - 2);
+      let height = rng.range(self.min_room..=area.height() - 2);
+      let x = rng.range(area.x() + 1..=area.right() - 1 - width);
+      let y = rng.range(area.y() + 1..=area.bottom() - 1 - height);
+      rooms.push(Rect::new(x, y, width, height));
+      return vec![rooms.len() - 1];
+    }
+
+    let vertical = if split_x && split_y {
+      if area.width() * 4 > area.height() * 5 {
+        true
+      } else if area.height() * 4 > area.width() * 5 {
+        false
+      } else {
+        rng.random()
+      }
+    } else {
+      split_x
+    };
+    let (a, b) = if vertical {
+      let at = rng.range(self.min_leaf..=area.width() - self.min_leaf);
+      (
+        Rect::new(area.x(), area.y(), at, area.height()),
+        Rect::new(area.x() + at, area.y(), area.width() - at, area.height()),
+      )
+    } else {
+      let at = rng.range(self.min_leaf..=area.height() - self.min_leaf);
+      (
+        Rect::new(area.x(), area.y(), area.width(), at),
+        Rect::new(area.x(), area.y() + at, area.width(), area.height() - at),
+      )
+    };
+
+    let mut left = self.partition(rng, a, rooms, links);
+    let right = self.partition(rng, b, rooms, links);
+    let closest = left
+      .iter()
+      .flat_map(|l| right.iter().map(move |r| (*l, *r)))
+      .min_by_key(|(l, r)| distance(rooms[*l].center(), rooms[*r].center()));
+    if let Some(link) = closest {
+      links.push(link);
+    }
+    left.extend(right);
+    left
+  }
+
+  /// Corridors leaving existing corridors that touch nothing else along their way
+  fn dig_dead_ends(&self, rng: &mut Rng, grid: &mut Grid) -> Vec<(usize, usize)> {
+    let starts: Vec<(usize, usize)> = Rect::new(0, 0, grid.width(), grid.height())
+      .tiles()
+      .filter(|(x, y)| grid.get(*x, *y) == Some(Tile::Corridor))
+      .collect();
+    let mut ret = vec![];
+    if self.dead_end_length < 2 {
+      return ret;
+    }
+    for _ in 0..self.dead_ends * 8 {
+      if ret.len() == self.dead_ends {
+        break;
+      }
+      let Some(start) = rng.choose(&starts).copied() else {
+        break;
+      };
+      let (dx, dy) = *rng.choose(&[(0, -1), (1, 0), (0, 1), (-1, 0)]).unwrap();
+      let length = rng.range(2..=self.dead_end_length);
+      let mut path = vec![];
+      let mut cur = start;
+      while path.len() < length {
+        let next = match (cur.0.checked_add_signed(dx), cur.1.checked_add_signed(dy)) {
+          (Some(x), Some(y)) if x + 1 < grid.width() && y + 1 < grid.height() && x > 0 && y > 0 => {
+            (x, y)
+          }
+          _ => break,
+        };
+        let isolated = grid.get(next.0, next.1) == Some(Tile::Wall)
+          && grid
+            .neighbours(next.0, next.1)
+            .all(|n| n == cur || !grid.is_walkable(n.0, n.1));
+        if !isolated {
+          break;
+        }
+        path.push(next);
+        cur = next;
+      }
+      if path.len() >= 2 {
+        for (x, y) in &path {
+          grid.set(*x, *y, Tile::Corridor);
+        }
+        ret.push(cur);
+      }
+    }
+    ret
+  }
+}
+
+fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
+  a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+/// Carves `path` through the walls, with a door where it enters or leaves a room
+fn carve_corridor(grid: &mut Grid, rooms: &[Rect], path: &[(usize, usize)]) {
+  let in_room = |p: &(usize, usize)| rooms.iter().any(|r| r.contains(p.0, p.1));
+  for (i, (x, y)) in path.iter().enumerate() {
+    if in_room(&path[i]) {
+      continue;
+    }
+    let entrance = (i > 0 && in_room(&path[i - 1])) || path.get(i + 1).is_some_and(in_room);
+    if entrance {
+      grid.set(*x, *y, Tile::Door);
+    } else if grid.get(*x, *y) == Some(Tile::Wall) {
+      grid.set(*x, *y, Tile::Corridor);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::DungeonGenerator;
+  use crate::{Seed, Tile};
+
+  #[test]
+  fn deterministic() {
+    let generator = DungeonGenerator::new();
+    let a = generator.generate(Seed::new(13)).unwrap();
+    assert_eq!(a, generator.generate(Seed::new(13)).unwrap());
+    assert_ne!(a.grid(), generator.generate(Seed::new(14)).unwrap().grid());
+    assert!(DungeonGenerator::new()
+      .with_min_leaf(5)
+      .generate(Seed::new(1))
+      .is_err());
+  }
+
+  #[test]
+  fn layout() {
+    for seed in 0..20 {
+      let level = DungeonGenerator::new()
+        .with_size(80, 50)
+        .generate(Seed::new(seed))
+        .unwrap();
+      let grid = level.grid();
+      assert!(level.areas().len() >= 4);
+      assert!(grid.is_connected(), "seed {}\n{}", seed, grid);
+
+      for (i, a) in level.areas().iter().enumerate() {
+        assert!(a.x() > 0 && a.y() > 0 && a.right() < grid.width() && a.bottom() < grid.height());
+        for b in &level.areas()[i + 1..] {
+          assert!(!a.intersects(b), "seed {}: {:?} overlaps {:?}", seed, a, b);
+        }
+      }
+      assert!(level.connections().len() >= level.areas().len() - 1);
+
+      let doors = level.doors();
+      assert!(!doors.is_empty());
+      for (x, y) in doors {
+        assert!(grid
+          .neighbours(x, y)
+          .any(|(nx, ny)| grid.get(nx, ny) == Some(Tile::Floor)));
+      }
+      for (x, y) in level.dead_ends() {
+        assert_eq!(grid.get(*x, *y), Some(Tile::Corridor));
+        let exits = grid
+          .neighbours(*x, *y)
+          .filter(|(nx, ny)| grid.is_walkable(*nx, *ny));
+        assert_eq!(exits.count(), 1, "seed {}\n{}", seed, grid);
+      }
+    }
+  }
+}
@@ -0,0 +1,303 @@
+use std::{collections::VecDeque, fmt::Display};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tile {
+  #[default]
+  Wall,
+  Floor,
+  Corridor,
+  Door,
+}
+
+impl Tile {
+  pub fn is_walkable(&self) -> bool {
+    *self != Tile::Wall
+  }
+
+  fn symbol(&self) -> char {
+    match self {
+      Tile::Wall => '#',
+      Tile::Floor => '.',
+      Tile::Corridor => ',',
+      Tile::Door => '+',
+    }
+  }
+}
+
+/// Axis aligned rectangle of tiles
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+  x: usize,
+  y: usize,
+  width: usize,
+  height: usize,
+}
+
+impl Rect {
+  pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+    Self {
+      x,
+      y,
+      width,
+      height,
+    }
+  }
+
+  pub fn x(&self) -> usize {
+    self.x
+  }
+
+  pub fn y(&self) -> usize {
+    self.y
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  /// First column past the rectangle
+  pub fn right(&self) -> usize {
+    self.x + self.width
+  }
+
+  /// First row past the rectangle
+  pub fn bottom(&self) -> usize {
+    self.y + self.height
+  }
+
+  pub fn area(&self) -> usize {
+    self.width * self.height
+  }
+
+  pub fn center(&self) -> (usize, usize) {
+    (self.x + self.width / 2, self.y + self.height / 2)
+  }
+
+  pub fn contains(&self, x: usize, y: usize) -> bool {
+    x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+  }
+
+  /// Whether the rectangles share at least one tile
+  pub fn intersects(&self, other: &Rect) -> bool {
+    self.x < other.right()
+      && other.x < self.right()
+      && self.y < other.bottom()
+      && other.y < self.bottom()
+  }
+
+  pub fn tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (self.y..self.bottom()).flat_map(move |y| (self.x..self.right()).map(move |x| (x, y)))
+  }
+}
+
+/// Tile map, `x` goes right and `y` goes down
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid {
+  width: usize,
+  height: usize,
+  tiles: Vec<Tile>,
+}
+
+impl Grid {
+  /// Grid filled with walls
+  pub fn new(width: usize, height: usize) -> Self {
+    Self {
+      width,
+      height,
+      tiles: vec![Tile::Wall; width * height],
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn tiles(&self) -> &[Tile] {
+    &self.tiles
+  }
+
+  pub fn contains(&self, x: usize, y: usize) -> bool {
+    x < self.width && y < self.height
+  }
+
+  pub fn get(&self, x: usize, y: usize) -> Option<Tile> {
+    if self.contains(x, y) {
+      Some(self.tiles[y * self.width + x])
+    } else {
+      None
+    }
+  }
+
+  /// Panics if `(x, y)` is outside the grid
+  pub fn set(&mut self, x: usize, y: usize, tile: Tile) {
+    assert!(self.contains(x, y), "({}, {}) outside of the grid", x, y);
+    self.tiles[y * self.width + x] = tile;
+  }
+
+  pub fn is_walkable(&self, x: usize, y: usize) -> bool {
+    self.get(x, y).is_some_and(|t| t.is_walkable())
+  }
+
+  pub fn count(&self, tile: Tile) -> usize {
+    self.tiles.iter().filter(|t| **t == tile).count()
+  }
+
+  /// Orthogonal neighbours inside the grid
+  pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+    [(0, -1), (1, 0), (0, 1), (-1, 0)]
+      .into_iter()
+      .filter_map(move |(dx, dy)| {
+        let (nx, ny) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.contains(nx, ny).then_some((nx, ny))
+      })
+  }
+
+  /// Walls among the 8 surrounding tiles, counting the outside of the grid as walls
+  pub fn walls_around(&self, x: usize, y: usize) -> usize {
+    let mut ret = 0;
+    for dy in -1..=1isize {
+      for dx in -1..=1isize {
+        if (dx, dy) == (0, 0) {
+          continue;
+        }
+        let walkable = match (x.checked_add_signed(dx), y.checked_add_signed(dy)) {
+          (Some(nx), Some(ny)) => self.is_walkable(nx, ny),
+          _ => false,
+        };
+        if !walkable {
+          ret += 1;
+        }
+      }
+    }
+    ret
+  }
+
+  /// Orthogonally connected groups of walkable tiles, in scan order
+  pub fn regions(&self) -> Vec<Vec<(usize, usize)>> {
+    let mut seen = vec![false; self.tiles.len()];
+    let mut ret = vec![];
+    for start in 0..self.tiles.len() {
+      if seen[start] || !self.tiles[start].is_walkable() {
+        continue;
+      }
+      seen[start] = true;
+      let mut region = vec![];
+      let mut queue = VecDeque::from([(start % self.width, start / self.width)]);
+      while let Some((x, y)) = queue.pop_front() {
+        region.push((x, y));
+        for (nx, ny) in self.neighbours(x, y) {
+          let i = ny * self.width + nx;
+          if !seen[i] && self.tiles[i].is_walkable() {
+            seen[i] = true;
+            queue.push_back((nx, ny));
+          }
+        }
+      }
+      ret.push(region);
+    }
+    ret
+  }
+
+  /// Whether every walkable tile can be reached from every other one
+  pub fn is_connected(&self) -> bool {
+    self.regions().len() <= 1
+  }
+}
+
+/// Tiles from `from` to `to` inclusive, moving along one axis then the other
+pub fn l_path(
+  from: (usize, usize),
+  to: (usize, usize),
+  horizontal_first: bool,
+) -> Vec<(usize, usize)> {
+  let corner = if horizontal_first {
+    (to.0, from.1)
+  } else {
+    (from.0, to.1)
+  };
+  let mut ret = vec![from];
+  for target in [corner, to] {
+    let mut cur = *ret.last().unwrap();
+    while cur != target {
+      cur.0 = step(cur.0, target.0);
+      cur.1 = step(cur.1, target.1);
+      ret.push(cur);
+    }
+  }
+  ret
+}
+
+fn step(from: usize, to: usize) -> usize {
+  match from.cmp(&to) {
+    std::cmp::Ordering::Less => from + 1,
+    std::cmp::Ordering::Greater => from - 1,
+    std::cmp::Ordering::Equal => from,
+  }
+}
+
+impl Display for Grid {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for row in self.tiles.chunks(self.width.max(1)) {
+      let line: String = row.iter().map(|t| t.symbol()).collect();
+      writeln!(f, "{}", line)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{l_path, Grid, Rect, Tile};
+
+  #[test]
+  fn regions() {
+    let mut grid = Grid::new(6, 4);
+    for (x, y) in Rect::new(1, 1, 2, 2).tiles() {
+      grid.set(x, y, Tile::Floor);
+    }
+    grid.set(4, 1, Tile::Corridor);
+    assert_eq!(grid.regions().len(), 2);
+    assert!(!grid.is_connected());
+
+    grid.set(3, 1, Tile::Door);
+    assert!(grid.is_connected());
+    assert_eq!(grid.regions()[0].len(), 6);
+    assert_eq!(grid.walls_around(0, 0), 7);
+    assert_eq!(grid.walls_around(2, 2), 4);
+    assert_eq!(grid.to_string(), "######\n#..+,#\n#..###\n######\n");
+    assert_eq!(grid.get(6, 0), None);
+  }
+
+  #[test]
+  fn rect() {
+    let a = Rect::new(2, 2, 3, 3);
+    assert!(a.intersects(&Rect::new(4, 4, 2, 2)));
+    assert!(!a.intersects(&Rect::new(5, 2, 2, 2)));
+    assert_eq!(a.center(), (3, 3));
+    assert_eq!(a.tiles().count(), a.area());
+  }
+
+  #[test]
+  fn path() {
+    assert_eq!(
+      l_path((1, 1), (3, 0), true),
+      [(1, 1), (2, 1), (3, 1), (3, 0)]
+    );
+    assert_eq!(
+      l_path((1, 1), (3, 0), false),
+      [(1, 1), (1, 0), (2, 0), (3, 0)]
+    );
+    assert_eq!(l_path((2, 2), (2, 2), true), [(2, 2)]);
+  }
+}
@@ -0,0 +1,211 @@
+use crate::{Grid, Rect, Seed, Tile, Vec3f32, Vec4f32, Vertex, VertexList};
+
+/// Edge of the level graph, a walkable path between two areas
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Connection {
+  from: usize,
+  to: usize,
+  path: Vec<(usize, usize)>,
+}
+
+impl Connection {
+  pub fn new(from: usize, to: usize, path: Vec<(usize, usize)>) -> Self {
+    Self { from, to, path }
+  }
+
+  /// Index of the first area in [`Level::areas`]
+  pub fn from(&self) -> usize {
+    self.from
+  }
+
+  pub fn to(&self) -> usize {
+    self.to
+  }
+
+  /// Tiles from the center of `from` to the center of `to`
+  pub fn path(&self) -> &[(usize, usize)] {
+    &self.path
+  }
+}
+
+/// Generated layout: a tile grid and a graph of areas linked by connections
+///
+/// Areas are the rooms of a dungeon or the bounds of the caverns of a cave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Level {
+  seed: Seed,
+  grid: Grid,
+  areas: Vec<Rect>,
+  connections: Vec<Connection>,
+  dead_ends: Vec<(usize, usize)>,
+}
+
+/// Triangle lists of a level, ready to upload to vertex buffers
+#[derive(Default)]
+pub struct LevelMesh {
+  floor: VertexList<f32>,
+  walls: VertexList<f32>,
+}
+
+impl LevelMesh {
+  /// Walkable tiles on the `y = 0` plane, facing up
+  pub fn floor(&self) -> &VertexList<f32> {
+    &self.floor
+  }
+
+  /// Sides of the walls bordering walkable tiles, facing the walkable side
+  pub fn walls(&self) -> &VertexList<f32> {
+    &self.walls
+  }
+}
+
+impl Level {
+  pub fn new(
+    seed: Seed,
+    grid: Grid,
+    areas: Vec<Rect>,
+    connections: Vec<Connection>,
+    dead_ends: Vec<(usize, usize)>,
+  ) -> Self {
+    Self {
+      seed,
+      grid,
+      areas,
+      connections,
+      dead_ends,
+    }
+  }
+
+  /// Seed the level was generated from
+  pub fn seed(&self) -> Seed {
+    self.seed
+  }
+
+  pub fn grid(&self) -> &Grid {
+    &self.grid
+  }
+
+  pub fn areas(&self) -> &[Rect] {
+    &self.areas
+  }
+
+  pub fn connections(&self) -> &[Connection] {
+    &self.connections
+  }
+
+  /// Last tile of each corridor leading nowhere
+  pub fn dead_ends(&self) -> &[(usize, usize)] {
+    &self.dead_ends
+  }
+
+  pub fn doors(&self) -> Vec<(usize, usize)> {
+    Rect::new(0, 0, self.grid.width(), self.grid.height())
+      .tiles()
+      .filter(|(x, y)| self.grid.get(*x, *y) == Some(Tile::Door))
+      .collect()
+  }
+
+  /// Grid tile `(x, y)` covers `[x, x + 1] * tile_size` on the world x axis and
+  /// `[y, y + 1] * tile_size` on the world z axis
+  pub fn mesh(&self, tile_size: f32, wall_height: f32) -> LevelMesh {
+    let mut ret = LevelMesh::default();
+    let wall_color = Vec4f32::new(0.3, 0.3, 0.32, 1.);
+    for (x, y) in Rect::new(0, 0, self.grid.width(), self.grid.height()).tiles() {
+      let tile = match self.grid.get(x, y) {
+        Some(t) if t.is_walkable() => t,
+        _ => continue,
+      };
+      let (x0, z0) = (x as f32 * tile_size, y as f32 * tile_size);
+      let (x1, z1) = (x0 + tile_size, z0 + tile_size);
+      let color = match tile {
+        Tile::Corridor => Vec4f32::new(0.4, 0.4, 0.4, 1.),
+        Tile::Door => Vec4f32::new(0.45, 0.3, 0.15, 1.),
+        _ => Vec4f32::new(0.55, 0.55, 0.55, 1.),
+      };
+      push_quad(
+        &mut ret.floor,
+        [
+          Vec3f32::new(x0, 0., z0),
+          Vec3f32::new(x0, 0., z1),
+          Vec3f32::new(x1, 0., z1),
+          Vec3f32::new(x1, 0., z0),
+        ],
+        color,
+      );
+
+      // Edges listed so that the quad faces the inside of the tile
+      let edges = [
+        ((Some(x), y.checked_sub(1)), (x0, z0), (x1, z0)),
+        ((x.checked_add(1), Some(y)), (x1, z0), (x1, z1)),
+        ((Some(x), y.checked_add(1)), (x1, z1), (x0, z1)),
+        ((x.checked_sub(1), Some(y)), (x0, z1), (x0, z0)),
+      ];
+      for (neighbour, (px, pz), (qx, qz)) in edges {
+        let open = match neighbour {
+          (Some(nx), Some(ny)) => self.grid.is_walkable(nx, ny),
+          _ => false,
+        };
+        if !open {
+          push_quad(
+            &mut ret.walls,
+            [
+              Vec3f32::new(px, 0., pz),
+              Vec3f32::new(qx, 0., qz),
+              Vec3f32::new(qx, wall_height, qz),
+              Vec3f32::new(px, wall_height, pz),
+            ],
+            wall_color,
+          );
+        }
+      }
+    }
+    ret
+  }
+}
+
+/// Two counter clockwise triangles
+fn push_quad(list: &mut VertexList<f32>, corners: [Vec3f32; 4], color: Vec4f32) {
+  let uv = [
+    Vec3f32::new(0., 0., 0.),
+    Vec3f32::new(0., 1., 0.),
+    Vec3f32::new(1., 1., 0.),
+    Vec3f32::new(1., 0., 0.),
+  ];
+  for i in [0, 1, 2, 0, 2, 3] {
+    list.push_back(Vertex::new(corners[i], color, uv[i]));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Level;
+  use crate::{Grid, Seed, Tile, Vec3f32};
+
+  #[test]
+  fn mesh() {
+    let mut grid = Grid::new(4, 3);
+    grid.set(1, 1, Tile::Floor);
+    grid.set(2, 1, Tile::Door);
+    let level = Level::new(Seed::new(0), grid, vec![], vec![], vec![]);
+    let mesh = level.mesh(2., 3.);
+    assert_eq!(mesh.floor().len(), 2 * 6);
+    assert_eq!(mesh.walls().len(), 6 * 6);
+
+    let normal = |t: &[crate::Vertex<f32>]| {
+      (*t[1].position() - *t[0].position()).cross(&(*t[2].position() - *t[0].position()))
+    };
+    let floor: Vec<_> = mesh.floor().iter().copied().collect();
+    for t in floor.chunks(3) {
+      assert!(normal(t).y() > 0.);
+    }
+    let walls: Vec<_> = mesh.walls().iter().copied().collect();
+    let center = Vec3f32::new(4., 0., 3.);
+    for t in walls.chunks(3) {
+      let inward = center - *t[0].position();
+      assert!(normal(t).dot(&inward) > 0.);
+      assert!(t.iter().all(|v| v.position().y() <= 3.));
+    }
+  }
+}
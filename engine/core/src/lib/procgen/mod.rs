@@ -0,0 +1,8 @@
+pub mod cave;
+pub mod dungeon;
+pub mod grid;
+pub mod level;
+pub use cave::*;
+pub use dungeon::*;
+pub use grid::*;
+pub use level::*;
@@ -0,0 +1,4 @@
+pub mod noise;
+pub mod rng;
+pub use noise::*;
+pub use rng::*;
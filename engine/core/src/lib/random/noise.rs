@@ -0,0 +1,318 @@
+use crate::{Rng, Seed, Vector};
+
+/// Coherent noise sampled at points of 1 to 3 dimensions
+pub trait Noise<const N: usize> {
+  fn sample(&self, p: Vector<f32, N>) -> f32;
+
+  /// Sum of `octaves` samples, each `lacunarity` times the frequency and `gain` times the
+  /// amplitude of the previous one, normalized to the range of a single sample
+  fn fbm(&self, p: Vector<f32, N>, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+    let (mut sum, mut norm) = (0., 0.);
+    let (mut amplitude, mut frequency) = (1., 1.);
+    for _ in 0..octaves {
+      sum += amplitude * self.sample(p * frequency);
+      norm += amplitude;
+      amplitude *= gain;
+      frequency *= lacunarity;
+    }
+    if norm > 0. {
+      sum / norm
+    } else {
+      0.
+    }
+  }
+}
+
+fn mix(mut z: u64) -> u64 {
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ (z >> 31)
+}
+
+fn hash<const N: usize>(seed: u64, cell: &[i32; N], salt: usize) -> u64 {
+  let mut h = mix(seed ^ (salt as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+  for c in cell {
+    h = mix(h ^ *c as u32 as u64);
+  }
+  h
+}
+
+/// Value in `[0, 1)` attached to a lattice cell
+fn unit<const N: usize>(seed: u64, cell: &[i32; N], salt: usize) -> f32 {
+  (hash(seed, cell, salt) >> 40) as f32 / (1u32 << 24) as f32
+}
+
+fn gradient<const N: usize>(seed: u64, cell: &[i32; N]) -> Vector<f32, N> {
+  let mut g = Vector::from_parts(std::array::from_fn(|i| unit(seed, cell, i) * 2. - 1.));
+  if g.sq_magnitude() < 1e-6 {
+    g[0] = 1.;
+  }
+  g.normalize();
+  g
+}
+
+fn floor<const N: usize>(p: &Vector<f32, N>) -> [i32; N] {
+  std::array::from_fn(|i| p[i].floor() as i32)
+}
+
+fn fade(t: f32) -> f32 {
+  t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+/// Gradient noise on a square lattice, in `[-1, 1]` and zero on integer coordinates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Perlin {
+  seed: Seed,
+}
+
+impl Perlin {
+  pub fn new(seed: Seed) -> Self {
+    Self { seed }
+  }
+
+  pub fn from_rng(rng: &mut Rng) -> Self {
+    Self::new(Seed::new(rng.next_u64()))
+  }
+
+  pub fn seed(&self) -> Seed {
+    self.seed
+  }
+}
+
+impl<const N: usize> Noise<N> for Perlin {
+  fn sample(&self, p: Vector<f32, N>) -> f32 {
+    let base = floor(&p);
+    let frac = Vector::from_parts(std::array::from_fn(|i| p[i] - base[i] as f32));
+    let mut ret = 0.;
+    for corner in 0..1usize << N {
+      let (mut cell, mut offset, mut weight) = (base, frac, 1.);
+      for i in 0..N {
+        let t = fade(frac[i]);
+        if corner >> i & 1 == 1 {
+          cell[i] += 1;
+          offset[i] -= 1.;
+          weight *= t;
+        } else {
+          weight *= 1. - t;
+        }
+      }
+      ret += weight * gradient(self.seed.value(), &cell).dot(&offset);
+    }
+    // Unit gradients reach at most sqrt(N) / 2
+    (ret * 2. / (N as f32).sqrt()).clamp(-1., 1.)
+  }
+}
+
+/// Gradient noise on a simplex lattice, in `[-1, 1]` with fewer axis aligned artifacts than
+/// [`Perlin`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Simplex {
+  seed: Seed,
+}
+
+impl Simplex {
+  pub fn new(seed: Seed) -> Self {
+    Self { seed }
+  }
+
+  pub fn from_rng(rng: &mut Rng) -> Self {
+    Self::new(Seed::new(rng.next_u64()))
+  }
+
+  pub fn seed(&self) -> Seed {
+    self.seed
+  }
+
+  fn contribution<const N: usize>(&self, cell: &[i32; N], d: &Vector<f32, N>) -> f32 {
+    let t = 0.5 - d.sq_magnitude();
+    if t > 0. {
+      t * t * t * t * gradient(self.seed.value(), cell).dot(d)
+    } else {
+      0.
+    }
+  }
+}
+
+impl<const N: usize> Noise<N> for Simplex {
+  fn sample(&self, p: Vector<f32, N>) -> f32 {
+    let n = N as f32;
+    let skew = ((n + 1.).sqrt() - 1.) / n;
+    let unskew = (1. - 1. / (n + 1.).sqrt()) / n;
+
+    let s = p.iter().sum::<f32>() * skew;
+    let mut cell = floor(&p.map(|x| x + s));
+    let t = cell.iter().sum::<i32>() as f32 * unskew;
+    let d0 = Vector::from_parts(std::array::from_fn(|i| p[i] - (cell[i] as f32 - t)));
+
+    // The simplex containing the point is walked by stepping along its largest offsets first
+    let mut order: [usize; N] = std::array::from_fn(|i| i);
+    order.sort_by(|a, b| d0[*b].total_cmp(&d0[*a]));
+
+    let mut ret = self.contribution(&cell, &d0);
+    let mut d = d0;
+    for axis in order {
+      cell[axis] += 1;
+      d[axis] -= 1.;
+      d = d.map(|x| x + unskew);
+      ret += self.contribution(&cell, &d);
+    }
+    // Measured peaks over many seeds, the kernels add up to about 0.014, 0.0102 and 0.0094
+    let scale = match N {
+      1 => 71.5,
+      2 => 98.,
+      _ => 106.,
+    };
+    (ret * scale).clamp(-1., 1.)
+  }
+}
+
+/// Result of a [`Worley`] lookup
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WorleySample<const N: usize> {
+  f1: f32,
+  f2: f32,
+  point: Vector<f32, N>,
+}
+
+impl<const N: usize> WorleySample<N> {
+  /// Distance to the nearest feature point
+  pub fn f1(&self) -> f32 {
+    self.f1
+  }
+
+  /// Distance to the second nearest feature point
+  pub fn f2(&self) -> f32 {
+    self.f2
+  }
+
+  /// Nearest feature point
+  pub fn point(&self) -> Vector<f32, N> {
+    self.point
+  }
+}
+
+/// Cellular noise with one feature point per unit cell, sampled as the distance to the nearest
+/// one
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Worley {
+  seed: Seed,
+}
+
+impl Worley {
+  pub fn new(seed: Seed) -> Self {
+    Self { seed }
+  }
+
+  pub fn from_rng(rng: &mut Rng) -> Self {
+    Self::new(Seed::new(rng.next_u64()))
+  }
+
+  pub fn seed(&self) -> Seed {
+    self.seed
+  }
+
+  /// Feature point of a cell
+  pub fn feature<const N: usize>(&self, cell: [i32; N]) -> Vector<f32, N> {
+    Vector::from_parts(std::array::from_fn(|i| {
+      cell[i] as f32 + unit(self.seed.value(), &cell, i)
+    }))
+  }
+
+  /// Nearest feature points around `p`, looking at the neighbouring cells only
+  pub fn cell<const N: usize>(&self, p: Vector<f32, N>) -> WorleySample<N> {
+    let base = floor(&p);
+    let (mut f1, mut f2, mut point) = (f32::INFINITY, f32::INFINITY, p);
+    for neighbour in 0..3usize.pow(N as u32) {
+      let mut cell = base;
+      let mut digits = neighbour;
+      for c in cell.iter_mut() {
+        *c += (digits % 3) as i32 - 1;
+        digits /= 3;
+      }
+      let feature = self.feature(cell);
+      let d = feature.sq_distance(&p);
+      if d < f1 {
+        (f2, f1, point) = (f1, d, feature);
+      } else if d < f2 {
+        f2 = d;
+      }
+    }
+    WorleySample {
+      f1: f1.sqrt(),
+      f2: f2.sqrt(),
+      point,
+    }
+  }
+}
+
+impl<const N: usize> Noise<N> for Worley {
+  fn sample(&self, p: Vector<f32, N>) -> f32 {
+    self.cell(p).f1()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Noise, Perlin, Simplex, Worley};
+  use crate::{Rng, Seed, Vec1f32, Vec2f32, Vec3f32, Vector};
+
+  fn check_noise<const N: usize, T: Noise<N>>(noise: &T, rng: &mut Rng, range: (f32, f32)) {
+    let (mut lo, mut hi) = (f32::INFINITY, f32::NEG_INFINITY);
+    for _ in 0..2000 {
+      let p = rng.random::<Vector<f32, N>>() * 50. - Vector::from_parts([25.; N]);
+      let v = noise.sample(p);
+      assert!(v >= range.0 && v <= range.1, "{} out of {:?}", v, range);
+      // Continuous: a tiny step gives a tiny change
+      let step = noise.sample(p + Vector::from_parts([1e-3; N]));
+      assert!((v - step).abs() < 0.05, "{} -> {}", v, step);
+      lo = lo.min(v);
+      hi = hi.max(v);
+    }
+    // Not constant either
+    assert!(hi - lo > 0.5 * (range.1 - range.0).min(1.), "{} {}", lo, hi);
+  }
+
+  #[test]
+  fn gradient_noise() {
+    let mut rng = Rng::new(Seed::new(666));
+    let perlin = Perlin::from_rng(&mut rng);
+    let simplex = Simplex::from_rng(&mut rng);
+    check_noise::<1, _>(&perlin, &mut rng, (-1., 1.));
+    check_noise::<2, _>(&perlin, &mut rng, (-1., 1.));
+    check_noise::<3, _>(&perlin, &mut rng, (-1., 1.));
+    check_noise::<1, _>(&simplex, &mut rng, (-1., 1.));
+    check_noise::<2, _>(&simplex, &mut rng, (-1., 1.));
+    check_noise::<3, _>(&simplex, &mut rng, (-1., 1.));
+
+    assert_eq!(perlin.sample(Vec3f32::new(3., -2., 7.)), 0.);
+    let p = Vec2f32::new(1.3, 4.7);
+    assert_eq!(simplex.sample(p), Simplex::new(simplex.seed()).sample(p));
+    assert_ne!(
+      perlin.sample(Vec1f32::new(0.4)),
+      Perlin::new(Seed::new(1)).sample(Vec1f32::new(0.4))
+    );
+
+    let fbm = perlin.fbm(p, 4, 2., 0.5);
+    assert!((-1. ..=1.).contains(&fbm));
+    assert_eq!(perlin.fbm(p, 1, 2., 0.5), perlin.sample(p));
+  }
+
+  #[test]
+  fn worley() {
+    let mut rng = Rng::new(Seed::new(13));
+    let worley = Worley::from_rng(&mut rng);
+    check_noise::<2, _>(&worley, &mut rng, (0., 2f32.sqrt()));
+    check_noise::<3, _>(&worley, &mut rng, (0., 3f32.sqrt()));
+
+    let feature = worley.feature([2, -1, 5]);
+    let sample = worley.cell(feature);
+    assert_eq!(sample.f1(), 0.);
+    assert_eq!(sample.point(), feature);
+    assert!(sample.f2() > 0.);
+
+    let p = Vec3f32::new(0.5, 8.25, -3.);
+    let sample = worley.cell(p);
+    assert!(sample.f1() <= sample.f2());
+    assert_eq!(sample.f1(), sample.point().distance(&p));
+  }
+}
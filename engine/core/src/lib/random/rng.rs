@@ -0,0 +1,439 @@
+use std::{
+  fmt::Display,
+  ops::{Range, RangeInclusive},
+  str::FromStr,
+  sync::atomic::{AtomicU64, Ordering},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{err, Aabb, Error, ErrorKind, Float, Sphere, Vector};
+
+/// Starting point of a random sequence, record it to replay a run exactly
+///
+/// Displayed and parsed as 16 hexadecimal digits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Seed(u64);
+
+impl Seed {
+  pub fn new(value: u64) -> Self {
+    Self(value)
+  }
+
+  pub fn value(&self) -> u64 {
+    self.0
+  }
+
+  /// Seed that differs between calls and between runs
+  pub fn from_entropy() -> Self {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let time = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_nanos() as u64)
+      .unwrap_or_default();
+    let local = 0u8;
+    let mut state = time
+      ^ (&local as *const u8 as u64).rotate_left(32)
+      ^ COUNTER
+        .fetch_add(1, Ordering::Relaxed)
+        .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    Self(split_mix(&mut state))
+  }
+}
+
+impl From<u64> for Seed {
+  fn from(value: u64) -> Self {
+    Self(value)
+  }
+}
+
+impl Display for Seed {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:016x}", self.0)
+  }
+}
+
+impl FromStr for Seed {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let digits = s.trim();
+    let digits = digits.strip_prefix("0x").unwrap_or(digits);
+    match u64::from_str_radix(digits, 16) {
+      Ok(value) => Ok(Self(value)),
+      Err(e) => err!(
+        ErrorKind::InvalidInput,
+        format!("invalid seed {:?}: {}", s, e)
+      ),
+    }
+  }
+}
+
+fn split_mix(state: &mut u64) -> u64 {
+  *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+  let mut z = *state;
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ (z >> 31)
+}
+
+/// Deterministic xoshiro256** generator, not suitable for cryptography
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rng {
+  seed: Seed,
+  state: [u64; 4],
+}
+
+impl Rng {
+  pub fn new(seed: Seed) -> Self {
+    let mut sm = seed.value();
+    let state = [
+      split_mix(&mut sm),
+      split_mix(&mut sm),
+      split_mix(&mut sm),
+      split_mix(&mut sm),
+    ];
+    Self { seed, state }
+  }
+
+  pub fn from_entropy() -> Self {
+    Self::new(Seed::from_entropy())
+  }
+
+  /// Seed this generator was created from
+  pub fn seed(&self) -> Seed {
+    self.seed
+  }
+
+  /// Independent generator seeded from this one, to give a subsystem its own stream
+  pub fn fork(&mut self) -> Self {
+    Self::new(Seed::new(self.next_u64()))
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    let s = &mut self.state;
+    let ret = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+    let t = s[1] << 17;
+    s[2] ^= s[0];
+    s[3] ^= s[1];
+    s[1] ^= s[2];
+    s[0] ^= s[3];
+    s[2] ^= t;
+    s[3] = s[3].rotate_left(45);
+    ret
+  }
+
+  pub fn next_u32(&mut self) -> u32 {
+    (self.next_u64() >> 32) as u32
+  }
+
+  /// Uniform value below `n`, without modulo bias
+  pub fn below(&mut self, n: u64) -> u64 {
+    assert!(n > 0, "cannot sample below 0");
+    // Lemire's multiply and reject
+    let mut m = self.next_u64() as u128 * n as u128;
+    if (m as u64) < n {
+      let threshold = n.wrapping_neg() % n;
+      while (m as u64) < threshold {
+        m = self.next_u64() as u128 * n as u128;
+      }
+    }
+    (m >> 64) as u64
+  }
+
+  /// Random value of any type implementing [`Random`], floats are in `[0, 1)`
+  pub fn random<T: Random>(&mut self) -> T {
+    T::random(self)
+  }
+
+  /// Uniform value in `range`, panics if the range is empty
+  pub fn range<T, R: SampleRange<T>>(&mut self, range: R) -> T {
+    range.sample(self)
+  }
+
+  /// `true` with a probability of `p`
+  pub fn chance(&mut self, p: f64) -> bool {
+    self.random::<f64>() < p
+  }
+
+  pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+    if items.is_empty() {
+      None
+    } else {
+      Some(&items[self.below(items.len() as u64) as usize])
+    }
+  }
+
+  /// Index picked proportionally to its weight, negative and NaN weights count as zero
+  ///
+  /// Returns `None` when no weight is positive.
+  pub fn weighted_index(&mut self, weights: &[f64]) -> Option<usize> {
+    let weight = |w: f64| if w > 0. { w } else { 0. };
+    let total: f64 = weights.iter().map(|w| weight(*w)).sum();
+    if total <= 0. || !total.is_finite() {
+      return None;
+    }
+    let mut target = self.random::<f64>() * total;
+    let mut last = None;
+    for (i, w) in weights.iter().enumerate() {
+      let w = weight(*w);
+      if w > 0. {
+        if target < w {
+          return Some(i);
+        }
+        target -= w;
+        last = Some(i);
+      }
+    }
+    // Rounding can leave a tiny remainder past the last positive weight
+    last
+  }
+
+  pub fn choose_weighted<'a, T, F: Fn(&T) -> f64>(
+    &mut self,
+    items: &'a [T],
+    weight: F,
+  ) -> Option<&'a T> {
+    let weights: Vec<f64> = items.iter().map(weight).collect();
+    self.weighted_index(&weights).map(|i| &items[i])
+  }
+
+  /// Fisher-Yates shuffle
+  pub fn shuffle<T>(&mut self, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+      let j = self.below(i as u64 + 1) as usize;
+      items.swap(i, j);
+    }
+  }
+
+  pub fn point_in_aabb<T: Float + Random>(&mut self, aabb: &Aabb<T>) -> Vector<T, 3> {
+    aabb.min() + (aabb.max() - aabb.min()) * self.random::<Vector<T, 3>>()
+  }
+
+  pub fn point_in_sphere<T: Float + Random>(&mut self, sphere: &Sphere<T>) -> Vector<T, 3> {
+    sphere.center() + self.in_unit_ball::<T, 3>() * sphere.radius()
+  }
+
+  /// Uniformly distributed direction of length 1
+  pub fn direction<T: Float + Random, const N: usize>(&mut self) -> Vector<T, N> {
+    loop {
+      let mut v = self.in_unit_ball::<T, N>();
+      // Tiny vectors lose their direction once normalized
+      if v.sq_magnitude() > T::epsilon() {
+        v.normalize();
+        return v;
+      }
+    }
+  }
+
+  fn in_unit_ball<T: Float + Random, const N: usize>(&mut self) -> Vector<T, N> {
+    let one = T::unit();
+    loop {
+      let v = self.random::<Vector<T, N>>().map(|p| p * (one + one) - one);
+      if v.sq_magnitude() <= one {
+        return v;
+      }
+    }
+  }
+}
+
+/// Types that can be drawn from an [`Rng`]
+pub trait Random {
+  fn random(rng: &mut Rng) -> Self;
+}
+
+impl Random for bool {
+  fn random(rng: &mut Rng) -> Self {
+    rng.next_u64() >> 63 == 1
+  }
+}
+
+impl Random for f32 {
+  fn random(rng: &mut Rng) -> Self {
+    (rng.next_u32() >> 8) as f32 * (1. / (1u32 << 24) as f32)
+  }
+}
+
+impl Random for f64 {
+  fn random(rng: &mut Rng) -> Self {
+    (rng.next_u64() >> 11) as f64 * (1. / (1u64 << 53) as f64)
+  }
+}
+
+impl<T: Random, const N: usize> Random for Vector<T, N> {
+  fn random(rng: &mut Rng) -> Self {
+    Vector::from_parts(std::array::from_fn(|_| T::random(rng)))
+  }
+}
+
+/// Ranges that [`Rng::range`] can sample from
+pub trait SampleRange<T> {
+  fn sample(self, rng: &mut Rng) -> T;
+}
+
+macro_rules! int_random_impl {
+  ( $(($typ:ty, $unsigned:ty)),* ) => {
+    $(
+    impl Random for $typ {
+      fn random(rng: &mut Rng) -> Self {
+        rng.next_u64() as $typ
+      }
+    }
+
+    impl SampleRange<$typ> for Range<$typ> {
+      fn sample(self, rng: &mut Rng) -> $typ {
+        assert!(self.start < self.end, "cannot sample empty range");
+        let span = self.end.wrapping_sub(self.start) as $unsigned as u64;
+        self.start.wrapping_add(rng.below(span) as $typ)
+      }
+    }
+
+    impl SampleRange<$typ> for RangeInclusive<$typ> {
+      fn sample(self, rng: &mut Rng) -> $typ {
+        let (start, end) = self.into_inner();
+        assert!(start <= end, "cannot sample empty range");
+        let span = (end.wrapping_sub(start) as $unsigned as u64).wrapping_add(1);
+        if span == 0 {
+          // The range covers every 64 bits value
+          rng.next_u64() as $typ
+        } else {
+          start.wrapping_add(rng.below(span) as $typ)
+        }
+      }
+    }
+    )*
+  };
+}
+
+int_random_impl!(
+  (u8, u8),
+  (u16, u16),
+  (u32, u32),
+  (u64, u64),
+  (usize, usize),
+  (i8, u8),
+  (i16, u16),
+  (i32, u32),
+  (i64, u64),
+  (isize, usize)
+);
+
+macro_rules! float_random_impl {
+  ( $($typ:ty),* ) => {
+    $(
+    impl SampleRange<$typ> for Range<$typ> {
+      fn sample(self, rng: &mut Rng) -> $typ {
+        assert!(self.start < self.end, "cannot sample empty range");
+        let ret = self.start + (self.end - self.start) * rng.random::<$typ>();
+        // Rounding can land on the excluded end
+        if ret < self.end {
+          ret
+        } else {
+          self.start
+        }
+      }
+    }
+
+    impl SampleRange<$typ> for RangeInclusive<$typ> {
+      fn sample(self, rng: &mut Rng) -> $typ {
+        let (start, end) = self.into_inner();
+        assert!(start <= end, "cannot sample empty range");
+        (start + (end - start) * rng.random::<$typ>()).min(end)
+      }
+    }
+    )*
+  };
+}
+
+float_random_impl!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+  use super::{Rng, Seed};
+  use crate::{Aabbf32, Spheref32, Vec3f32};
+
+  #[test]
+  fn deterministic() {
+    let mut a = Rng::new(Seed::new(42));
+    let mut b = Rng::new(Seed::new(42));
+    let first: Vec<u64> = (0..16).map(|_| a.next_u64()).collect();
+    assert_eq!(first, (0..16).map(|_| b.next_u64()).collect::<Vec<_>>());
+
+    let mut c = Rng::new(Seed::new(43));
+    assert_ne!(first, (0..16).map(|_| c.next_u64()).collect::<Vec<_>>());
+
+    let mut replay = Rng::new(a.seed());
+    assert_eq!(replay.next_u64(), first[0]);
+
+    let seed = Seed::from_entropy();
+    assert_ne!(seed, Seed::from_entropy());
+    assert_eq!(seed.to_string().parse::<Seed>().unwrap(), seed);
+    assert_eq!("0x2a".parse::<Seed>().unwrap(), Seed::new(42));
+    assert!("spooky".parse::<Seed>().is_err());
+  }
+
+  #[test]
+  fn ranges() {
+    let mut rng = Rng::new(Seed::new(7));
+    let mut counts = [0; 6];
+    for _ in 0..6000 {
+      counts[rng.range(0..6usize)] += 1;
+      let v = rng.range(-3..=3i8);
+      assert!((-3..=3).contains(&v));
+      let f = rng.range(1.5..2.5f32);
+      assert!((1.5..2.5).contains(&f));
+      assert!((0. ..1.).contains(&rng.random::<f64>()));
+    }
+    assert!(
+      counts.iter().all(|c| (900..1100).contains(c)),
+      "{:?}",
+      counts
+    );
+    assert_eq!(rng.range(5..=5u32), 5);
+    rng.range(i64::MIN..=i64::MAX);
+    rng.range(0..=u8::MAX);
+  }
+
+  #[test]
+  fn choices() {
+    let mut rng = Rng::new(Seed::new(1));
+    assert_eq!(rng.choose::<u8>(&[]), None);
+    assert_eq!(rng.weighted_index(&[0., -1.]), None);
+
+    let mut counts = [0; 3];
+    for _ in 0..4000 {
+      counts[rng.weighted_index(&[1., 0., 3.]).unwrap()] += 1;
+    }
+    assert_eq!(counts[1], 0);
+    assert!((2800..3200).contains(&counts[2]), "{:?}", counts);
+    assert_eq!(
+      rng.choose_weighted(&["ghost", "zombie"], |m| if *m == "ghost" {
+        1.
+      } else {
+        0.
+      }),
+      Some(&"ghost")
+    );
+
+    let mut items: Vec<u32> = (0..50).collect();
+    rng.shuffle(&mut items);
+    assert_ne!(items, (0..50).collect::<Vec<_>>());
+    items.sort();
+    assert_eq!(items, (0..50).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn points() {
+    let mut rng = Rng::new(Seed::new(3));
+    let aabb = Aabbf32::new(Vec3f32::new(-1., 2., 3.), Vec3f32::new(0., 4., 3.5));
+    let sphere = Spheref32::new(Vec3f32::new(10., 0., 0.), 2.);
+    for _ in 0..1000 {
+      assert!(aabb.contains(rng.point_in_aabb(&aabb)));
+      assert!(sphere.contains(rng.point_in_sphere(&sphere)));
+      let d: Vec3f32 = rng.direction();
+      assert!((d.magnitude() - 1.).abs() < 1e-5);
+    }
+  }
+}
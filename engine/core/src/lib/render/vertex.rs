@@ -8,7 +8,9 @@ use crate::Vector;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex<T = f32, P = Vector<T, 3>, C = Vector<T, 4>, TC = Vector<T, 3>> {
+  #[cfg_attr(feature = "serde", serde(skip))]
   _phantom: PhantomData<T>,
   position: P,
   color: C,
@@ -0,0 +1,645 @@
+use std::{
+  any::TypeId,
+  collections::{HashMap, VecDeque},
+  fs::File,
+  io::{BufRead, BufReader, BufWriter, Write},
+  path::Path,
+  time::Duration,
+};
+
+use crate::{
+  err, here, CustomEvent, Error, ErrorKind, Event, EventBus, EventContext, EventListener,
+  EventOrigin, FakeClock,
+};
+
+/// First line of a recording
+pub const RECORDING_HEADER: &str = "rhg-events 1";
+
+/// Priority letting an [`EventRecorder`] see events before any listener handles them
+pub const RECORDER_PRIORITY: i32 = i32::MAX;
+
+/// Custom event that can be written to a recording
+pub trait RecordableEvent: CustomEvent + Sized {
+  /// Identifies the type in recordings, without whitespace
+  const NAME: &'static str;
+
+  fn encode(&self) -> String;
+  fn decode(payload: &str) -> crate::Result<Self>;
+}
+
+type Encoder = fn(&Event) -> String;
+type Decoder = fn(&str) -> crate::Result<Event>;
+
+/// Recorded name of an engine event, `None` for custom events
+fn engine_name(e: &Event) -> Option<&'static str> {
+  match e {
+    Event::EngineInitStarted => Some("EngineInitStarted"),
+    Event::EngineInitStopped => Some("EngineInitStopped"),
+    Event::Custom(_) => None,
+  }
+}
+
+/// Engine event recorded as `name`, the inverse of [`engine_name`]
+fn engine_event(name: &str) -> Option<Event> {
+  match name {
+    "EngineInitStarted" => Some(Event::EngineInitStarted),
+    "EngineInitStopped" => Some(Event::EngineInitStopped),
+    _ => None,
+  }
+}
+
+/// Event types a recording can hold, engine events are always known
+#[derive(Debug, Clone, Default)]
+pub struct EventCodec {
+  encoders: HashMap<TypeId, (&'static str, Encoder)>,
+  decoders: HashMap<&'static str, (TypeId, Decoder)>,
+}
+
+impl EventCodec {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Panics if the name of `T` is invalid or already taken by another type
+  pub fn register<T: RecordableEvent>(&mut self) {
+    let ty = TypeId::of::<T>();
+    assert!(
+      !T::NAME.is_empty() && !T::NAME.contains(char::is_whitespace),
+      "invalid event name '{}'",
+      T::NAME
+    );
+    assert!(
+      engine_event(T::NAME).is_none() && self.decoders.get(T::NAME).is_none_or(|(t, _)| *t == ty),
+      "event name '{}' registered twice",
+      T::NAME
+    );
+    self.encoders.insert(ty, (T::NAME, encode_as::<T>));
+    self.decoders.insert(T::NAME, (ty, decode_as::<T>));
+  }
+
+  pub fn with_event<T: RecordableEvent>(mut self) -> Self {
+    self.register::<T>();
+    self
+  }
+
+  /// Name and payload of `e`, `None` for custom events of an unregistered type
+  pub fn encode(&self, e: &Event) -> Option<(&'static str, String)> {
+    if let Some(name) = engine_name(e) {
+      return Some((name, String::new()));
+    }
+    let (name, encoder) = self.encoders.get(&e.payload_type())?;
+    Some((name, encoder(e)))
+  }
+
+  pub fn decode(&self, name: &str, payload: &str) -> crate::Result<Event> {
+    if let Some(e) = engine_event(name) {
+      return Ok(e);
+    }
+    match self.decoders.get(name) {
+      Some((_, decoder)) => decoder(payload),
+      None => err!(ErrorKind::InvalidInput, format!("unknown event '{}'", name)),
+    }
+  }
+}
+
+fn encode_as<T: RecordableEvent>(e: &Event) -> String {
+  e.downcast_ref::<T>().map(T::encode).unwrap_or_default()
+}
+
+fn decode_as<T: RecordableEvent>(payload: &str) -> crate::Result<Event> {
+  T::decode(payload).map(Event::custom)
+}
+
+/// Event seen by an [`EventRecorder`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+  frame: u64,
+  time: Duration,
+  origin: EventOrigin,
+  event: Event,
+}
+
+impl RecordedEvent {
+  pub fn new(frame: u64, time: Duration, origin: EventOrigin, event: Event) -> Self {
+    Self {
+      frame,
+      time,
+      origin,
+      event,
+    }
+  }
+
+  pub fn frame(&self) -> u64 {
+    self.frame
+  }
+
+  /// Game time of the propagation
+  pub fn time(&self) -> Duration {
+    self.time
+  }
+
+  pub fn origin(&self) -> EventOrigin {
+    self.origin
+  }
+
+  pub fn event(&self) -> &Event {
+    &self.event
+  }
+
+  /// `frame time_ns origin name [payload]`, `None` if the codec does not know the event
+  fn to_line(&self, codec: &EventCodec) -> Option<String> {
+    let (name, payload) = codec.encode(&self.event)?;
+    let mut ret = format!(
+      "{} {} {} {}",
+      self.frame,
+      self.time.as_nanos(),
+      origin_name(self.origin),
+      name
+    );
+    if !payload.is_empty() {
+      ret.push(' ');
+      ret.push_str(&escape(&payload));
+    }
+    Some(ret)
+  }
+
+  fn parse(codec: &EventCodec, line: &str) -> crate::Result<Self> {
+    let mut fields = line.splitn(5, ' ');
+    let (Some(frame), Some(time), Some(origin), Some(name)) =
+      (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+      return err!(ErrorKind::Corrupted, "missing fields");
+    };
+    let (Ok(frame), Ok(time)) = (frame.parse(), time.parse()) else {
+      return err!(ErrorKind::Corrupted, "invalid frame or time");
+    };
+    let Some(origin) = parse_origin(origin) else {
+      return err!(ErrorKind::Corrupted, format!("invalid origin '{}'", origin));
+    };
+    let payload = unescape(fields.next().unwrap_or_default())?;
+    Ok(Self::new(
+      frame,
+      Duration::from_nanos(time),
+      origin,
+      codec.decode(name, &payload)?,
+    ))
+  }
+}
+
+/// `frame time_ns` line of a frame holding only skipped events, `None` for event lines
+fn parse_frame_time(line: &str) -> Option<crate::Result<(u64, Duration)>> {
+  let (frame, time) = line.split_once(' ')?;
+  if time.contains(' ') {
+    return None;
+  }
+  Some(match (frame.parse(), time.parse()) {
+    (Ok(frame), Ok(time)) => Ok((frame, Duration::from_nanos(time))),
+    _ => err!(ErrorKind::Corrupted, "invalid frame or time"),
+  })
+}
+
+fn origin_name(origin: EventOrigin) -> &'static str {
+  match origin {
+    EventOrigin::Input => "input",
+    EventOrigin::Scheduled => "scheduled",
+    EventOrigin::Emitted => "emitted",
+  }
+}
+
+fn parse_origin(s: &str) -> Option<EventOrigin> {
+  match s {
+    "input" => Some(EventOrigin::Input),
+    "scheduled" => Some(EventOrigin::Scheduled),
+    "emitted" => Some(EventOrigin::Emitted),
+    _ => None,
+  }
+}
+
+/// Keeps each event on its own line
+fn escape(s: &str) -> String {
+  s.replace('\\', "\\\\")
+    .replace('\n', "\\n")
+    .replace('\r', "\\r")
+}
+
+fn unescape(s: &str) -> crate::Result<String> {
+  let mut ret = String::with_capacity(s.len());
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      ret.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('\\') => ret.push('\\'),
+      Some('n') => ret.push('\n'),
+      Some('r') => ret.push('\r'),
+      _ => return err!(ErrorKind::Corrupted, "invalid escape sequence"),
+    }
+  }
+  Ok(ret)
+}
+
+/// Listener writing every event it receives to `W`, one per line. Register it with
+/// [`RECORDER_PRIORITY`] to see events before they are handled.
+///
+/// A frame whose events are all skipped is written as a `frame time_ns` line, so that a replay
+/// still follows its game time.
+///
+/// The writer is flushed at the start of each frame, a crash only loses the current one.
+pub struct EventRecorder<W: Write> {
+  codec: EventCodec,
+  writer: W,
+  frame: Option<u64>,
+  frame_written: bool,
+  recorded: usize,
+  skipped: usize,
+}
+
+impl<W: Write> EventRecorder<W> {
+  /// Writes the header right away
+  pub fn new(codec: EventCodec, mut writer: W) -> crate::Result<Self> {
+    writeln!(writer, "{}", RECORDING_HEADER)?;
+    Ok(Self {
+      codec,
+      writer,
+      frame: None,
+      frame_written: false,
+      recorded: 0,
+      skipped: 0,
+    })
+  }
+
+  pub fn codec(&self) -> &EventCodec {
+    &self.codec
+  }
+
+  pub fn recorded(&self) -> usize {
+    self.recorded
+  }
+
+  /// Custom events of unregistered types, they are left out of the recording
+  pub fn skipped(&self) -> usize {
+    self.skipped
+  }
+
+  pub fn flush(&mut self) -> crate::Result<()> {
+    Ok(self.writer.flush()?)
+  }
+
+  pub fn into_inner(self) -> W {
+    self.writer
+  }
+}
+
+impl EventRecorder<BufWriter<File>> {
+  pub fn create<P: AsRef<Path>>(codec: EventCodec, path: P) -> crate::Result<Self> {
+    let f = File::create(&path).map_err(|e| {
+      Error::new(
+        ErrorKind::IO,
+        format!(
+          "failed to create recording '{}', {}",
+          path.as_ref().display(),
+          e
+        ),
+        None,
+        here!(),
+      )
+    })?;
+    Self::new(codec, BufWriter::new(f))
+  }
+}
+
+impl<W: Write> EventListener for EventRecorder<W> {
+  fn on_event(&mut self, value: &Event, ctx: &mut EventContext) -> crate::Result<()> {
+    if self.frame != Some(ctx.frame()) {
+      if self.frame.is_some() {
+        self.flush()?;
+      }
+      self.frame = Some(ctx.frame());
+      self.frame_written = false;
+    }
+    let record = RecordedEvent::new(ctx.frame(), ctx.time(), ctx.origin(), value.clone());
+    match record.to_line(&self.codec) {
+      Some(line) => {
+        writeln!(self.writer, "{}", line)?;
+        self.recorded += 1;
+      }
+      None => {
+        if !self.frame_written {
+          writeln!(self.writer, "{} {}", ctx.frame(), ctx.time().as_nanos())?;
+        }
+        self.skipped += 1;
+      }
+    }
+    self.frame_written = true;
+    Ok(())
+  }
+}
+
+/// Recorded events fed back to a bus in place of live input
+///
+/// Only [`EventOrigin::Input`] events are queued, the listeners produce the other ones again.
+#[derive(Debug)]
+pub struct EventReplay {
+  events: VecDeque<RecordedEvent>,
+  /// Game time of the frames holding only skipped events
+  frame_times: VecDeque<(u64, Duration)>,
+  clock: FakeClock,
+}
+
+impl EventReplay {
+  pub fn new(events: impl IntoIterator<Item = RecordedEvent>) -> Self {
+    Self {
+      events: events.into_iter().collect(),
+      frame_times: VecDeque::new(),
+      clock: FakeClock::new(),
+    }
+  }
+
+  pub fn load<R: BufRead>(codec: &EventCodec, r: R) -> crate::Result<Self> {
+    let mut lines = r.lines();
+    if lines.next().transpose()?.as_deref() != Some(RECORDING_HEADER) {
+      return err!(ErrorKind::Corrupted, "not an event recording");
+    }
+    let mut events = vec![];
+    let mut frame_times = VecDeque::new();
+    for (i, line) in lines.enumerate() {
+      let line = line?;
+      if line.is_empty() {
+        continue;
+      }
+      let invalid = |e| {
+        Error::new(
+          ErrorKind::Corrupted,
+          format!("invalid event at line {}", i + 2),
+          Some(e),
+          here!(),
+        )
+      };
+      match parse_frame_time(&line) {
+        Some(frame_time) => frame_times.push_back(frame_time.map_err(invalid)?),
+        None => events.push(RecordedEvent::parse(codec, &line).map_err(invalid)?),
+      }
+    }
+    let mut ret = Self::new(events);
+    ret.frame_times = frame_times;
+    Ok(ret)
+  }
+
+  pub fn load_file<P: AsRef<Path>>(codec: &EventCodec, path: P) -> crate::Result<Self> {
+    let f = File::open(&path)?;
+    Self::load(codec, BufReader::new(f)).map_err(|e| {
+      Error::new(
+        e.kind(),
+        format!("failed to load recording '{}'", path.as_ref().display()),
+        Some(e),
+        here!(),
+      )
+    })
+  }
+
+  /// Events left to replay, including the ones produced by listeners
+  pub fn events(&self) -> &VecDeque<RecordedEvent> {
+    &self.events
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.events.is_empty() && self.frame_times.is_empty()
+  }
+
+  /// Follows the recorded game time, drive the replayed bus with it through
+  /// [`crate::EngineClock::from_source`] so that timers fire in the same frames
+  pub fn clock(&self) -> FakeClock {
+    self.clock.clone()
+  }
+
+  /// Sets the clock to the time recorded for the next frame of `bus` and queues its input
+  /// events, call it before each propagation. Returns the number of queued events.
+  pub fn queue_frame<B: EventBus + ?Sized>(&mut self, bus: &mut B) -> usize {
+    let frame = bus.frame();
+    while let Some(&(f, time)) = self.frame_times.front() {
+      if f > frame {
+        break;
+      }
+      if f == frame {
+        self.clock.set(time);
+      }
+      self.frame_times.pop_front();
+    }
+    let mut ret = 0;
+    while let Some(record) = self.events.pop_front() {
+      if record.frame > frame {
+        self.events.push_front(record);
+        break;
+      }
+      if record.frame < frame {
+        log::warn!(
+          "{:?} skipped, recorded for frame {}",
+          record.event,
+          record.frame
+        );
+        continue;
+      }
+      self.clock.set(record.time);
+      if record.origin == EventOrigin::Input {
+        bus.queue_event(record.event);
+        ret += 1;
+      }
+    }
+    ret
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{cell::RefCell, rc::Rc, time::Duration};
+
+  use super::{EventCodec, EventRecorder, EventReplay, RecordableEvent, RECORDER_PRIORITY};
+  use crate::{
+    err, EngineClock, ErrorKind, Event, EventBus, EventBusExt, EventOrigin, FakeClock, StdEventBus,
+  };
+
+  #[derive(Debug, PartialEq)]
+  struct KeyPressed(char);
+
+  impl RecordableEvent for KeyPressed {
+    const NAME: &'static str = "KeyPressed";
+
+    fn encode(&self) -> String {
+      self.0.to_string()
+    }
+
+    fn decode(payload: &str) -> crate::Result<Self> {
+      match payload.chars().next() {
+        Some(c) => Ok(Self(c)),
+        None => err!(ErrorKind::InvalidInput, "missing key"),
+      }
+    }
+  }
+
+  #[derive(Debug, PartialEq)]
+  struct Moved(u32);
+
+  impl RecordableEvent for Moved {
+    const NAME: &'static str = "Moved";
+
+    fn encode(&self) -> String {
+      self.0.to_string()
+    }
+
+    fn decode(payload: &str) -> crate::Result<Self> {
+      match payload.parse() {
+        Ok(distance) => Ok(Self(distance)),
+        Err(_) => err!(ErrorKind::InvalidInput, "invalid distance"),
+      }
+    }
+  }
+
+  #[derive(Debug, PartialEq)]
+  struct Unrecorded;
+
+  fn codec() -> EventCodec {
+    EventCodec::new()
+      .with_event::<KeyPressed>()
+      .with_event::<Moved>()
+  }
+
+  /// Bus running the game logic, with a recorder seeing all its events
+  fn game(clock: &FakeClock) -> (StdEventBus, Rc<RefCell<EventRecorder<Vec<u8>>>>) {
+    let mut bus = StdEventBus::default().with_clock(EngineClock::from_source(clock.clone()));
+    let recorder = Rc::new(RefCell::new(EventRecorder::new(codec(), vec![]).unwrap()));
+    bus
+      .add_listener_with_priority(recorder.clone(), RECORDER_PRIORITY)
+      .detach();
+    bus
+      .subscribe::<KeyPressed>(|e, ctx| {
+        ctx.set_handled();
+        match e.0 {
+          'a' => ctx.emit_after(Moved(1), Duration::from_secs(2)),
+          'b' => ctx.emit_after(Unrecorded, Duration::from_secs(1)),
+          _ => ctx.emit(Moved(e.0 as u32)),
+        }
+        Ok(())
+      })
+      .detach();
+    bus
+      .subscribe::<Unrecorded>(|_, ctx| {
+        ctx.emit_after(Moved(3), Duration::from_millis(1500));
+        Ok(())
+      })
+      .detach();
+    (bus, recorder)
+  }
+
+  fn recording(bus: StdEventBus, recorder: Rc<RefCell<EventRecorder<Vec<u8>>>>) -> String {
+    drop(bus);
+    let recorder = Rc::try_unwrap(recorder).ok().unwrap().into_inner();
+    String::from_utf8(recorder.into_inner()).unwrap()
+  }
+
+  #[test]
+  fn record_and_replay() {
+    let clock = FakeClock::new();
+    let (mut bus, recorder) = game(&clock);
+    for frame in 0..4 {
+      match frame {
+        0 => {
+          bus.queue_event(Event::EngineInitStarted);
+          bus.emit(KeyPressed('a'));
+        }
+        2 => {
+          bus.emit(KeyPressed('\n'));
+          bus.emit(Unrecorded);
+        }
+        _ => (),
+      }
+      bus.propagate().unwrap();
+      clock.advance(Duration::from_secs(1));
+    }
+    assert_eq!(recorder.borrow().recorded(), 5);
+    assert_eq!(recorder.borrow().skipped(), 1);
+    let live = recording(bus, recorder);
+    assert_eq!(
+      live.lines().collect::<Vec<_>>(),
+      [
+        "rhg-events 1",
+        "0 0 input EngineInitStarted",
+        "0 0 input KeyPressed a",
+        "2 2000000000 input KeyPressed \\n",
+        "2 2000000000 scheduled Moved 1",
+        "2 2000000000 emitted Moved 10",
+      ]
+    );
+
+    let mut replay = EventReplay::load(&codec(), live.as_bytes()).unwrap();
+    assert_eq!(replay.events().len(), 5);
+    assert_eq!(replay.events()[2].event(), &Event::custom(KeyPressed('\n')));
+    assert_eq!(replay.events()[3].origin(), EventOrigin::Scheduled);
+
+    let (mut bus, recorder) = game(&replay.clock());
+    while !replay.is_finished() {
+      replay.queue_frame(&mut bus);
+      bus.propagate().unwrap();
+    }
+    assert_eq!(recording(bus, recorder), live);
+  }
+
+  #[test]
+  fn skipped_frames() {
+    let clock = FakeClock::new();
+    let (mut bus, recorder) = game(&clock);
+    bus.emit(KeyPressed('b'));
+    for _ in 0..4 {
+      bus.propagate().unwrap();
+      clock.advance(Duration::from_secs(1));
+    }
+    assert_eq!(recorder.borrow().skipped(), 1);
+    let live = recording(bus, recorder);
+    assert_eq!(
+      live.lines().collect::<Vec<_>>(),
+      [
+        "rhg-events 1",
+        "0 0 input KeyPressed b",
+        "1 1000000000",
+        "3 3000000000 scheduled Moved 3",
+      ]
+    );
+
+    // The timer set while handling the skipped event is due at the recorded time
+    let mut replay = EventReplay::load(&codec(), live.as_bytes()).unwrap();
+    let (mut bus, recorder) = game(&replay.clock());
+    while !replay.is_finished() {
+      replay.queue_frame(&mut bus);
+      bus.propagate().unwrap();
+    }
+    assert_eq!(recording(bus, recorder), live);
+  }
+
+  #[test]
+  fn corrupted() {
+    let codec = codec();
+    assert!(EventReplay::load(&codec, "".as_bytes()).is_err());
+    for line in [
+      "0 0 input Jumped",
+      "0 x input Moved 1",
+      "0 0 sent Moved 1",
+      "0 0 input",
+      "0 x",
+    ] {
+      let e =
+        EventReplay::load(&codec, format!("rhg-events 1\n{}\n", line).as_bytes()).unwrap_err();
+      assert_eq!(e.kind(), ErrorKind::Corrupted, "{}", line);
+    }
+  }
+
+  #[test]
+  fn engine_events() {
+    let codec = EventCodec::default();
+    for e in [Event::EngineInitStarted, Event::EngineInitStopped] {
+      let (name, payload) = codec.encode(&e).unwrap();
+      assert_eq!(codec.decode(name, &payload).unwrap(), e);
+    }
+    assert!(codec.encode(&Event::custom(KeyPressed('a'))).is_none());
+  }
+}
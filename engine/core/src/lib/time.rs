@@ -0,0 +1,136 @@
+use std::{
+  cell::Cell,
+  rc::Rc,
+  time::{Duration, Instant},
+};
+
+pub trait Clock {
+  /// Time elapsed since the clock started
+  fn now(&self) -> Duration;
+}
+
+/// Wall clock time
+#[derive(Debug, Copy, Clone)]
+pub struct SystemClock(Instant);
+
+impl SystemClock {
+  pub fn new() -> Self {
+    Self(Instant::now())
+  }
+}
+
+impl Default for SystemClock {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Clock for SystemClock {
+  fn now(&self) -> Duration {
+    self.0.elapsed()
+  }
+}
+
+/// Manually driven time for tests, clones share the same time
+#[derive(Debug, Clone, Default)]
+pub struct FakeClock(Rc<Cell<Duration>>);
+
+impl FakeClock {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn advance(&self, d: Duration) {
+    self.0.set(self.0.get() + d);
+  }
+
+  pub fn set(&self, now: Duration) {
+    self.0.set(now);
+  }
+}
+
+impl Clock for FakeClock {
+  fn now(&self) -> Duration {
+    self.0.get()
+  }
+}
+
+/// Game time, stands still while the game is paused
+pub struct EngineClock {
+  source: Box<dyn Clock>,
+  paused_at: Option<Duration>,
+  paused_total: Duration,
+}
+
+impl EngineClock {
+  /// Driven by a [`SystemClock`]
+  pub fn new() -> Self {
+    Self::from_source(SystemClock::new())
+  }
+
+  pub fn from_source(source: impl Clock + 'static) -> Self {
+    Self {
+      source: Box::new(source),
+      paused_at: None,
+      paused_total: Duration::ZERO,
+    }
+  }
+
+  pub fn pause(&mut self) {
+    if self.paused_at.is_none() {
+      self.paused_at = Some(self.source.now());
+    }
+  }
+
+  pub fn resume(&mut self) {
+    if let Some(at) = self.paused_at.take() {
+      self.paused_total += self.source.now().saturating_sub(at);
+    }
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused_at.is_some()
+  }
+}
+
+impl Default for EngineClock {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Clock for EngineClock {
+  fn now(&self) -> Duration {
+    self
+      .paused_at
+      .unwrap_or_else(|| self.source.now())
+      .saturating_sub(self.paused_total)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{Clock, EngineClock, FakeClock, SystemClock};
+
+  #[test]
+  fn pause() {
+    let fake = FakeClock::new();
+    let mut clock = EngineClock::from_source(fake.clone());
+    fake.advance(Duration::from_secs(2));
+    assert_eq!(clock.now(), Duration::from_secs(2));
+
+    clock.pause();
+    fake.advance(Duration::from_secs(5));
+    assert!(clock.is_paused());
+    assert_eq!(clock.now(), Duration::from_secs(2));
+
+    clock.resume();
+    fake.advance(Duration::from_secs(1));
+    assert_eq!(clock.now(), Duration::from_secs(3));
+
+    let system = SystemClock::new();
+    assert!(system.now() <= system.now());
+  }
+}
@@ -1,26 +1,30 @@
 use chrono::{DateTime, Utc};
 use clap::Parser;
 use rhg_pack::{
-  AddCommandOptions, CliOptions, Command, ExtractCommandOptions, Filter, ListCommandOptions,
-  RemoveCommandOptions, UpdateCommandOptions,
+  export_file, import_file, rename_entries, verbosity_level, AddCommandOptions, CliOptions,
+  Command, ExportCommandOptions, ExtractCommandOptions, Filter, FilterSetOptions,
+  ImportCommandOptions, ListCommandOptions, MoveCommandOptions, PackExitCode, PackLogger,
+  PackWatcher, Packer, RemoveCommandOptions, UpdateCommandOptions, WatchCommandOptions,
 };
 use std::{
+  collections::HashMap,
   io::{stdout, Stdout},
   ops::{Deref, DerefMut},
   path::{Path, PathBuf},
   process::{exit, ExitCode, ExitStatus},
   str::FromStr,
-  time::SystemTime,
+  time::{Duration, SystemTime},
 };
 
 use rhg_engine_core::{err, here, Archive, ArchiveFile, Error, ErrorKind};
 
 fn add(opt: &AddCommandOptions) -> rhg_engine_core::Result<()> {
-  let mut a = Archive::default();
-  for f in &opt.files {
-    a.add(ArchiveFile::load(f)?)?;
+  let mut packer = Packer::default().with_progress(log::log_enabled!(log::Level::Info));
+  if let Some(jobs) = opt.jobs {
+    packer = packer.with_jobs(jobs);
   }
-  a.save_file(&opt.archive)?;
+  let summary = packer.pack(&opt.archive, &opt.files)?;
+  log::info!("packed {}: {}", opt.archive.display(), summary);
   Ok(())
 }
 
@@ -30,7 +34,7 @@ fn update(opt: &UpdateCommandOptions) -> rhg_engine_core::Result<()> {
     false => Archive::default(),
   };
   for f in &opt.files {
-    let _ = a.remove_file(f);
+    let _ = a.remove_path(f);
     a.add(ArchiveFile::load(f)?)?;
   }
   a.save_file(&opt.archive)?;
@@ -40,19 +44,20 @@ fn update(opt: &UpdateCommandOptions) -> rhg_engine_core::Result<()> {
 fn remove(opt: &RemoveCommandOptions) -> rhg_engine_core::Result<()> {
   let mut a = Archive::load_file(&opt.archive)?;
   let mut modified = false;
-  if let Some(files) = filter_files(&a, &opt.filter)
-    .map(|files| files.iter().map(|file| (*file).clone()).collect::<Vec<_>>())
-  {
-    for file in files {
-      if let Some(_) = a.remove_file(file.path()) {
-        modified = true;
-      }
+  let files = filter_files(&a, &opt.filter, &opt.filter_set)?
+    .iter()
+    .map(|file| (*file).clone())
+    .collect::<Vec<_>>();
+  for file in files {
+    if let Some(_) = a.remove_path(file.path()) {
+      log::debug!("remove {}", file.path().display());
+      modified = true;
     }
   }
   if modified {
     a.save_file(&opt.archive)?;
   } else {
-    eprintln!("\x1b[0;33mwarn\x1b[0m: archive left untouched")
+    log::warn!("archive left untouched")
   }
   Ok(())
 }
@@ -64,60 +69,89 @@ fn extract(opt: &ExtractCommandOptions) -> rhg_engine_core::Result<()> {
     .as_ref()
     .map(|v| v.clone())
     .unwrap_or_else(|| PathBuf::from("."));
-  if !output_dir.exists() {
-    if let Err(e) = std::fs::create_dir_all(&output_dir) {
+  let set = opt.filter_set.filter_set(&opt.filter)?;
+  let mut targets = HashMap::new();
+  let mut files = vec![];
+  for file in filter_files(&a, &opt.filter, &opt.filter_set)? {
+    let target = match &opt.rename {
+      Some(template) => set
+        .rename(file.path(), template)
+        .unwrap_or_else(|| Ok(file.path().clone()))?,
+      None => file.path().clone(),
+    };
+    if let Some(other) = targets.insert(target.clone(), file.path()) {
       return err!(
-        ErrorKind::IO,
+        ErrorKind::InvalidInput,
         format!(
-          "failed to create directory '{}', {}",
-          output_dir.display(),
-          e
+          "cannot extract both '{}' and '{}' to '{}'",
+          other.display(),
+          file.path().display(),
+          target.display()
         )
       );
     }
+    files.push((file, output_dir.join(target)));
   }
-  if let Some(files) = filter_files(&a, &opt.filter) {
-    for file in files {
-      let out_path = output_dir.join(file.path());
-      if let Err(e) = std::fs::write(&out_path, file.content()) {
+  for (file, out_path) in files {
+    if let Some(dir) = out_path.parent() {
+      if let Err(e) = std::fs::create_dir_all(dir) {
         return err!(
           ErrorKind::IO,
-          format!("failed to write file '{}', {}", out_path.display(), e)
+          format!("failed to create directory '{}', {}", dir.display(), e)
         );
       }
-      println!("write {} - {}B", out_path.display(), file.content_len());
     }
+    if let Err(e) = std::fs::write(&out_path, file.content()) {
+      return err!(
+        ErrorKind::IO,
+        format!("failed to write file '{}', {}", out_path.display(), e)
+      );
+    }
+    log::info!("write {} - {}B", out_path.display(), file.content_len());
   }
   Ok(())
 }
 
-fn filter_files<'a>(a: &'a Archive, filters: &[Filter]) -> Option<Vec<&'a ArchiveFile>> {
-  let filtered = a
-    .files()
-    .iter()
-    .filter(|file| {
-      let matches = filters.is_empty()
-        || filters.iter().any(|filter| {
-          file
-            .name()
-            .map(|ref name| filter.matches(&name))
-            .unwrap_or_default()
-        });
-      return matches;
-    })
-    .collect::<Vec<_>>();
-  if filtered.is_empty() {
-    eprintln!(
-      "\x1b[0;33mwarn\x1b[0m: no files match filters: {}",
-      filters
-        .iter()
-        .map(|filter| filter.to_string())
-        .collect::<Vec<_>>()
-        .join(", ")
+fn mv(opt: &MoveCommandOptions) -> rhg_engine_core::Result<()> {
+  let mut a = Archive::load_file(&opt.archive)?;
+  let set = opt.filter_set.filter_set(std::slice::from_ref(&opt.from))?;
+  if set.select(a.files()).is_empty() {
+    return err!(
+      ErrorKind::NotFound,
+      format!("no files match filters: {}", set)
     );
-    return None;
   }
-  Some(filtered)
+  let renamed = rename_entries(&mut a, &set, &opt.to)?;
+  for (from, to) in &renamed {
+    log::debug!("rename {} -> {}", from.display(), to.display());
+  }
+  if renamed.is_empty() {
+    log::warn!("archive left untouched");
+    return Ok(());
+  }
+  a.save_file(&opt.archive)?;
+  log::info!(
+    "renamed {} files in {}",
+    renamed.len(),
+    opt.archive.display()
+  );
+  Ok(())
+}
+
+fn filter_files<'a>(
+  a: &'a Archive,
+  filters: &[Filter],
+  opt: &FilterSetOptions,
+) -> rhg_engine_core::Result<Vec<&'a ArchiveFile>> {
+  let set = opt.filter_set(filters)?;
+  let filtered = set.select(a.files());
+  if filtered.is_empty() && !set.is_empty() {
+    return err!(
+      ErrorKind::NotFound,
+      format!("no files match filters: {}", set)
+    );
+  }
+  Ok(filtered)
 }
 
 type Getter<'a> = fn(&'a ArchiveFile) -> Option<String>;
@@ -151,39 +185,175 @@ fn list(opt: &ListCommandOptions) -> rhg_engine_core::Result<()> {
   }
   let a = Archive::load_file(&opt.archive)?;
   {
-    if let Some(filtered) = filter_files(&a, &opt.filters) {
-      for file in filtered {
-        // println!("{}", &["Offset", "Created at", "Modified at", ""]);
-        let tpl_vals = tpl_vars
-          .iter()
-          .map(|(key, getter)| (format!("%{}", key), getter(file)))
-          .collect::<Vec<_>>();
-        let mut tpl_out = opt.template.clone();
-        for (tpl_key, tpl_val) in &tpl_vals {
-          tpl_out = tpl_out.replace(
-            tpl_key,
-            &tpl_val.as_ref().map(|v| v.clone()).unwrap_or_default(),
-          );
-        }
-        println!("{}", tpl_out)
+    for file in filter_files(&a, &opt.filters, &opt.filter_set)? {
+      // println!("{}", &["Offset", "Created at", "Modified at", ""]);
+      let tpl_vals = tpl_vars
+        .iter()
+        .map(|(key, getter)| (format!("%{}", key), getter(file)))
+        .collect::<Vec<_>>();
+      let mut tpl_out = opt.template.clone();
+      for (tpl_key, tpl_val) in &tpl_vals {
+        tpl_out = tpl_out.replace(
+          tpl_key,
+          &tpl_val.as_ref().map(|v| v.clone()).unwrap_or_default(),
+        );
       }
+      println!("{}", tpl_out)
     }
   }
   Ok(())
 }
 
+fn watch(opt: &WatchCommandOptions) -> rhg_engine_core::Result<()> {
+  let mut w = PackWatcher::new(
+    &opt.archive,
+    &opt.sources,
+    Duration::from_millis(opt.debounce),
+  )?;
+  let summary = w.rebuild_all()?;
+  log::info!("packed {}: {}", opt.archive.display(), summary);
+  log::info!(
+    "watching {} for changes ...",
+    opt
+      .sources
+      .iter()
+      .map(|src| src.display().to_string())
+      .collect::<Vec<_>>()
+      .join(", ")
+  );
+  w.run(|summary| match summary {
+    Ok(summary) => log::info!(
+      "[{}] repacked {}: {}",
+      Utc::now().format("%T"),
+      opt.archive.display(),
+      summary
+    ),
+    Err(e) => log::error!("{}", e),
+  })
+}
+
+fn import(opt: &ImportCommandOptions) -> rhg_engine_core::Result<()> {
+  let mut a = import_file(&opt.input, opt.format)?;
+  a.save_file(&opt.archive)?;
+  log::info!(
+    "imported {} files from {} into {}",
+    a.files().len(),
+    opt.input.display(),
+    opt.archive.display()
+  );
+  Ok(())
+}
+
+fn export(opt: &ExportCommandOptions) -> rhg_engine_core::Result<()> {
+  let a = Archive::load_file(&opt.archive)?;
+  export_file(&a, &opt.output, opt.format)?;
+  log::info!(
+    "exported {} files from {} into {}",
+    a.files().len(),
+    opt.archive.display(),
+    opt.output.display()
+  );
+  Ok(())
+}
+
+#[cfg(feature = "fuse")]
+fn mount(opt: &rhg_pack::MountCommandOptions) -> rhg_engine_core::Result<()> {
+  let a = Archive::load_file(&opt.archive)?;
+  log::info!(
+    "mounting {} on {}, unmount with 'fusermount -u {}'",
+    opt.archive.display(),
+    opt.mountpoint.display(),
+    opt.mountpoint.display()
+  );
+  rhg_pack::mount(a, &opt.mountpoint)
+}
+
 fn main() -> ExitCode {
   let options = CliOptions::parse();
+  let _ = PackLogger::new(
+    verbosity_level(options.quiet, options.verbose),
+    options.color,
+  )
+  .init();
   let e = match options.command {
     Command::Add(opts) => add(&opts),
     Command::Update(opts) => update(&opts),
     Command::Remove(opts) => remove(&opts),
     Command::List(opts) => list(&opts),
+    Command::Move(opts) => mv(&opts),
     Command::Extract(opts) => extract(&opts),
+    Command::Watch(opts) => watch(&opts),
+    Command::Import(opts) => import(&opts),
+    Command::Export(opts) => export(&opts),
+    #[cfg(feature = "fuse")]
+    Command::Mount(opts) => mount(&opts),
   };
   if let Err(e) = e {
-    eprintln!("\x1b[0;31merror\x1b[0m: {}", e);
-    return ExitCode::FAILURE;
+    log::error!("{}", e);
+    return PackExitCode::from(&e).into();
+  }
+  PackExitCode::Success.into()
+}
+
+#[cfg(test)]
+mod test {
+  use std::path::PathBuf;
+
+  use clap::Parser;
+  use rhg_engine_core::{Archive, ArchiveFile};
+  use rhg_pack::{CliOptions, Command};
+
+  use super::{remove, update};
+
+  fn command(args: &[&str]) -> Command {
+    CliOptions::try_parse_from(["rhg_pack"].iter().chain(args))
+      .unwrap()
+      .command
+  }
+
+  /// Archive holding `textures/a.png` and `models/a.png`
+  fn same_name(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+    let root = std::env::temp_dir().join(format!("rhg_pack_{}_{}", name, std::process::id()));
+    let (texture, model) = (root.join("textures/a.png"), root.join("models/a.png"));
+    for f in [&texture, &model] {
+      std::fs::create_dir_all(f.parent().unwrap()).unwrap();
+      std::fs::write(f, f.to_str().unwrap()).unwrap();
+    }
+    let out = root.join("out.pack");
+    let mut a = Archive::default();
+    a.add(ArchiveFile::load(&texture).unwrap()).unwrap();
+    a.add(ArchiveFile::load(&model).unwrap()).unwrap();
+    a.save_file(&out).unwrap();
+    (out, texture, model)
+  }
+
+  #[test]
+  fn update_same_name() {
+    let (out, texture, model) = same_name("update");
+    std::fs::write(&model, b"model").unwrap();
+    let args = [out.to_str().unwrap(), model.to_str().unwrap()];
+    match command(&[&["update"], &args[..]].concat()) {
+      Command::Update(opt) => update(&opt).unwrap(),
+      _ => unreachable!(),
+    }
+    let a = Archive::load_file(&out).unwrap();
+    assert_eq!(a.files().len(), 2);
+    assert_eq!(a.get_path(&model).unwrap().content(), &b"model".to_vec());
+    assert!(a.get_path(&texture).is_some());
+    std::fs::remove_dir_all(out.parent().unwrap()).unwrap();
+  }
+
+  #[test]
+  fn remove_same_name() {
+    let (out, texture, model) = same_name("remove");
+    let args = [out.to_str().unwrap(), model.to_str().unwrap()];
+    match command(&[&["remove"], &args[..]].concat()) {
+      Command::Remove(opt) => remove(&opt).unwrap(),
+      _ => unreachable!(),
+    }
+    let a = Archive::load_file(&out).unwrap();
+    assert_eq!(a.files().len(), 1);
+    assert_eq!(a.files()[0].path(), &texture);
+    std::fs::remove_dir_all(out.parent().unwrap()).unwrap();
   }
-  ExitCode::SUCCESS
 }
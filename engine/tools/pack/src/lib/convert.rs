@@ -0,0 +1,305 @@
+use std::{
+  fmt::Display,
+  io::{Read, Seek, Write},
+  path::{Component, Path, PathBuf},
+  str::FromStr,
+  time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use rhg_engine_core::{err, here, Archive, ArchiveFile, Error, ErrorKind};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Foreign archive formats `rhg_pack` can convert from and to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ConvertFormat {
+  Zip,
+  Tar,
+}
+
+impl ConvertFormat {
+  /// Guess the format from the file extension
+  pub fn detect<P: AsRef<Path>>(path: P) -> rhg_engine_core::Result<Self> {
+    let ext = path
+      .as_ref()
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .unwrap_or_default();
+    ext.parse::<Self>().map_err(|_| {
+      Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+          "cannot guess archive format of '{}', expected a .zip or .tar file",
+          path.as_ref().display()
+        ),
+        None,
+        here!(),
+      )
+    })
+  }
+}
+
+impl FromStr for ConvertFormat {
+  type Err = rhg_engine_core::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_lowercase().as_str() {
+      "zip" => Ok(Self::Zip),
+      "tar" => Ok(Self::Tar),
+      _ => err!(
+        ErrorKind::InvalidInput,
+        format!("unknown archive format '{}'", s)
+      ),
+    }
+  }
+}
+
+impl Display for ConvertFormat {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Self::Zip => "zip",
+        Self::Tar => "tar",
+      }
+    )
+  }
+}
+
+fn convert_error<E: Display>(action: &str, e: E) -> Error {
+  Error::new(ErrorKind::IO, format!("{}, {}", action, e), None, here!())
+}
+
+/// Strip root and parent components so foreign entries cannot escape the archive
+fn sanitize_path<P: AsRef<Path>>(path: P) -> PathBuf {
+  path
+    .as_ref()
+    .components()
+    .filter(|c| matches!(c, Component::Normal(_)))
+    .collect()
+}
+
+fn to_unix_secs(time: &SystemTime) -> u64 {
+  time
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or_default()
+}
+
+/// Timestamp carried over to foreign formats, which only know about modification times
+fn export_time(file: &ArchiveFile) -> SystemTime {
+  file
+    .modified_at()
+    .or(file.archived_at())
+    .copied()
+    .unwrap_or_else(SystemTime::now)
+}
+
+/// Read the entries of a zip file into an archive
+pub fn import_zip<R: Read + Seek>(r: R) -> rhg_engine_core::Result<Archive> {
+  let mut zip = ZipArchive::new(r).map_err(|e| convert_error("failed to read zip", e))?;
+  let mut a = Archive::default();
+  for i in 0..zip.len() {
+    let mut entry = zip
+      .by_index(i)
+      .map_err(|e| convert_error("failed to read zip entry", e))?;
+    if entry.is_dir() {
+      continue;
+    }
+    let path = sanitize_path(entry.mangled_name());
+    let mut content = vec![];
+    entry.read_to_end(&mut content)?;
+    let mut f = ArchiveFile::new(path, &content);
+    *f.modified_at_mut() = entry
+      .last_modified()
+      .and_then(|dt| chrono::NaiveDateTime::try_from(dt).ok())
+      .map(|dt| dt.and_utc().into());
+    a.add(f)?;
+  }
+  Ok(a)
+}
+
+/// Write the entries of an archive as a zip file
+pub fn export_zip<W: Write + Seek>(a: &Archive, w: W) -> rhg_engine_core::Result<()> {
+  let mut zip = ZipWriter::new(w);
+  for f in a.files() {
+    let modified_at: DateTime<Utc> = export_time(f).into();
+    let options = SimpleFileOptions::default()
+      .compression_method(CompressionMethod::Deflated)
+      .last_modified_time(zip::DateTime::try_from(modified_at.naive_utc()).unwrap_or_default());
+    let name = sanitize_path(f.path())
+      .components()
+      .map(|c| c.as_os_str().to_string_lossy().to_string())
+      .collect::<Vec<_>>()
+      .join("/");
+    zip
+      .start_file(name, options)
+      .map_err(|e| convert_error("failed to write zip entry", e))?;
+    zip.write_all(f.content())?;
+  }
+  zip
+    .finish()
+    .map_err(|e| convert_error("failed to write zip", e))?;
+  Ok(())
+}
+
+/// Read the regular files of a tar stream into an archive
+pub fn import_tar<R: Read>(r: R) -> rhg_engine_core::Result<Archive> {
+  let mut tar = tar::Archive::new(r);
+  let mut a = Archive::default();
+  for entry in tar.entries()? {
+    let mut entry = entry?;
+    if !entry.header().entry_type().is_file() {
+      continue;
+    }
+    let path = sanitize_path(entry.path()?);
+    let mtime = entry.header().mtime()?;
+    let mut content = vec![];
+    entry.read_to_end(&mut content)?;
+    let mut f = ArchiveFile::new(path, &content);
+    *f.modified_at_mut() = SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(mtime));
+    a.add(f)?;
+  }
+  Ok(a)
+}
+
+/// Write the entries of an archive as a tar stream
+pub fn export_tar<W: Write>(a: &Archive, w: W) -> rhg_engine_core::Result<()> {
+  let mut tar = tar::Builder::new(w);
+  for f in a.files() {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(f.content_len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(to_unix_secs(&export_time(f)));
+    tar.append_data(&mut header, sanitize_path(f.path()), f.content().as_slice())?;
+  }
+  tar.into_inner()?.flush()?;
+  Ok(())
+}
+
+/// Load a zip or tar file as an archive
+pub fn import_file<P: AsRef<Path>>(
+  path: P,
+  format: Option<ConvertFormat>,
+) -> rhg_engine_core::Result<Archive> {
+  let format = match format {
+    Some(format) => format,
+    None => ConvertFormat::detect(&path)?,
+  };
+  let file = std::fs::File::open(&path).map_err(|e| {
+    Error::new(
+      ErrorKind::IO,
+      format!("{}: {}", path.as_ref().display(), e),
+      None,
+      here!(),
+    )
+  })?;
+  match format {
+    ConvertFormat::Zip => import_zip(file),
+    ConvertFormat::Tar => import_tar(std::io::BufReader::new(file)),
+  }
+}
+
+/// Save an archive as a zip or tar file
+pub fn export_file<P: AsRef<Path>>(
+  a: &Archive,
+  path: P,
+  format: Option<ConvertFormat>,
+) -> rhg_engine_core::Result<()> {
+  let format = match format {
+    Some(format) => format,
+    None => ConvertFormat::detect(&path)?,
+  };
+  let file = std::fs::File::create(&path).map_err(|e| {
+    Error::new(
+      ErrorKind::IO,
+      format!("{}: {}", path.as_ref().display(), e),
+      None,
+      here!(),
+    )
+  })?;
+  match format {
+    ConvertFormat::Zip => export_zip(a, file),
+    ConvertFormat::Tar => export_tar(a, std::io::BufWriter::new(file)),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::{
+    io::Cursor,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+  };
+
+  use rhg_engine_core::{Archive, ArchiveFile, ErrorKind};
+
+  use super::{export_tar, export_zip, import_tar, import_zip, ConvertFormat};
+
+  fn sample() -> Archive {
+    let mut a = Archive::default();
+    let mut f = ArchiveFile::new("textures/wall.png", b"wall");
+    *f.modified_at_mut() = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    a.add(f).unwrap();
+    a.add_file("readme.txt", b"hello").unwrap();
+    a
+  }
+
+  fn assert_same(a: &Archive, b: &Archive) {
+    assert_eq!(a.files().len(), b.files().len());
+    for (l, r) in a.files().iter().zip(b.files()) {
+      assert_eq!(l.path(), r.path());
+      assert_eq!(l.content(), r.content());
+    }
+    assert_eq!(a.files()[0].modified_at(), b.files()[0].modified_at());
+  }
+
+  #[test]
+  fn zip() {
+    let a = sample();
+    let mut buf = Cursor::new(vec![]);
+    export_zip(&a, &mut buf).unwrap();
+    buf.set_position(0);
+    assert_same(&a, &import_zip(buf).unwrap());
+  }
+
+  #[test]
+  fn tar() {
+    let a = sample();
+    let mut buf = vec![];
+    export_tar(&a, &mut buf).unwrap();
+    assert_same(&a, &import_tar(buf.as_slice()).unwrap());
+  }
+
+  #[test]
+  fn same_name() {
+    let mut a = Archive::default();
+    a.add_file("textures/a.png", b"texture").unwrap();
+    a.add_file("models/a.png", b"model").unwrap();
+    let mut buf = Cursor::new(vec![]);
+    export_zip(&a, &mut buf).unwrap();
+    buf.set_position(0);
+    assert_eq!(import_zip(buf).unwrap().files().len(), 2);
+    let mut buf = vec![];
+    export_tar(&a, &mut buf).unwrap();
+    assert_eq!(import_tar(buf.as_slice()).unwrap().files().len(), 2);
+  }
+
+  #[test]
+  fn detect() {
+    assert_eq!(
+      ConvertFormat::detect(PathBuf::from("drop.ZIP")).unwrap(),
+      ConvertFormat::Zip
+    );
+    assert_eq!(
+      ConvertFormat::detect(PathBuf::from("qa/out.tar")).unwrap(),
+      ConvertFormat::Tar
+    );
+    let e = ConvertFormat::detect(PathBuf::from("out.pack")).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    let e = "rar".parse::<ConvertFormat>().unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+  }
+}
@@ -0,0 +1,62 @@
+use std::process::ExitCode;
+
+use rhg_engine_core::{Error, ErrorKind};
+
+/// Process exit codes of `rhg_pack`, `2` is left to clap for usage errors
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PackExitCode {
+  Success = 0,
+  /// Invalid input, such as a malformed filter or conflicting entries, or any other failure
+  Failure = 1,
+  NoMatch = 3,
+  CorruptedArchive = 4,
+  /// Reading or writing a file failed
+  IO = 5,
+}
+
+impl From<&Error> for PackExitCode {
+  fn from(e: &Error) -> Self {
+    match e.kind() {
+      ErrorKind::NotFound => Self::NoMatch,
+      ErrorKind::Corrupted => Self::CorruptedArchive,
+      ErrorKind::IO => Self::IO,
+      ErrorKind::InvalidInput => Self::Failure,
+      _ => Self::Failure,
+    }
+  }
+}
+
+impl From<PackExitCode> for ExitCode {
+  fn from(code: PackExitCode) -> Self {
+    ExitCode::from(code as u8)
+  }
+}
+
+pub const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success
+  1  failure, e.g. invalid filters or templates, conflicting entries
+  2  invalid usage
+  3  no archive entry matched the filters
+  4  corrupted archive
+  5  I/O error, reading or writing a file failed";
+
+#[cfg(test)]
+mod test {
+  use rhg_engine_core::{here, Archive, Error, ErrorKind};
+
+  use super::PackExitCode;
+
+  #[test]
+  fn from_error() {
+    let code = |kind| PackExitCode::from(&Error::new(kind, String::new(), None, here!()));
+    assert_eq!(code(ErrorKind::InvalidInput), PackExitCode::Failure);
+    assert_eq!(code(ErrorKind::IO), PackExitCode::IO);
+    assert_eq!(code(ErrorKind::NotFound), PackExitCode::NoMatch);
+
+    let mut a = Archive::default();
+    a.add_file("a.png", b"a").unwrap();
+    let e = a.add_file("a.png", b"b").unwrap_err();
+    assert_eq!(PackExitCode::from(&e), PackExitCode::Failure);
+  }
+}
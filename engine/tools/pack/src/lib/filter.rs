@@ -1,83 +1,48 @@
 use std::{
   fmt::Display,
+  iter::Peekable,
   ops::{Deref, DerefMut},
-  str::FromStr,
+  path::Path,
+  str::{Chars, FromStr},
 };
 
 use rhg_engine_core::{err, ErrorKind};
 
+use crate::FilterMatcher;
+
+/// Characters that have to be escaped to be matched literally
+const SPECIAL_CHARS: &str = "*?[]{},!\\";
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FilterPart {
+  /// `*`, any sequence of characters within a path segment
   Star,
+  /// `**`, any sequence of characters, spanning directories
+  GlobStar,
+  /// `**/`, zero or more whole directories
+  Directories,
+  /// `?`, any single character but `/`
   Wildcard,
+  /// `[a-z]` or `[!a-z]`, a single character within (or outside of) the given ranges
+  Class {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+  },
+  /// `{png,jpg}`, any of the given alternatives
+  Alternation(Vec<Filter>),
   Exact(String),
   Exclude(String),
 }
 
-impl FilterPart {
-  pub fn capture<'a>(
-    &self,
-    expr: &str,
-    offs: &mut usize,
-    parts: &mut core::iter::Peekable<std::slice::Iter<'a, FilterPart>>,
-  ) -> Option<String> {
-    match self {
-      FilterPart::Wildcard => {
-        let ret = expr.chars().nth(*offs).map(|c| c.to_string());
-        if *offs < expr.len() {
-          *offs += 1
-        }
-        ret
-      }
-      FilterPart::Exclude(value) => {
-        let mut accu = String::new();
-        while *offs < expr.len() {
-          let ch = expr.chars().nth(*offs).unwrap();
-          let next_part = parts.peek().map(|p| *p);
-          let prev_offs = *offs;
-          let next_capture = next_part.and_then(|np| (*np).capture(expr, offs, parts));
-          *offs = prev_offs;
-          if next_part.is_some() && next_capture.is_some() {
-            break;
-          }
-          accu.push(ch);
-          *offs += 1;
-        }
-        if value.eq(&accu) {
-          return None;
-        }
-        Some(accu)
-      }
-      FilterPart::Star => {
-        let mut accu = String::new();
-        while *offs < expr.len() {
-          let ch = expr.chars().nth(*offs).unwrap();
-          let next_part = parts.peek().map(|p| *p);
-          let prev_offs = *offs;
-          let next_capture = next_part.and_then(|np| (*np).capture(expr, offs, parts));
-          *offs = prev_offs;
-          if next_part.is_some() && next_capture.is_some() {
-            break;
-          }
-          accu.push(ch);
-          *offs += 1;
-        }
-        Some(accu)
-      }
-      FilterPart::Exact(q) => {
-        if q.len() > (expr.len() - *offs) {
-          return None;
-        }
-        let sub_expr = &expr[*offs..*offs + q.len()];
-        if sub_expr.eq(q) {
-          *offs += q.len();
-          Some(sub_expr.to_string())
-        } else {
-          None
-        }
-      }
+fn escape(value: &str) -> String {
+  let mut ret = String::new();
+  for ch in value.chars() {
+    if SPECIAL_CHARS.contains(ch) {
+      ret.push('\\');
     }
+    ret.push(ch);
   }
+  ret
 }
 
 impl Display for FilterPart {
@@ -87,14 +52,58 @@ impl Display for FilterPart {
       "{}",
       match self {
         Self::Star => "*".to_string(),
+        Self::GlobStar => "**".to_string(),
+        Self::Directories => "**/".to_string(),
         Self::Exclude(value) => format!("!({})", value),
         Self::Wildcard => "?".to_string(),
-        Self::Exact(q) => q.clone(),
+        Self::Class { negated, ranges } => format!(
+          "[{}{}]",
+          if *negated { "!" } else { "" },
+          ranges
+            .iter()
+            .map(|(lo, hi)| match lo == hi {
+              true => escape(&lo.to_string()),
+              false => format!("{}-{}", escape(&lo.to_string()), escape(&hi.to_string())),
+            })
+            .collect::<Vec<_>>()
+            .join("")
+        ),
+        Self::Alternation(alternatives) => format!(
+          "{{{}}}",
+          alternatives
+            .iter()
+            .map(|alt| alt.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+        ),
+        Self::Exact(q) => escape(q),
       }
     )
   }
 }
 
+/// How a [`Filter`] compares characters
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FilterOptions {
+  /// Ignore case, so that `*.png` matches `WALL.PNG`
+  pub case_insensitive: bool,
+  /// Compare the NFC normalized forms of the pattern and the path, so that decomposed names
+  /// (as written by macOS) match composed patterns
+  pub normalize: bool,
+}
+
+impl FilterOptions {
+  pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+    self.case_insensitive = case_insensitive;
+    self
+  }
+
+  pub fn with_normalize(mut self, normalize: bool) -> Self {
+    self.normalize = normalize;
+    self
+  }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Filter(Vec<FilterPart>);
 
@@ -114,32 +123,44 @@ impl Display for Filter {
 }
 
 impl Filter {
+  /// Compile the filter, to be preferred when matching many paths against it
+  pub fn compile(&self) -> FilterMatcher {
+    FilterMatcher::new(self)
+  }
+
+  pub fn compile_with(&self, options: FilterOptions) -> FilterMatcher {
+    FilterMatcher::compile(self, options)
+  }
+
+  pub fn matches_with(&self, s: &str, options: FilterOptions) -> bool {
+    self.compile_with(options).matches(s)
+  }
+
   pub fn matches(&self, s: &str) -> bool {
-    self.capture(s).is_some()
+    self.compile().matches(s)
+  }
+
+  /// Match against a path, using `/` as the separator whatever the platform
+  pub fn matches_path<P: AsRef<Path>>(&self, path: P) -> bool {
+    self.matches(&path_to_str(path))
   }
 
   pub fn capture(&self, expr: &str) -> Option<Vec<(FilterPart, String)>> {
-    let mut ret = vec![];
-    let mut part_it = self.0.iter().peekable();
-    let mut offs = 0;
-    // println!("capture '{}' on '{}'", self, expr);
-    while let Some(part) = part_it.next() {
-      // println!("  capture '{}' on '{}'", part, &expr[offs..]);
-      match part.capture(expr, &mut offs, &mut part_it) {
-        Some(extracted) => {
-          //   println!("    -> captured '{}'", extracted);
-          ret.push((part.clone(), extracted));
-        }
-        None => return None,
-      };
-    }
-    if offs < expr.len() {
-      return None;
-    }
-    return Some(ret);
+    self.compile().capture(expr)
+  }
+
+  pub fn capture_path<P: AsRef<Path>>(&self, path: P) -> Option<Vec<(FilterPart, String)>> {
+    self.capture(&path_to_str(path))
   }
 }
 
+pub(crate) fn path_to_str<P: AsRef<Path>>(path: P) -> String {
+  path
+    .as_ref()
+    .to_string_lossy()
+    .replace(std::path::MAIN_SEPARATOR, "/")
+}
+
 impl Deref for Filter {
   type Target = Vec<FilterPart>;
 
@@ -160,22 +181,119 @@ impl AsRef<Vec<FilterPart>> for Filter {
   }
 }
 
+fn parse_escaped(ch_it: &mut Peekable<Chars<'_>>, s: &str) -> rhg_engine_core::Result<char> {
+  match ch_it.next() {
+    Some(ch) => Ok(ch),
+    None => err!(
+      ErrorKind::InvalidInput,
+      format!("invalid filter '{}', trailing escape character", s)
+    ),
+  }
+}
+
+fn parse_class(ch_it: &mut Peekable<Chars<'_>>, s: &str) -> rhg_engine_core::Result<FilterPart> {
+  let negated = matches!(ch_it.peek(), Some('!') | Some('^'));
+  if negated {
+    ch_it.next();
+  }
+  let mut ranges = vec![];
+  let mut first = true;
+  loop {
+    let lo = match ch_it.next() {
+      Some(']') if !first => break,
+      Some('\\') => parse_escaped(ch_it, s)?,
+      Some(ch) => ch,
+      None => {
+        return err!(
+          ErrorKind::InvalidInput,
+          format!("invalid filter class, expected '[...]' in '{}'", s)
+        )
+      }
+    };
+    first = false;
+    let mut lookahead = ch_it.clone();
+    let hi = match (lookahead.next(), lookahead.next()) {
+      (Some('-'), Some(hi)) if hi != ']' => {
+        ch_it.next();
+        match ch_it.next() {
+          Some('\\') => parse_escaped(ch_it, s)?,
+          _ => hi,
+        }
+      }
+      _ => lo,
+    };
+    if hi < lo {
+      return err!(
+        ErrorKind::InvalidInput,
+        format!("invalid filter class range '{}-{}' in '{}'", lo, hi, s)
+      );
+    }
+    ranges.push((lo, hi));
+  }
+  Ok(FilterPart::Class { negated, ranges })
+}
+
+fn parse_alternation(
+  ch_it: &mut Peekable<Chars<'_>>,
+  s: &str,
+) -> rhg_engine_core::Result<FilterPart> {
+  let mut alternatives = vec![];
+  let mut accu = String::new();
+  let mut depth = 0;
+  loop {
+    match ch_it.next() {
+      Some('\\') => {
+        accu.push('\\');
+        accu.push(parse_escaped(ch_it, s)?);
+      }
+      Some('{') => {
+        depth += 1;
+        accu.push('{');
+      }
+      Some('}') if depth > 0 => {
+        depth -= 1;
+        accu.push('}');
+      }
+      Some('}') => break,
+      Some(',') if depth == 0 => alternatives.push(std::mem::take(&mut accu).parse::<Filter>()?),
+      Some(ch) => accu.push(ch),
+      None => {
+        return err!(
+          ErrorKind::InvalidInput,
+          format!("invalid filter alternation, expected '{{...}}' in '{}'", s)
+        )
+      }
+    }
+  }
+  alternatives.push(accu.parse::<Filter>()?);
+  Ok(FilterPart::Alternation(alternatives))
+}
+
 impl FromStr for Filter {
   type Err = rhg_engine_core::Error;
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
     let mut f = Filter::default();
-    let mut ch_it = s.chars();
-    while let Some(mut ch) = ch_it.next() {
+    let mut ch_it = s.chars().peekable();
+    while let Some(ch) = ch_it.next() {
       let part = match ch {
-        '*' => FilterPart::Star,
+        '*' => match ch_it.next_if_eq(&'*') {
+          Some(_) => match ch_it.next_if_eq(&'/') {
+            Some(_) => FilterPart::Directories,
+            None => FilterPart::GlobStar,
+          },
+          None => FilterPart::Star,
+        },
         '?' => FilterPart::Wildcard,
+        '[' => parse_class(&mut ch_it, s)?,
+        '{' => parse_alternation(&mut ch_it, s)?,
+        '\\' => FilterPart::Exact(parse_escaped(&mut ch_it, s)?.to_string()),
         '!' => {
-          ch = match ch_it.next() {
-            Some('(') => '(',
+          match ch_it.next() {
+            Some('(') => {}
             Some(ch) => {
               return err!(
-                ErrorKind::IO,
+                ErrorKind::InvalidInput,
                 format!(
                   "invalid filter exclusion pattern, expected '!(...)' but got '!{}'",
                   ch
@@ -184,14 +302,14 @@ impl FromStr for Filter {
             }
             None => {
               return err!(
-                ErrorKind::IO,
-                format!("invalid filter exclusion pattern, expected '!(...)' but got '!'")
+                ErrorKind::InvalidInput,
+                "invalid filter exclusion pattern, expected '!(...)' but got '!'"
               )
             }
           };
           let mut accu = String::new();
           let mut found_close_paren = false;
-          while let Some(ch) = ch_it.next() {
+          for ch in ch_it.by_ref() {
             if ch == ')' {
               found_close_paren = true;
               break;
@@ -200,7 +318,7 @@ impl FromStr for Filter {
           }
           if !found_close_paren {
             return err!(
-              ErrorKind::IO,
+              ErrorKind::InvalidInput,
               format!(
                 "invalid filter exclusion pattern, expected '!(...)' but got '!({}'",
                 accu
@@ -231,6 +349,8 @@ pub fn parse_filter(value: &str) -> std::result::Result<Filter, std::io::Error>
 
 #[cfg(test)]
 mod test {
+  use rhg_engine_core::ErrorKind;
+
   use crate::FilterPart;
 
   use super::Filter;
@@ -361,4 +481,120 @@ mod test {
       ),])
     );
   }
+
+  #[test]
+  fn star_stops_at_separator() {
+    filter_match!(
+      "*.png",
+      "a/b.png",
+      None as Option<Vec<(FilterPart, String)>>
+    );
+    filter_match!(
+      "*/*.png",
+      "a/b.png",
+      Some(vec![
+        (FilterPart::Star, String::from("a")),
+        (FilterPart::Exact(String::from("/")), String::from("/")),
+        (FilterPart::Star, String::from("b")),
+        (
+          FilterPart::Exact(String::from(".png")),
+          String::from(".png")
+        ),
+      ])
+    );
+    filter_match!("a?c", "a/c", None as Option<Vec<(FilterPart, String)>>);
+  }
+
+  #[test]
+  fn globstar() {
+    let f = "textures/**/*.png".parse::<Filter>().expect("parse");
+    assert!(f.matches("textures/wall.png"));
+    assert!(f.matches("textures/walls/brick/red.png"));
+    assert!(!f.matches("textures/walls/red.jpg"));
+    assert!(!f.matches("models/walls/red.png"));
+
+    let f = "textures/**".parse::<Filter>().expect("parse");
+    assert!(f.matches("textures/walls/red.jpg"));
+    assert!(!f.matches("models/red.jpg"));
+
+    filter_match!(
+      "**/b.txt",
+      "a/b.txt",
+      Some(vec![
+        (FilterPart::Directories, String::from("a/")),
+        (
+          FilterPart::Exact(String::from("b.txt")),
+          String::from("b.txt")
+        ),
+      ])
+    );
+  }
+
+  #[test]
+  fn class() {
+    let f = "[a-c]?.txt".parse::<Filter>().expect("parse");
+    assert!(f.matches("b1.txt"));
+    assert!(!f.matches("d1.txt"));
+
+    let f = "[!x]".parse::<Filter>().expect("parse");
+    assert!(f.matches("y"));
+    assert!(!f.matches("x"));
+    assert!(!f.matches("/"));
+
+    let f = "[]a-]".parse::<Filter>().expect("parse");
+    assert!(f.matches("]"));
+    assert!(f.matches("-"));
+    assert!(!f.matches("b"));
+
+    assert!("[a-".parse::<Filter>().is_err());
+    let e = "[z-a]".parse::<Filter>().unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+  }
+
+  #[test]
+  fn alternation() {
+    let f = "*.{png,jpg}".parse::<Filter>().expect("parse");
+    assert!(f.matches("wall.png"));
+    assert!(f.matches("wall.jpg"));
+    assert!(!f.matches("wall.tga"));
+
+    let f = "{textures/**/,}*.{png,j{pe,p}g}"
+      .parse::<Filter>()
+      .expect("parse");
+    assert!(f.matches("wall.jpeg"));
+    assert!(f.matches("textures/a/wall.jpg"));
+    assert!(!f.matches("models/wall.jpg"));
+
+    assert!("{a,b".parse::<Filter>().is_err());
+  }
+
+  #[test]
+  fn escape() {
+    let f = "\\*\\[a\\].txt".parse::<Filter>().expect("parse");
+    assert_eq!(f, Filter(vec![FilterPart::Exact("*[a].txt".to_string())]));
+    assert!(f.matches("*[a].txt"));
+    assert!(!f.matches("b[a].txt"));
+    assert_eq!(f.to_string(), "\\*\\[a\\].txt");
+    assert!("abc\\".parse::<Filter>().is_err());
+  }
+
+  #[test]
+  fn display() {
+    for s in [
+      "a*b",
+      "**/c?.txt",
+      "x/**",
+      "[!a-z0]",
+      "*.{png,jpg}",
+      "a!(bc)d",
+    ] {
+      assert_eq!(s.parse::<Filter>().expect("parse").to_string(), s);
+    }
+  }
+
+  #[test]
+  fn path() {
+    let f = "textures/*.png".parse::<Filter>().expect("parse");
+    assert!(f.matches_path(std::path::Path::new("textures").join("wall.png")));
+  }
 }
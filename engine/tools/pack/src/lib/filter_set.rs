@@ -0,0 +1,452 @@
+use std::{
+  borrow::Cow,
+  fmt::Display,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use regex::{Regex, RegexBuilder};
+use rhg_engine_core::{here, ArchiveFile, Error, ErrorKind};
+
+use crate::{
+  filter::path_to_str, matcher::nfc, Filter, FilterMatcher, FilterOptions, FilterPart,
+  PathCaptures, RenameTemplate,
+};
+
+/// A single pattern of a [`FilterSet`]
+#[derive(Debug, Clone)]
+pub enum FilterPattern {
+  /// Glob filter, which has to match the whole path
+  Glob(FilterMatcher),
+  /// Regular expression, which may match anywhere in the path unless anchored
+  Regex(Regex),
+}
+
+impl FilterPattern {
+  /// Same pattern, matching according to `options`
+  pub fn with_options(self, options: FilterOptions) -> Self {
+    match self {
+      Self::Glob(m) => Self::Glob(m.filter().compile_with(options)),
+      Self::Regex(re) => Self::Regex(
+        RegexBuilder::new(re.as_str())
+          .case_insensitive(options.case_insensitive)
+          .build()
+          .unwrap_or(re),
+      ),
+    }
+  }
+
+  pub fn matches(&self, expr: &str) -> bool {
+    match self {
+      Self::Glob(m) => m.matches(expr),
+      Self::Regex(re) => re.is_match(expr),
+    }
+  }
+
+  /// Captures of a match, one per wildcard part for globs, one per group for regular expressions
+  pub fn captures(&self, expr: &str) -> Option<PathCaptures> {
+    match self {
+      Self::Glob(m) => {
+        let parts = m.capture(expr)?;
+        let indexed = std::iter::once(Some(expr.to_string()))
+          .chain(
+            parts
+              .into_iter()
+              .filter(|(part, _)| !matches!(part, FilterPart::Exact(_)))
+              .map(|(_, value)| Some(value)),
+          )
+          .collect();
+        Some(PathCaptures::new(indexed, Default::default()))
+      }
+      Self::Regex(re) => {
+        let captures = re.captures(expr)?;
+        let indexed = captures
+          .iter()
+          .map(|m| m.map(|m| m.as_str().to_string()))
+          .collect();
+        let named = re
+          .capture_names()
+          .flatten()
+          .filter_map(|name| Some((name.to_string(), captures.name(name)?.as_str().to_string())))
+          .collect();
+        Some(PathCaptures::new(indexed, named))
+      }
+    }
+  }
+}
+
+impl From<Filter> for FilterPattern {
+  fn from(filter: Filter) -> Self {
+    Self::Glob(filter.compile())
+  }
+}
+
+impl From<Regex> for FilterPattern {
+  fn from(re: Regex) -> Self {
+    Self::Regex(re)
+  }
+}
+
+impl Display for FilterPattern {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Glob(m) => write!(f, "{}", m.filter()),
+      Self::Regex(re) => write!(f, "regex:{}", re),
+    }
+  }
+}
+
+impl FromStr for FilterPattern {
+  type Err = rhg_engine_core::Error;
+
+  /// Parse a glob, or a regular expression when prefixed by `regex:`
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.strip_prefix("regex:") {
+      Some(re) => Ok(Self::Regex(parse_regex(re).map_err(|e| {
+        Error::new(ErrorKind::InvalidInput, e.to_string(), None, here!())
+      })?)),
+      None => Ok(
+        s.strip_prefix("glob:")
+          .unwrap_or(s)
+          .parse::<Filter>()?
+          .into(),
+      ),
+    }
+  }
+}
+
+/// Entries selected by any of the included patterns, minus the ones matching any of the excluded
+/// patterns. Selects everything when no pattern is included.
+#[derive(Debug, Default, Clone)]
+pub struct FilterSet {
+  include: Vec<FilterPattern>,
+  exclude: Vec<FilterPattern>,
+  by_name: bool,
+  options: FilterOptions,
+}
+
+impl FilterSet {
+  pub fn include(&self) -> &Vec<FilterPattern> {
+    &self.include
+  }
+
+  pub fn include_mut(&mut self) -> &mut Vec<FilterPattern> {
+    &mut self.include
+  }
+
+  pub fn exclude(&self) -> &Vec<FilterPattern> {
+    &self.exclude
+  }
+
+  pub fn exclude_mut(&mut self) -> &mut Vec<FilterPattern> {
+    &mut self.exclude
+  }
+
+  pub fn by_name(&self) -> bool {
+    self.by_name
+  }
+
+  pub fn options(&self) -> FilterOptions {
+    self.options
+  }
+
+  pub fn with_include<P: Into<FilterPattern>>(mut self, pattern: P) -> Self {
+    self.include.push(pattern.into().with_options(self.options));
+    self
+  }
+
+  pub fn with_exclude<P: Into<FilterPattern>>(mut self, pattern: P) -> Self {
+    self.exclude.push(pattern.into().with_options(self.options));
+    self
+  }
+
+  /// Match every pattern of the set, including the ones added later, according to `options`
+  pub fn with_options(mut self, options: FilterOptions) -> Self {
+    self.options = options;
+    for patterns in [&mut self.include, &mut self.exclude] {
+      *patterns = std::mem::take(patterns)
+        .into_iter()
+        .map(|p| p.with_options(options))
+        .collect();
+    }
+    self
+  }
+
+  /// Match patterns against file names instead of full entry paths
+  pub fn with_by_name(mut self, by_name: bool) -> Self {
+    self.by_name = by_name;
+    self
+  }
+
+  /// Add a pattern from a list file line, `exclude:` subtracts it from the selection
+  pub fn add_line(&mut self, line: &str) -> rhg_engine_core::Result<()> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      return Ok(());
+    }
+    match line.strip_prefix("exclude:") {
+      Some(pattern) => self
+        .exclude
+        .push(pattern.parse::<FilterPattern>()?.with_options(self.options)),
+      None => self
+        .include
+        .push(line.parse::<FilterPattern>()?.with_options(self.options)),
+    }
+    Ok(())
+  }
+
+  /// Add the patterns listed in a file, one per line.
+  ///
+  /// Lines are globs unless prefixed by `regex:`, and select entries unless prefixed by
+  /// `exclude:`. Empty lines and lines starting with `#` are ignored.
+  pub fn with_file<P: AsRef<Path>>(mut self, path: P) -> rhg_engine_core::Result<Self> {
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+      Error::new(
+        ErrorKind::IO,
+        format!("{}: {}", path.as_ref().display(), e),
+        None,
+        here!(),
+      )
+    })?;
+    for (i, line) in content.lines().enumerate() {
+      self.add_line(line).map_err(|e| {
+        Error::new(
+          e.kind(),
+          format!("{}:{}: {}", path.as_ref().display(), i + 1, e.message()),
+          None,
+          here!(),
+        )
+      })?;
+    }
+    Ok(self)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.include.is_empty() && self.exclude.is_empty()
+  }
+
+  /// Match a `/` separated path
+  pub fn matches(&self, expr: &str) -> bool {
+    let expr = self.input(expr);
+    (self.include.is_empty() || self.include.iter().any(|p| p.matches(&expr)))
+      && !self.exclude.iter().any(|p| p.matches(&expr))
+  }
+
+  /// Match a path, or its file name when matching by name
+  pub fn matches_path<P: AsRef<Path>>(&self, path: P) -> bool {
+    match self.by_name {
+      true => path
+        .as_ref()
+        .file_name()
+        .map(|name| self.matches(&name.to_string_lossy()))
+        .unwrap_or_default(),
+      false => self.matches(&path_to_str(path)),
+    }
+  }
+
+  /// Text patterns get matched against, normalized once for all of them
+  fn input<'a>(&self, expr: &'a str) -> Cow<'a, str> {
+    match self.options.normalize {
+      true => nfc(expr),
+      false => Cow::Borrowed(expr),
+    }
+  }
+
+  /// Captures of the first included pattern matching the path, or of the whole path when no
+  /// pattern is included
+  pub fn captures_path<P: AsRef<Path>>(&self, path: P) -> Option<PathCaptures> {
+    if !self.matches_path(&path) {
+      return None;
+    }
+    let expr = match self.by_name {
+      true => path.as_ref().file_name()?.to_string_lossy().to_string(),
+      false => path_to_str(path),
+    };
+    let expr = self.input(&expr);
+    match self.include.is_empty() {
+      true => Some(PathCaptures::new(
+        vec![Some(expr.to_string())],
+        Default::default(),
+      )),
+      false => self.include.iter().find_map(|p| p.captures(&expr)),
+    }
+  }
+
+  /// New path of a selected entry, only its file name gets replaced when matching by name
+  pub fn rename<P: AsRef<Path>>(
+    &self,
+    path: P,
+    template: &RenameTemplate,
+  ) -> Option<rhg_engine_core::Result<PathBuf>> {
+    let captures = self.captures_path(&path)?;
+    Some(template.render(&captures).map(|renamed| {
+      match self.by_name {
+        true => path
+          .as_ref()
+          .parent()
+          .map(|parent| parent.join(&renamed))
+          .unwrap_or(renamed),
+        false => renamed,
+      }
+    }))
+  }
+
+  /// Keep the archive files selected by the set
+  pub fn select<'a, I: IntoIterator<Item = &'a ArchiveFile>>(
+    &self,
+    files: I,
+  ) -> Vec<&'a ArchiveFile> {
+    files
+      .into_iter()
+      .filter(|file| self.matches_path(file.path()))
+      .collect()
+  }
+}
+
+impl Display for FilterSet {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}",
+      self
+        .include
+        .iter()
+        .map(|p| p.to_string())
+        .chain(self.exclude.iter().map(|p| format!("exclude:{}", p)))
+        .collect::<Vec<_>>()
+        .join(", ")
+    )
+  }
+}
+
+pub fn parse_pattern(value: &str) -> std::result::Result<FilterPattern, std::io::Error> {
+  value
+    .parse::<FilterPattern>()
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+pub fn parse_regex(value: &str) -> std::result::Result<Regex, std::io::Error> {
+  Regex::new(value).map_err(|e| {
+    std::io::Error::new(
+      std::io::ErrorKind::InvalidInput,
+      format!("invalid regex '{}', {}", value, e),
+    )
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use rhg_engine_core::{ArchiveFile, ErrorKind};
+
+  use super::{parse_regex, FilterSet};
+
+  #[test]
+  fn include_exclude() {
+    let set = FilterSet::default()
+      .with_include("textures/**".parse::<crate::Filter>().unwrap())
+      .with_include(parse_regex(r"\.ogg$").unwrap())
+      .with_exclude("**/*_old.*".parse::<crate::Filter>().unwrap());
+    assert!(set.matches("textures/wall.png"));
+    assert!(set.matches("sounds/step.ogg"));
+    assert!(!set.matches("textures/wall_old.png"));
+    assert!(!set.matches("models/crate.obj"));
+    assert_eq!(
+      set.to_string(),
+      "textures/**, regex:\\.ogg$, exclude:**/*_old.*"
+    );
+
+    let set = FilterSet::default().with_exclude("*.txt".parse::<crate::Filter>().unwrap());
+    assert!(set.matches("a.png"));
+    assert!(!set.matches("a.txt"));
+    assert!(FilterSet::default().matches("anything"));
+  }
+
+  #[test]
+  fn by_name() {
+    let set = FilterSet::default()
+      .with_include("wall.*".parse::<crate::Filter>().unwrap())
+      .with_by_name(true);
+    let files = [
+      ArchiveFile::new("textures/wall.png", b""),
+      ArchiveFile::new("textures/floor.png", b""),
+    ];
+    let selected = set.select(&files);
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].path(), files[0].path());
+  }
+
+  #[test]
+  fn rename() {
+    let template = "albedo/{1}.{ext}".parse::<crate::RenameTemplate>().unwrap();
+    let set = FilterSet::default()
+      .with_include(parse_regex(r"^textures/(\w+)_albedo\.(?P<ext>png|jpg)$").unwrap());
+    assert_eq!(
+      set
+        .rename("textures/wall_albedo.png", &template)
+        .unwrap()
+        .unwrap(),
+      std::path::PathBuf::from("albedo/wall.png")
+    );
+    assert!(set.rename("textures/wall_normal.png", &template).is_none());
+
+    let template = "{1}/{2}.png".parse::<crate::RenameTemplate>().unwrap();
+    let set = FilterSet::default()
+      .with_include("*_*.png".parse::<crate::Filter>().unwrap())
+      .with_by_name(true);
+    assert_eq!(
+      set
+        .rename("textures/wall_albedo.png", &template)
+        .unwrap()
+        .unwrap(),
+      std::path::PathBuf::from("textures/wall/albedo.png")
+    );
+  }
+
+  #[test]
+  fn options() {
+    let options = crate::FilterOptions::default()
+      .with_case_insensitive(true)
+      .with_normalize(true);
+    let set = FilterSet::default()
+      .with_include("café/*.png".parse::<crate::Filter>().unwrap())
+      .with_options(options)
+      .with_include(parse_regex(r"^menu/(\w+)\.txt$").unwrap())
+      .with_exclude("**/*_OLD.*".parse::<crate::Filter>().unwrap());
+    assert!(set.matches("Cafe\u{301}/Wall.PNG"));
+    assert!(!set.matches("cafe\u{301}/wall_old.png"));
+    assert!(set.matches("MENU/Lunch.txt"));
+    assert_eq!(
+      set.captures_path("MENU/Lunch.txt").unwrap().get(1),
+      Some(&Some("Lunch".to_string()))
+    );
+    assert!(!FilterSet::default()
+      .with_include("café/*.png".parse::<crate::Filter>().unwrap())
+      .matches("Cafe\u{301}/Wall.PNG"));
+  }
+
+  #[test]
+  fn list_file() {
+    let path = std::env::temp_dir().join(format!("rhg_pack_filters_{}.txt", std::process::id()));
+    std::fs::write(
+      &path,
+      "# levels\nlevels/*.map\n\nregex:^sounds/.*\\.ogg$\nexclude:levels/test_*\nexclude:regex:_wip\n",
+    )
+    .unwrap();
+    let set = FilterSet::default().with_file(&path).unwrap();
+    assert_eq!(set.include().len(), 2);
+    assert_eq!(set.exclude().len(), 2);
+    assert!(set.matches("levels/e1m1.map"));
+    assert!(!set.matches("levels/test_1.map"));
+    assert!(set.matches("sounds/step.ogg"));
+    assert!(!set.matches("sounds/step_wip.ogg"));
+
+    std::fs::write(&path, "ok/*\n[z-a]\n").unwrap();
+    let e = FilterSet::default().with_file(&path).unwrap_err();
+    assert!(e.message().contains(":2:"));
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    std::fs::write(&path, "regex:(\n").unwrap();
+    let e = FilterSet::default().with_file(&path).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidInput);
+    std::fs::remove_file(&path).unwrap();
+  }
+}
@@ -0,0 +1,90 @@
+use std::io::{IsTerminal as _, Write as _};
+
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// When to colorize the output
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ColorChoice {
+  /// Colorize when writing to a terminal
+  #[default]
+  Auto,
+  Always,
+  Never,
+}
+
+impl ColorChoice {
+  pub fn enabled(&self) -> bool {
+    match self {
+      Self::Auto => std::io::stderr().is_terminal(),
+      Self::Always => true,
+      Self::Never => false,
+    }
+  }
+}
+
+/// Logger writing records to stderr, prefixed by their level
+pub struct PackLogger {
+  level: LevelFilter,
+  color: bool,
+}
+
+impl PackLogger {
+  pub fn new(level: LevelFilter, color: ColorChoice) -> Self {
+    Self {
+      level,
+      color: color.enabled(),
+    }
+  }
+
+  /// Install the logger as the global `log` facade
+  pub fn init(self) -> Result<(), log::SetLoggerError> {
+    log::set_max_level(self.level);
+    log::set_boxed_logger(Box::new(self))
+  }
+
+  fn prefix(&self, level: Level) -> String {
+    let (color, name) = match level {
+      Level::Error => ("0;31", "error"),
+      Level::Warn => ("0;33", "warn"),
+      Level::Info => return String::new(),
+      Level::Debug => ("0;36", "debug"),
+      Level::Trace => ("0;90", "trace"),
+    };
+    match self.color {
+      true => format!("\x1b[{}m{}\x1b[0m: ", color, name),
+      false => format!("{}: ", name),
+    }
+  }
+}
+
+impl Log for PackLogger {
+  fn enabled(&self, metadata: &Metadata) -> bool {
+    metadata.level() <= self.level
+  }
+
+  fn log(&self, record: &Record) {
+    if self.enabled(record.metadata()) {
+      let _ = writeln!(
+        std::io::stderr(),
+        "{}{}",
+        self.prefix(record.level()),
+        record.args()
+      );
+    }
+  }
+
+  fn flush(&self) {
+    let _ = std::io::stderr().flush();
+  }
+}
+
+/// Map the `-q` / `-v` flags to the maximum level to log
+pub fn verbosity_level(quiet: bool, verbose: u8) -> LevelFilter {
+  match (quiet, verbose) {
+    (true, _) => LevelFilter::Error,
+    (false, 0) => LevelFilter::Info,
+    (false, 1) => LevelFilter::Debug,
+    (false, _) => LevelFilter::Trace,
+  }
+}
@@ -0,0 +1,520 @@
+use std::{borrow::Cow, path::Path};
+
+use unicode_normalization::{is_nfc, UnicodeNormalization as _};
+
+use crate::{filter::path_to_str, Filter, FilterOptions, FilterPart};
+
+/// Case folded form of a character, used when ignoring case
+fn fold(ch: char) -> char {
+  let mut lower = ch.to_lowercase();
+  match (lower.next(), lower.next()) {
+    (Some(lower), None) => lower,
+    _ => ch,
+  }
+}
+
+/// Case folded form of class ranges, the characters of each range are folded one by one as
+/// case folding does not preserve ranges
+fn fold_ranges(ranges: &[(char, char)]) -> Vec<(char, char)> {
+  let mut chars: Vec<char> = ranges
+    .iter()
+    .flat_map(|(lo, hi)| (*lo..=*hi).map(fold))
+    .collect();
+  chars.sort_unstable();
+  chars.dedup();
+  let mut ret: Vec<(char, char)> = vec![];
+  for ch in chars {
+    match ret.last_mut() {
+      Some((_, hi)) if *hi as u32 + 1 == ch as u32 => *hi = ch,
+      _ => ret.push((ch, ch)),
+    }
+  }
+  ret
+}
+
+pub(crate) fn nfc(s: &str) -> Cow<'_, str> {
+  match is_nfc(s) {
+    true => Cow::Borrowed(s),
+    false => Cow::Owned(s.nfc().collect()),
+  }
+}
+
+/// Set of characters a single instruction accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CharSet {
+  Any,
+  NotSeparator,
+  Literal(char),
+  Class {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+  },
+}
+
+impl CharSet {
+  /// Whether the set contains `ch`, whose case folded form is `folded` when ignoring case and
+  /// `ch` otherwise. Literals and class ranges are folded when compiling.
+  fn contains(&self, ch: char, folded: char) -> bool {
+    match self {
+      Self::Any => true,
+      Self::NotSeparator => ch != '/',
+      Self::Literal(c) => *c == folded,
+      Self::Class { negated, ranges } => {
+        let found = ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&folded));
+        ch != '/' && found != *negated
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+  /// Consume a character of the set and jump
+  Char(CharSet, usize),
+  /// Consume a character that is not a separator, jumping to `eq` if it is `ch`, to `ne` otherwise
+  Branch {
+    ch: char,
+    eq: usize,
+    ne: usize,
+  },
+  /// Fork, the first branch has priority
+  Split(usize, usize),
+  Jmp(usize),
+  /// Record the current offset in a capture slot
+  Save(usize),
+  Match,
+}
+
+/// A [`Filter`] compiled to a non-deterministic automaton.
+///
+/// Matching simulates every alternative at once (Pike VM), so it runs in
+/// `O(path length * pattern length)` whatever the pattern, while reporting the
+/// same captures a backtracking matcher preferring shorter matches would.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterMatcher {
+  filter: Filter,
+  options: FilterOptions,
+  insts: Vec<Inst>,
+}
+
+impl FilterMatcher {
+  pub fn new(filter: &Filter) -> Self {
+    Self::compile(filter, FilterOptions::default())
+  }
+
+  pub fn compile(filter: &Filter, options: FilterOptions) -> Self {
+    let mut m = Self {
+      filter: filter.clone(),
+      options,
+      insts: vec![],
+    };
+    for (i, part) in filter.iter().enumerate() {
+      m.insts.push(Inst::Save(i * 2));
+      m.compile_part(part);
+      m.insts.push(Inst::Save(i * 2 + 1));
+    }
+    m.insts.push(Inst::Match);
+    m
+  }
+
+  /// Filter this matcher was compiled from
+  pub fn filter(&self) -> &Filter {
+    &self.filter
+  }
+
+  pub fn options(&self) -> FilterOptions {
+    self.options
+  }
+
+  /// Form of a pattern character the input gets compared to
+  fn pattern_char(&self, ch: char) -> char {
+    match self.options.case_insensitive {
+      true => fold(ch),
+      false => ch,
+    }
+  }
+
+  /// Form of a pattern string the input gets compared to
+  fn pattern_chars(&self, s: &str) -> Vec<char> {
+    let s = match self.options.normalize {
+      true => nfc(s),
+      false => Cow::Borrowed(s),
+    };
+    s.chars().map(|ch| self.pattern_char(ch)).collect()
+  }
+
+  /// Form of a class range bound, bounds decomposing into several characters are kept as is
+  fn class_char(&self, ch: char) -> char {
+    if !self.options.normalize {
+      return ch;
+    }
+    let mut composed = std::iter::once(ch).nfc();
+    match (composed.next(), composed.next()) {
+      (Some(composed), None) => composed,
+      _ => ch,
+    }
+  }
+
+  /// Input as it gets compared to the pattern
+  fn input<'a>(&self, expr: &'a str) -> Cow<'a, str> {
+    match self.options.normalize {
+      true => nfc(expr),
+      false => Cow::Borrowed(expr),
+    }
+  }
+
+  fn pc(&self) -> usize {
+    self.insts.len()
+  }
+
+  /// Emit the instructions of a part, which continue to the instruction emitted right after them
+  fn compile_part(&mut self, part: &FilterPart) {
+    let start = self.pc();
+    match part {
+      FilterPart::Star => self.compile_repeat(CharSet::NotSeparator),
+      FilterPart::GlobStar => self.compile_repeat(CharSet::Any),
+      FilterPart::Directories => {
+        // zero or more characters ending with a separator, as short as possible
+        let out = start + 4;
+        self.insts.push(Inst::Split(out, start + 1));
+        self.insts.push(Inst::Split(start + 3, start + 2));
+        self.insts.push(Inst::Char(CharSet::Any, start + 1));
+        self.insts.push(Inst::Char(CharSet::Literal('/'), out));
+      }
+      FilterPart::Wildcard => self
+        .insts
+        .push(Inst::Char(CharSet::NotSeparator, start + 1)),
+      FilterPart::Class { negated, ranges } => {
+        let mut ranges: Vec<(char, char)> = ranges
+          .iter()
+          .map(|(lo, hi)| (self.class_char(*lo), self.class_char(*hi)))
+          .collect();
+        if self.options.case_insensitive {
+          ranges = fold_ranges(&ranges);
+        }
+        self.insts.push(Inst::Char(
+          CharSet::Class {
+            negated: *negated,
+            ranges,
+          },
+          start + 1,
+        ))
+      }
+      FilterPart::Exact(q) => {
+        for ch in self.pattern_chars(q) {
+          let next = self.pc() + 1;
+          self.insts.push(Inst::Char(CharSet::Literal(ch), next));
+        }
+      }
+      FilterPart::Exclude(value) => {
+        // Track how much of `value` was consumed so far, the part may only end
+        // once the captured text diverged from it or is a strict prefix of it
+        let value = self.pattern_chars(value);
+        let diverged = start + value.len() * 2 + 1;
+        let out = diverged + 2;
+        for (k, ch) in value.iter().enumerate() {
+          let state = start + k * 2;
+          self.insts.push(Inst::Split(out, state + 1));
+          self.insts.push(Inst::Branch {
+            ch: *ch,
+            eq: state + 2,
+            ne: diverged,
+          });
+        }
+        self.insts.push(Inst::Char(CharSet::NotSeparator, diverged));
+        self.insts.push(Inst::Split(out, diverged + 1));
+        self.insts.push(Inst::Char(CharSet::NotSeparator, diverged));
+      }
+      FilterPart::Alternation(alternatives) => {
+        let mut jumps = vec![];
+        for (i, alt) in alternatives.iter().enumerate() {
+          let split = self.pc();
+          let is_last = i + 1 == alternatives.len();
+          if !is_last {
+            self.insts.push(Inst::Split(split + 1, 0));
+          }
+          for part in alt.iter() {
+            self.compile_part(part);
+          }
+          if !is_last {
+            jumps.push(self.pc());
+            self.insts.push(Inst::Jmp(0));
+            let next = self.pc();
+            self.insts[split] = Inst::Split(split + 1, next);
+          }
+        }
+        let out = self.pc();
+        for jump in jumps {
+          self.insts[jump] = Inst::Jmp(out);
+        }
+      }
+    }
+  }
+
+  /// Zero or more characters of the set, as few as possible
+  fn compile_repeat(&mut self, set: CharSet) {
+    let start = self.pc();
+    self.insts.push(Inst::Split(start + 2, start + 1));
+    self.insts.push(Inst::Char(set, start));
+  }
+
+  pub fn matches(&self, expr: &str) -> bool {
+    self.run(&self.input(expr), 0).is_some()
+  }
+
+  /// Match against a path, using `/` as the separator whatever the platform
+  pub fn matches_path<P: AsRef<Path>>(&self, path: P) -> bool {
+    self.matches(&path_to_str(path))
+  }
+
+  /// Text captured by each part, taken from the normalized input when normalizing
+  pub fn capture(&self, expr: &str) -> Option<Vec<(FilterPart, String)>> {
+    let expr = self.input(expr);
+    let slots = self.run(&expr, self.filter.len() * 2)?;
+    Some(
+      self
+        .filter
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+          (
+            part.clone(),
+            expr[slots[i * 2]..slots[i * 2 + 1]].to_string(),
+          )
+        })
+        .collect(),
+    )
+  }
+
+  pub fn capture_path<P: AsRef<Path>>(&self, path: P) -> Option<Vec<(FilterPart, String)>> {
+    self.capture(&path_to_str(path))
+  }
+
+  fn run(&self, expr: &str, num_slots: usize) -> Option<Vec<usize>> {
+    self.run_counting(expr, num_slots, &mut 0)
+  }
+
+  /// Adds the number of threads stepped over the input to `steps`, at most one per instruction
+  /// and character
+  fn run_counting(&self, expr: &str, num_slots: usize, steps: &mut usize) -> Option<Vec<usize>> {
+    let mut current = Threads::new(self.insts.len());
+    let mut next = Threads::new(self.insts.len());
+    self.add_thread(&mut current, 0, 0, vec![0; num_slots]);
+    for (i, ch) in expr.char_indices() {
+      if current.threads.is_empty() {
+        return None;
+      }
+      let pos = i + ch.len_utf8();
+      let folded = self.pattern_char(ch);
+      *steps += current.threads.len();
+      for (pc, slots) in current.threads.drain(..) {
+        match &self.insts[pc] {
+          Inst::Char(set, goto) if set.contains(ch, folded) => {
+            self.add_thread(&mut next, *goto, pos, slots)
+          }
+          Inst::Branch { ch: c, eq, ne } if ch != '/' => {
+            let goto = if *c == folded { *eq } else { *ne };
+            self.add_thread(&mut next, goto, pos, slots)
+          }
+          _ => {}
+        }
+      }
+      std::mem::swap(&mut current, &mut next);
+      next.clear();
+    }
+    current
+      .threads
+      .into_iter()
+      .find(|(pc, _)| self.insts[*pc] == Inst::Match)
+      .map(|(_, slots)| slots)
+  }
+
+  /// Follow the epsilon transitions from `pc`, queueing the threads that wait for input
+  fn add_thread(&self, threads: &mut Threads, pc: usize, pos: usize, mut slots: Vec<usize>) {
+    if threads.visited[pc] {
+      return;
+    }
+    threads.visited[pc] = true;
+    match &self.insts[pc] {
+      Inst::Jmp(goto) => self.add_thread(threads, *goto, pos, slots),
+      Inst::Split(first, second) => {
+        self.add_thread(threads, *first, pos, slots.clone());
+        self.add_thread(threads, *second, pos, slots);
+      }
+      Inst::Save(slot) => {
+        if let Some(value) = slots.get_mut(*slot) {
+          *value = pos;
+        }
+        self.add_thread(threads, pc + 1, pos, slots)
+      }
+      _ => threads.threads.push((pc, slots)),
+    }
+  }
+}
+
+impl From<&Filter> for FilterMatcher {
+  fn from(filter: &Filter) -> Self {
+    Self::new(filter)
+  }
+}
+
+/// Threads alive at a given offset, in priority order
+struct Threads {
+  visited: Vec<bool>,
+  threads: Vec<(usize, Vec<usize>)>,
+}
+
+impl Threads {
+  fn new(num_insts: usize) -> Self {
+    Self {
+      visited: vec![false; num_insts],
+      threads: vec![],
+    }
+  }
+
+  fn clear(&mut self) {
+    self.visited.iter_mut().for_each(|v| *v = false);
+    self.threads.clear();
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use crate::{Filter, FilterOptions, FilterPart};
+
+  use super::FilterMatcher;
+
+  fn matcher(pattern: &str) -> FilterMatcher {
+    FilterMatcher::new(&pattern.parse::<Filter>().expect("parse"))
+  }
+
+  fn steps(m: &FilterMatcher, expr: &str) -> usize {
+    let mut steps = 0;
+    m.run_counting(expr, 0, &mut steps);
+    steps
+  }
+
+  #[test]
+  fn backtracking() {
+    let m = matcher("a*b*c");
+    assert!(m.matches("axbxbxc"));
+    assert!(m.matches("abc"));
+    assert!(!m.matches("axbxbx"));
+    assert_eq!(
+      m.capture("axbxbxc").unwrap()[1..4]
+        .iter()
+        .map(|(_, s)| s.as_str())
+        .collect::<Vec<_>>(),
+      vec!["x", "b", "xbx"]
+    );
+
+    let m = matcher("*.png.*");
+    assert!(m.matches("a.png.png.bak"));
+  }
+
+  #[test]
+  fn exclude() {
+    let m = matcher("a!(bc)d");
+    assert!(!m.matches("abcd"));
+    assert!(m.matches("abd"));
+    assert!(m.matches("abcxd"));
+    assert!(m.matches("ad"));
+    assert!(!m.matches("ab/d"));
+
+    let m = matcher("!()");
+    assert!(!m.matches(""));
+    assert!(m.matches("a"));
+  }
+
+  #[test]
+  fn alternation_priority() {
+    assert_eq!(
+      matcher("{a,ab}*").capture("abc").unwrap(),
+      vec![
+        (
+          FilterPart::Alternation(vec![
+            "a".parse::<Filter>().unwrap(),
+            "ab".parse::<Filter>().unwrap()
+          ]),
+          String::from("a")
+        ),
+        (FilterPart::Star, String::from("bc")),
+      ]
+    );
+  }
+
+  #[test]
+  fn utf8() {
+    let m = matcher("é?/[à-ü]*.png");
+    assert!(m.matches("éa/üb.png"));
+    assert!(m.matches("éà/ê.png"));
+    assert!(!m.matches("éa/zb.png"));
+    assert_eq!(
+      matcher("*é*").capture("aébé").unwrap()[0].1,
+      String::from("a")
+    );
+  }
+
+  #[test]
+  fn case_insensitive() {
+    let options = FilterOptions::default().with_case_insensitive(true);
+    let m = FilterMatcher::compile(&"textures/*.png".parse::<Filter>().unwrap(), options);
+    assert!(m.matches("Textures/Wall.PNG"));
+    assert!(!matcher("textures/*.png").matches("Textures/Wall.PNG"));
+    let m = FilterMatcher::compile(&"[a-c]!(WALL)[É]".parse::<Filter>().unwrap(), options);
+    assert!(m.matches("Bflooré"));
+    assert!(!m.matches("bwallé"));
+    assert!(!m.matches("dfloorÉ"));
+    let m = FilterMatcher::compile(&"[A-Z]*.png".parse::<Filter>().unwrap(), options);
+    assert!(m.matches("wall.png"));
+  }
+
+  #[test]
+  fn class_case_sensitive() {
+    assert!(!matcher("[A-Z]*.png").matches("wall.png"));
+    assert!(matcher("[A-Z]*.png").matches("Wall.png"));
+    assert!(matcher("[!A-Z]*.png").matches("wall.png"));
+    assert!(!matcher("[!a-z]*.png").matches("wall.png"));
+    assert!(!matcher("[É]").matches("é"));
+  }
+
+  #[test]
+  fn normalize() {
+    let options = FilterOptions::default().with_normalize(true);
+    // Pattern composed, path decomposed as written by macOS
+    let m = FilterMatcher::compile(&"café/*".parse::<Filter>().unwrap(), options);
+    assert!(m.matches("cafe\u{301}/menu.txt"));
+    assert!(!matcher("café/*").matches("cafe\u{301}/menu.txt"));
+    let m = FilterMatcher::compile(&"cafe\u{301}/[é]*".parse::<Filter>().unwrap(), options);
+    assert!(m.matches("café/éclair"));
+    assert_eq!(m.capture("café/e\u{301}clair").unwrap()[2].1, "clair");
+  }
+
+  #[test]
+  fn pathological_pattern_is_linear() {
+    let pattern = "*a".repeat(20) + "b";
+    let m = matcher(&pattern);
+    let expr = "a".repeat(10_000);
+    assert!(!m.matches(&expr));
+    assert!(m.matches(&(expr.clone() + "b")));
+    // Backtracking would try about C(10000, 20) ways to place the `*`, the VM steps each
+    // instruction at most once per character and grows linearly with the input
+    let steps_long = steps(&m, &expr);
+    assert!(steps_long <= expr.len() * m.insts.len());
+    assert!(steps_long <= steps(&m, &"a".repeat(1_000)) * 11);
+  }
+
+  #[test]
+  fn long_paths() {
+    let m = matcher("**/textures/**/{wall,floor}_*.{png,jpg}");
+    let dirs = (0..2_000)
+      .map(|i| format!("dir{}", i))
+      .collect::<Vec<_>>()
+      .join("/");
+    let expr = format!("{}/textures/{}/wall_01.png", dirs, dirs);
+    assert!(m.matches(&expr));
+    assert!(!m.matches(&format!("{}/textures/{}/roof_01.png", dirs, dirs)));
+    assert!(!m.matches(&format!("{}/models/{}/wall_01.png", dirs, dirs)));
+    assert!(steps(&m, &expr) <= expr.len() * m.insts.len());
+  }
+}
@@ -1,5 +1,25 @@
+pub mod convert;
+pub mod exit_code;
 pub mod filter;
+pub mod filter_set;
+pub mod logger;
+pub mod matcher;
+#[cfg(feature = "fuse")]
+pub mod mount;
 pub mod options;
+pub mod pack;
+pub mod rename;
+pub mod watch;
 
+pub use convert::*;
+pub use exit_code::*;
 pub use filter::*;
+pub use filter_set::*;
+pub use logger::*;
+pub use matcher::*;
+#[cfg(feature = "fuse")]
+pub use mount::*;
 pub use options::*;
+pub use pack::*;
+pub use rename::*;
+pub use watch::*;
@@ -0,0 +1,375 @@
+use std::{
+  collections::BTreeMap,
+  ffi::OsStr,
+  os::unix::fs::MetadataExt as _,
+  path::{Component, Path},
+  time::{Duration, SystemTime},
+};
+
+use fuser::{
+  FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+  Request,
+};
+use rhg_engine_core::{err, here, Archive, Error, ErrorKind};
+
+/// How long the kernel may cache attributes, the archive never changes while mounted
+const TTL: Duration = Duration::from_secs(1);
+
+pub const ROOT_INODE: u64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveNodeKind {
+  /// Children indexed by name
+  Directory(BTreeMap<String, u64>),
+  /// Index of the file in the archive
+  File(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveNode {
+  pub parent: u64,
+  pub kind: ArchiveNodeKind,
+  pub attr: FileAttr,
+}
+
+/// Read-only file system exposing the entries of an archive
+pub struct ArchiveFs {
+  archive: Archive,
+  nodes: Vec<ArchiveNode>,
+}
+
+impl ArchiveFs {
+  pub fn new(archive: Archive, uid: u32, gid: u32) -> rhg_engine_core::Result<Self> {
+    let mut fs = Self {
+      archive,
+      nodes: vec![],
+    };
+    fs.push_node(
+      ROOT_INODE,
+      ArchiveNodeKind::Directory(BTreeMap::new()),
+      0,
+      uid,
+      gid,
+    );
+    for i in 0..fs.archive.files().len() {
+      let path = fs.archive.files()[i].path().clone();
+      let names = path
+        .components()
+        .filter_map(|c| match c {
+          Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+          _ => None,
+        })
+        .collect::<Vec<_>>();
+      let (file_name, dir_names) = match names.split_last() {
+        Some(split) => split,
+        None => continue,
+      };
+      let mut parent = ROOT_INODE;
+      for name in dir_names {
+        parent = match fs.child(parent, name) {
+          Some(ino) if fs.is_dir(ino) => ino,
+          Some(_) => {
+            return err!(
+              ErrorKind::InvalidInput,
+              format!("entry '{}' conflicts with file '{}'", path.display(), name)
+            )
+          }
+          None => fs.insert(
+            parent,
+            name,
+            ArchiveNodeKind::Directory(BTreeMap::new()),
+            0,
+            uid,
+            gid,
+          ),
+        };
+      }
+      if fs.child(parent, file_name).is_some() {
+        return err!(
+          ErrorKind::InvalidInput,
+          format!("duplicate entry '{}'", path.display())
+        );
+      }
+      let size = fs.archive.files()[i].content_len() as u64;
+      let ino = fs.insert(parent, file_name, ArchiveNodeKind::File(i), size, uid, gid);
+      fs.stamp(ino, i);
+    }
+    Ok(fs)
+  }
+
+  pub fn archive(&self) -> &Archive {
+    &self.archive
+  }
+
+  pub fn node(&self, ino: u64) -> Option<&ArchiveNode> {
+    ino
+      .checked_sub(ROOT_INODE)
+      .and_then(|i| self.nodes.get(i as usize))
+  }
+
+  pub fn child(&self, parent: u64, name: &str) -> Option<u64> {
+    match self.node(parent).map(|node| &node.kind) {
+      Some(ArchiveNodeKind::Directory(children)) => children.get(name).copied(),
+      _ => None,
+    }
+  }
+
+  /// Resolve a slash separated path to its inode
+  pub fn lookup_path<P: AsRef<Path>>(&self, path: P) -> Option<u64> {
+    let mut ino = ROOT_INODE;
+    for c in path.as_ref().components() {
+      if let Component::Normal(name) = c {
+        ino = self.child(ino, &name.to_string_lossy())?;
+      }
+    }
+    Some(ino)
+  }
+
+  fn is_dir(&self, ino: u64) -> bool {
+    matches!(
+      self.node(ino).map(|node| &node.kind),
+      Some(ArchiveNodeKind::Directory(_))
+    )
+  }
+
+  fn push_node(
+    &mut self,
+    parent: u64,
+    kind: ArchiveNodeKind,
+    size: u64,
+    uid: u32,
+    gid: u32,
+  ) -> u64 {
+    let ino = self.nodes.len() as u64 + ROOT_INODE;
+    let (file_type, perm, nlink) = match kind {
+      ArchiveNodeKind::Directory(_) => (FileType::Directory, 0o555, 2),
+      ArchiveNodeKind::File(_) => (FileType::RegularFile, 0o444, 1),
+    };
+    self.nodes.push(ArchiveNode {
+      parent,
+      kind,
+      attr: FileAttr {
+        ino,
+        size,
+        blocks: size.div_ceil(512),
+        atime: SystemTime::UNIX_EPOCH,
+        mtime: SystemTime::UNIX_EPOCH,
+        ctime: SystemTime::UNIX_EPOCH,
+        crtime: SystemTime::UNIX_EPOCH,
+        kind: file_type,
+        perm,
+        nlink,
+        uid,
+        gid,
+        rdev: 0,
+        blksize: 512,
+        flags: 0,
+      },
+    });
+    ino
+  }
+
+  fn insert(
+    &mut self,
+    parent: u64,
+    name: &str,
+    kind: ArchiveNodeKind,
+    size: u64,
+    uid: u32,
+    gid: u32,
+  ) -> u64 {
+    let ino = self.push_node(parent, kind, size, uid, gid);
+    if let ArchiveNodeKind::Directory(children) =
+      &mut self.nodes[(parent - ROOT_INODE) as usize].kind
+    {
+      children.insert(name.to_string(), ino);
+    }
+    ino
+  }
+
+  /// Apply the file timestamps to its node, and bubble the latest ones up to its ancestors
+  fn stamp(&mut self, ino: u64, index: usize) {
+    let file = &self.archive.files()[index];
+    let archived_at = file
+      .archived_at()
+      .copied()
+      .unwrap_or(SystemTime::UNIX_EPOCH);
+    let modified_at = file.modified_at().copied().unwrap_or(archived_at);
+    let created_at = file.created_at().copied().unwrap_or(modified_at);
+    let attr = &mut self.nodes[(ino - ROOT_INODE) as usize].attr;
+    attr.atime = archived_at;
+    attr.ctime = archived_at;
+    attr.mtime = modified_at;
+    attr.crtime = created_at;
+    let mut ino = self.nodes[(ino - ROOT_INODE) as usize].parent;
+    loop {
+      let node = &mut self.nodes[(ino - ROOT_INODE) as usize];
+      node.attr.atime = node.attr.atime.max(archived_at);
+      node.attr.ctime = node.attr.ctime.max(archived_at);
+      node.attr.mtime = node.attr.mtime.max(modified_at);
+      node.attr.crtime = node.attr.crtime.max(created_at);
+      if ino == ROOT_INODE {
+        break;
+      }
+      ino = node.parent;
+    }
+  }
+}
+
+impl Filesystem for ArchiveFs {
+  fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+    match self
+      .child(parent, &name.to_string_lossy())
+      .and_then(|ino| self.node(ino))
+    {
+      Some(node) => reply.entry(&TTL, &node.attr, 0),
+      None => reply.error(libc::ENOENT),
+    }
+  }
+
+  fn getattr(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+    match self.node(ino) {
+      Some(node) => reply.attr(&TTL, &node.attr),
+      None => reply.error(libc::ENOENT),
+    }
+  }
+
+  fn read(
+    &mut self,
+    _req: &Request<'_>,
+    ino: u64,
+    _fh: u64,
+    offset: i64,
+    size: u32,
+    _flags: i32,
+    _lock_owner: Option<u64>,
+    reply: ReplyData,
+  ) {
+    match self.node(ino).map(|node| &node.kind) {
+      Some(ArchiveNodeKind::File(i)) => {
+        let content = self.archive.files()[*i].content();
+        let start = (offset.max(0) as usize).min(content.len());
+        let end = start.saturating_add(size as usize).min(content.len());
+        reply.data(&content[start..end]);
+      }
+      Some(ArchiveNodeKind::Directory(_)) => reply.error(libc::EISDIR),
+      None => reply.error(libc::ENOENT),
+    }
+  }
+
+  fn readdir(
+    &mut self,
+    _req: &Request<'_>,
+    ino: u64,
+    _fh: u64,
+    offset: i64,
+    mut reply: ReplyDirectory,
+  ) {
+    let node = match self.node(ino) {
+      Some(node) => node,
+      None => return reply.error(libc::ENOENT),
+    };
+    let children = match &node.kind {
+      ArchiveNodeKind::Directory(children) => children,
+      ArchiveNodeKind::File(_) => return reply.error(libc::ENOTDIR),
+    };
+    let entries = [(ino, ".".to_string()), (node.parent, "..".to_string())]
+      .into_iter()
+      .chain(children.iter().map(|(name, ino)| (*ino, name.clone())));
+    for (i, (ino, name)) in entries.enumerate().skip(offset.max(0) as usize) {
+      let kind = match self.is_dir(ino) {
+        true => FileType::Directory,
+        false => FileType::RegularFile,
+      };
+      if reply.add(ino, (i + 1) as i64, kind, name) {
+        break;
+      }
+    }
+    reply.ok();
+  }
+}
+
+/// Mount the archive at `mountpoint`, blocking until it gets unmounted
+pub fn mount<P: AsRef<Path>>(archive: Archive, mountpoint: P) -> rhg_engine_core::Result<()> {
+  let md = std::fs::metadata(&mountpoint).map_err(|e| {
+    Error::new(
+      ErrorKind::IO,
+      format!("{}: {}", mountpoint.as_ref().display(), e),
+      None,
+      here!(),
+    )
+  })?;
+  let fs = ArchiveFs::new(archive, md.uid(), md.gid())?;
+  let options = [
+    MountOption::RO,
+    MountOption::FSName("rhg_pack".to_string()),
+    MountOption::DefaultPermissions,
+  ];
+  fuser::mount2(fs, &mountpoint, &options).map_err(|e| {
+    Error::new(
+      ErrorKind::IO,
+      format!("failed to mount '{}', {}", mountpoint.as_ref().display(), e),
+      None,
+      here!(),
+    )
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use std::time::{Duration, SystemTime};
+
+  use rhg_engine_core::Archive;
+
+  use super::{ArchiveFs, ArchiveNodeKind, ROOT_INODE};
+
+  #[test]
+  fn tree() {
+    let mut a = Archive::default();
+    a.add_file("textures/wall.png", b"wall").unwrap();
+    a.add_file("textures/floor/stone.png", b"stone").unwrap();
+    a.add_file("readme.txt", b"hi").unwrap();
+    let fs = ArchiveFs::new(a, 0, 0).unwrap();
+
+    let root = fs.node(ROOT_INODE).unwrap();
+    match &root.kind {
+      ArchiveNodeKind::Directory(children) => assert_eq!(
+        children.keys().collect::<Vec<_>>(),
+        vec!["readme.txt", "textures"]
+      ),
+      kind => panic!("root is not a directory: {:?}", kind),
+    }
+    let stone = fs.lookup_path("textures/floor/stone.png").unwrap();
+    assert_eq!(fs.node(stone).unwrap().attr.size, 5);
+    assert_eq!(
+      fs.node(stone).unwrap().parent,
+      fs.lookup_path("textures/floor").unwrap()
+    );
+    assert!(fs.lookup_path("textures/missing.png").is_none());
+  }
+
+  #[test]
+  fn timestamps() {
+    let mut buf = vec![];
+    let mut a = Archive::default();
+    a.add_file("a/b.txt", b"b").unwrap();
+    a.save(None, &mut buf).unwrap();
+    let a = Archive::load("test.pack", &mut buf.as_slice()).unwrap();
+    let archived_at = *a.files()[0].archived_at().unwrap();
+    let fs = ArchiveFs::new(a, 0, 0).unwrap();
+
+    let b = fs.node(fs.lookup_path("a/b.txt").unwrap()).unwrap();
+    assert_eq!(b.attr.ctime, archived_at);
+    let dir = fs.node(fs.lookup_path("a").unwrap()).unwrap();
+    assert_eq!(dir.attr.ctime, archived_at);
+    assert!(archived_at > SystemTime::UNIX_EPOCH + Duration::from_secs(1));
+  }
+
+  #[test]
+  fn conflict() {
+    let mut a = Archive::default();
+    a.add_file("a", b"a").unwrap();
+    a.add_file("a/b", b"b").unwrap();
+    assert!(ArchiveFs::new(a, 0, 0).is_err());
+  }
+}
@@ -1,13 +1,29 @@
 use std::path::PathBuf;
 
-use clap::{builder::ValueParser, command, Parser, Subcommand};
+use clap::{builder::ValueParser, command, ArgAction, Args, Parser, Subcommand};
+use regex::Regex;
 
-use crate::{parse_filter, Filter};
+use crate::{
+  parse_filter, parse_pattern, parse_regex, parse_rename_template, ColorChoice, ConvertFormat,
+  Filter, FilterOptions, FilterPattern, FilterSet, RenameTemplate, EXIT_CODES_HELP,
+};
 
 #[derive(Parser, Debug)]
-#[command(author = env!("CARGO_PKG_AUTHORS"), version = env!("CARGO_PKG_VERSION"), about, long_about)]
+#[command(author = env!("CARGO_PKG_AUTHORS"), version = env!("CARGO_PKG_VERSION"), about, long_about, after_help = EXIT_CODES_HELP)]
 /// Command-line resource packing utility
 pub struct CliOptions {
+  /// Only print errors
+  #[arg(short, long, global = true, conflicts_with = "verbose")]
+  pub quiet: bool,
+
+  /// Print more details, repeat for even more
+  #[arg(short, long, global = true, action = ArgAction::Count)]
+  pub verbose: u8,
+
+  /// When to colorize the output
+  #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+  pub color: ColorChoice,
+
   /// Main application mode
   #[command(subcommand)]
   pub command: Command,
@@ -20,6 +36,9 @@ pub struct AddCommandOptions {
   /// Files to add to the archive
   #[arg(num_args = 1..)]
   pub files: Vec<PathBuf>,
+  /// Number of files read in parallel, defaults to the number of CPUs
+  #[arg(short, long)]
+  pub jobs: Option<usize>,
 }
 
 #[derive(Parser, Debug)]
@@ -31,6 +50,65 @@ pub struct UpdateCommandOptions {
   pub files: Vec<PathBuf>,
 }
 
+#[derive(Args, Debug, Default)]
+pub struct FilterSetOptions {
+  /// Select entries whose path matches a regular expression
+  #[arg(long, value_parser = ValueParser::new(parse_regex))]
+  pub regex: Vec<Regex>,
+
+  /// Leave out entries matching a filter, even when selected
+  #[arg(short = 'x', long, value_parser = ValueParser::new(parse_filter))]
+  pub exclude: Vec<Filter>,
+
+  /// Read filters from a file, one per line, prefixed by `regex:` for regular expressions and
+  /// `exclude:` for exclusions
+  #[arg(long)]
+  pub from_file: Vec<PathBuf>,
+
+  /// Match filters against file names instead of full entry paths
+  #[arg(short, long)]
+  pub name: bool,
+
+  /// Ignore case when matching filters
+  #[arg(short = 'i', long)]
+  pub ignore_case: bool,
+
+  /// Compare the Unicode NFC normalized forms of filters and paths
+  #[arg(long)]
+  pub nfc: bool,
+}
+
+impl FilterSetOptions {
+  pub fn filter_options(&self) -> FilterOptions {
+    FilterOptions::default()
+      .with_case_insensitive(self.ignore_case)
+      .with_normalize(self.nfc)
+  }
+
+  /// Combine the options with the positional filters of a command
+  pub fn filter_set<P: Into<FilterPattern> + Clone>(
+    &self,
+    filters: &[P],
+  ) -> rhg_engine_core::Result<FilterSet> {
+    let mut set = FilterSet::default()
+      .with_by_name(self.name)
+      .with_options(self.filter_options());
+    for filter in filters {
+      set = set.with_include(filter.clone());
+    }
+    for re in &self.regex {
+      set = set.with_include(re.clone());
+    }
+    for filter in &self.exclude {
+      set = set.with_exclude(filter.clone());
+    }
+    for path in &self.from_file {
+      set = set.with_file(path)?;
+    }
+    Ok(set)
+  }
+}
+
 const DEFAULT_LIST_TEMPLATE: &'static str = "%offset %archived_at %name";
 
 #[derive(Parser, Debug)]
@@ -41,6 +119,9 @@ pub struct ListCommandOptions {
   /// Filter listed files
   #[arg(value_parser = ValueParser::new(parse_filter))]
   pub filters: Vec<Filter>,
+
+  #[command(flatten)]
+  pub filter_set: FilterSetOptions,
   
   /// Change output columns
   #[arg(short, long, default_value = DEFAULT_LIST_TEMPLATE)]
@@ -56,8 +137,15 @@ pub struct RemoveCommandOptions {
   /// Path of the archive to write
   pub archive: PathBuf,
   /// Files to remove from the archive
-  #[arg(num_args = 1.., value_parser = ValueParser::new(parse_filter))]
+  #[arg(
+    num_args = 1..,
+    value_parser = ValueParser::new(parse_filter),
+    required_unless_present_any = ["regex", "from_file"]
+  )]
   pub filter: Vec<Filter>,
+
+  #[command(flatten)]
+  pub filter_set: FilterSetOptions,
 }
 
 #[derive(Parser, Debug)]
@@ -66,12 +154,90 @@ pub struct ExtractCommandOptions {
   pub archive: PathBuf,
   
   /// Files to extract from the archive
-  #[arg(num_args = 1.., value_parser = ValueParser::new(parse_filter))]
+  #[arg(
+    num_args = 1..,
+    value_parser = ValueParser::new(parse_filter),
+    required_unless_present_any = ["regex", "from_file"]
+  )]
   pub filter: Vec<Filter>,
 
+  #[command(flatten)]
+  pub filter_set: FilterSetOptions,
+
   /// Optional output dir
   #[arg(short, long)]
-  pub output_dir: Option<PathBuf>
+  pub output_dir: Option<PathBuf>,
+
+  /// Write files to a path built from the filter captures, e.g. 'albedo/{1}.png'
+  #[arg(long, value_parser = ValueParser::new(parse_rename_template))]
+  pub rename: Option<RenameTemplate>,
+}
+
+#[derive(Parser, Debug)]
+pub struct MoveCommandOptions {
+  /// Path of the archive to write
+  pub archive: PathBuf,
+
+  /// Entries to rename, a filter or a regular expression prefixed by 'regex:'
+  #[arg(value_parser = ValueParser::new(parse_pattern))]
+  pub from: FilterPattern,
+
+  /// New path of the entries, built from the captures of the filter, e.g. 'albedo/{1}.png'
+  #[arg(value_parser = ValueParser::new(parse_rename_template))]
+  pub to: RenameTemplate,
+
+  #[command(flatten)]
+  pub filter_set: FilterSetOptions,
+}
+
+#[derive(Parser, Debug)]
+pub struct WatchCommandOptions {
+  /// Path of the archive to write
+  pub archive: PathBuf,
+
+  /// Source directories to watch
+  #[arg(num_args = 1..)]
+  pub sources: Vec<PathBuf>,
+
+  /// Delay to wait for changes to settle before repacking, in milliseconds
+  #[arg(short, long, default_value_t = 250)]
+  pub debounce: u64,
+}
+
+#[derive(Parser, Debug)]
+pub struct ImportCommandOptions {
+  /// Path of the zip or tar file to import
+  pub input: PathBuf,
+
+  /// Path of the archive to write
+  pub archive: PathBuf,
+
+  /// Format of the input, guessed from its extension by default
+  #[arg(short, long)]
+  pub format: Option<ConvertFormat>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ExportCommandOptions {
+  /// Path of the archive to read
+  pub archive: PathBuf,
+
+  /// Path of the zip or tar file to write
+  pub output: PathBuf,
+
+  /// Format of the output, guessed from its extension by default
+  #[arg(short, long)]
+  pub format: Option<ConvertFormat>,
+}
+
+#[cfg(feature = "fuse")]
+#[derive(Parser, Debug)]
+pub struct MountCommandOptions {
+  /// Path of the archive to mount
+  pub archive: PathBuf,
+
+  /// Directory to mount the archive on
+  pub mountpoint: PathBuf,
 }
 
 #[derive(Subcommand, Debug)]
@@ -82,8 +248,20 @@ pub enum Command {
   Update(UpdateCommandOptions),
   /// Remove files from the archive
   Remove(RemoveCommandOptions),
-  /// Remove files from the archive
+  /// Extract files from the archive
   Extract(ExtractCommandOptions),
   /// List all files contained within the archive
   List(ListCommandOptions),
+  /// Rename files inside the archive
+  #[command(name = "mv")]
+  Move(MoveCommandOptions),
+  /// Watch source directories and repack the archive when they change
+  Watch(WatchCommandOptions),
+  /// Convert a zip or tar file to an archive
+  Import(ImportCommandOptions),
+  /// Convert an archive to a zip or tar file
+  Export(ExportCommandOptions),
+  /// Mount the archive as a read-only file system
+  #[cfg(feature = "fuse")]
+  Mount(MountCommandOptions),
 }
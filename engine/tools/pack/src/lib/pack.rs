@@ -0,0 +1,251 @@
+use std::{
+  collections::HashSet,
+  fmt::Display,
+  io::BufWriter,
+  path::{Path, PathBuf},
+  time::{Duration, Instant},
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use rhg_engine_core::{err, here, ArchiveEntry, ArchiveWriter, Error, ErrorKind};
+
+/// Summary of a packing run
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PackSummary {
+  pub files: usize,
+  pub bytes: u64,
+  pub elapsed: Duration,
+}
+
+impl Display for PackSummary {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} files, {} in {}ms",
+      self.files,
+      indicatif::HumanBytes(self.bytes),
+      self.elapsed.as_millis()
+    )
+  }
+}
+
+/// Pack `files` into a new archive, reading them on a thread pool while the
+/// archive is streamed to disk in order
+pub struct Packer {
+  jobs: usize,
+  progress: bool,
+}
+
+impl Default for Packer {
+  fn default() -> Self {
+    Self {
+      jobs: rayon::current_num_threads(),
+      progress: false,
+    }
+  }
+}
+
+impl Packer {
+  /// Number of files read at the same time
+  pub fn with_jobs(mut self, jobs: usize) -> Self {
+    self.jobs = jobs.max(1);
+    self
+  }
+
+  /// Draw a progress bar on stderr
+  pub fn with_progress(mut self, progress: bool) -> Self {
+    self.progress = progress;
+    self
+  }
+
+  fn progress_bar(&self, total_bytes: u64) -> ProgressBar {
+    if !self.progress {
+      return ProgressBar::hidden();
+    }
+    let pb = ProgressBar::new(total_bytes);
+    pb.set_style(
+      ProgressStyle::with_template(
+        "[{elapsed_precise}] {wide_bar} {bytes}/{total_bytes} ({binary_bytes_per_sec}) {msg}",
+      )
+      .expect("invalid progress template"),
+    );
+    pb
+  }
+
+  pub fn pack<P: AsRef<Path>>(
+    &self,
+    archive: P,
+    files: &[PathBuf],
+  ) -> rhg_engine_core::Result<PackSummary> {
+    let start = Instant::now();
+    let pool = rayon::ThreadPoolBuilder::new()
+      .num_threads(self.jobs)
+      .build()
+      .map_err(|e| {
+        Error::new(
+          ErrorKind::Unknown,
+          format!("failed to start packing threads, {}", e),
+          None,
+          here!(),
+        )
+      })?;
+    let entries = pool.install(|| {
+      files
+        .par_iter()
+        .map(ArchiveEntry::stat)
+        .collect::<rhg_engine_core::Result<Vec<_>>>()
+    })?;
+    // Same rule as `Archive::add`, entries are unique by path
+    let mut seen = HashSet::new();
+    if let Some(e) = entries.iter().find(|e| !seen.insert(e.path())) {
+      return err!(
+        ErrorKind::InvalidInput,
+        format!("file '{}' already exists", e.path().display())
+      );
+    }
+
+    // The previous archive is only replaced once the new one is complete
+    let archive = archive.as_ref();
+    let tmp = temp_path(archive);
+    let summary = match self.write(&pool, &tmp, entries, files) {
+      Ok(summary) => summary,
+      Err(e) => {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+      }
+    };
+    std::fs::rename(&tmp, archive).map_err(|e| {
+      let _ = std::fs::remove_file(&tmp);
+      Error::new(
+        ErrorKind::IO,
+        format!("{}: {}", archive.display(), e),
+        None,
+        here!(),
+      )
+    })?;
+    Ok(PackSummary {
+      elapsed: start.elapsed(),
+      ..summary
+    })
+  }
+
+  fn write(
+    &self,
+    pool: &rayon::ThreadPool,
+    path: &Path,
+    entries: Vec<ArchiveEntry>,
+    files: &[PathBuf],
+  ) -> rhg_engine_core::Result<PackSummary> {
+    let total_bytes = entries.iter().map(|e| e.content_len()).sum();
+    let out = std::fs::File::create(path).map_err(|e| {
+      Error::new(
+        ErrorKind::IO,
+        format!("{}: {}", path.display(), e),
+        None,
+        here!(),
+      )
+    })?;
+    let mut writer = ArchiveWriter::new(BufWriter::new(out), entries)?;
+    let pb = self.progress_bar(total_bytes);
+    let mut summary = PackSummary::default();
+    // Files are read in batches so memory stays bounded while the output keeps its order
+    for batch in files.chunks(self.jobs * 2) {
+      let contents = pool.install(|| {
+        batch
+          .par_iter()
+          .map(|path| {
+            std::fs::read(path).map_err(|e| {
+              Error::new(
+                ErrorKind::IO,
+                format!("{}: {}", path.display(), e),
+                None,
+                here!(),
+              )
+            })
+          })
+          .collect::<Vec<_>>()
+      });
+      for content in contents {
+        let content = content?;
+        writer.write_content(&content)?;
+        summary.files += 1;
+        summary.bytes += content.len() as u64;
+        pb.inc(content.len() as u64);
+        pb.set_message(format!("{}/{} files", summary.files, files.len()));
+      }
+    }
+    writer.finish()?;
+    pb.finish_and_clear();
+    Ok(summary)
+  }
+}
+
+/// Hidden file next to `archive`, so that renaming it stays on the same file system
+fn temp_path(archive: &Path) -> PathBuf {
+  let name = archive
+    .file_name()
+    .map(|n| n.to_string_lossy().to_string())
+    .unwrap_or_default();
+  archive.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+#[cfg(test)]
+mod test {
+  use rhg_engine_core::Archive;
+
+  use super::Packer;
+
+  #[test]
+  fn pack() {
+    let root = std::env::temp_dir().join(format!("rhg_pack_pack_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let files = (0..10)
+      .map(|i| {
+        let path = root.join(format!("{}.txt", i));
+        std::fs::write(&path, i.to_string().repeat(i + 1)).unwrap();
+        path
+      })
+      .collect::<Vec<_>>();
+
+    let summary = Packer::default()
+      .with_jobs(3)
+      .pack(root.join("out.pack"), &files)
+      .unwrap();
+    assert_eq!(summary.files, 10);
+    assert_eq!(summary.bytes, 55);
+
+    let a = Archive::load_file(root.join("out.pack")).unwrap();
+    for (i, f) in a.files().iter().enumerate() {
+      assert_eq!(f.path(), &files[i]);
+      assert_eq!(f.content(), &i.to_string().repeat(i + 1).into_bytes());
+    }
+    std::fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn failure_keeps_archive() {
+    let root = std::env::temp_dir().join(format!("rhg_pack_keep_{}", std::process::id()));
+    for dir in ["textures", "models"] {
+      std::fs::create_dir_all(root.join(dir)).unwrap();
+      std::fs::write(root.join(dir).join("a.png"), dir).unwrap();
+    }
+    let files = vec![root.join("textures/a.png"), root.join("models/a.png")];
+    let out = root.join("out.pack");
+    Packer::default().pack(&out, &files).unwrap();
+    assert_eq!(Archive::load_file(&out).unwrap().files().len(), 2);
+
+    // Reading a directory fails once the archive is being written
+    let broken = vec![root.join("textures/a.png"), root.join("models")];
+    assert!(Packer::default().pack(&out, &broken).is_err());
+    assert!(Packer::default()
+      .pack(&out, &[files[0].clone(), files[0].clone()])
+      .is_err());
+    assert_eq!(Archive::load_file(&out).unwrap().files().len(), 2);
+    let mut left = std::fs::read_dir(&root)
+      .unwrap()
+      .map(|e| e.unwrap().file_name());
+    assert!(left.all(|name| !name.to_string_lossy().ends_with(".tmp")));
+    std::fs::remove_dir_all(&root).unwrap();
+  }
+}
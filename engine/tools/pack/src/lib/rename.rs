@@ -0,0 +1,350 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  fmt::Display,
+  path::{Component, PathBuf},
+  str::FromStr,
+};
+
+use rhg_engine_core::{err, Archive, ArchiveFile, ErrorKind};
+
+use crate::FilterSet;
+
+/// Text captured when matching a path against a pattern
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PathCaptures {
+  /// Whole match first, then one capture per group
+  indexed: Vec<Option<String>>,
+  named: BTreeMap<String, String>,
+}
+
+impl PathCaptures {
+  pub fn new(indexed: Vec<Option<String>>, named: BTreeMap<String, String>) -> Self {
+    Self { indexed, named }
+  }
+
+  pub fn get(&self, index: usize) -> Option<&Option<String>> {
+    self.indexed.get(index)
+  }
+
+  pub fn name(&self, name: &str) -> Option<&String> {
+    self.named.get(name)
+  }
+
+  pub fn len(&self) -> usize {
+    self.indexed.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.indexed.is_empty()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenamePart {
+  Literal(String),
+  /// `{1}`, text of a capture, `{0}` being the whole match
+  Index(usize),
+  /// `{name}`, text of a named capture
+  Name(String),
+}
+
+impl Display for RenamePart {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Literal(s) => write!(f, "{}", s.replace('{', "{{").replace('}', "}}")),
+      Self::Index(i) => write!(f, "{{{}}}", i),
+      Self::Name(name) => write!(f, "{{{}}}", name),
+    }
+  }
+}
+
+/// Destination path template filled from the captures of a filter, e.g. `albedo/{1}.png`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RenameTemplate(Vec<RenamePart>);
+
+impl RenameTemplate {
+  pub fn parts(&self) -> &Vec<RenamePart> {
+    &self.0
+  }
+
+  /// Fill the template, the result has to stay a relative path
+  pub fn render(&self, captures: &PathCaptures) -> rhg_engine_core::Result<PathBuf> {
+    let mut ret = String::new();
+    for part in &self.0 {
+      match part {
+        RenamePart::Literal(s) => ret.push_str(s),
+        RenamePart::Index(i) => match captures.get(*i) {
+          Some(value) => ret.push_str(value.as_deref().unwrap_or_default()),
+          None => {
+            return err!(
+              ErrorKind::InvalidInput,
+              format!(
+                "rename template '{}' refers to capture {{{}}} but the filter only has {}",
+                self,
+                i,
+                captures.len().saturating_sub(1)
+              )
+            )
+          }
+        },
+        RenamePart::Name(name) => match captures.name(name) {
+          Some(value) => ret.push_str(value),
+          None => {
+            return err!(
+              ErrorKind::InvalidInput,
+              format!(
+                "rename template '{}' refers to unknown capture {{{}}}",
+                self, name
+              )
+            )
+          }
+        },
+      }
+    }
+    let path = PathBuf::from(&ret);
+    if ret.is_empty()
+      || !path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+      return err!(
+        ErrorKind::InvalidInput,
+        format!("invalid renamed path '{}', expected a relative path", ret)
+      );
+    }
+    Ok(path)
+  }
+}
+
+impl Display for RenameTemplate {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for part in &self.0 {
+      write!(f, "{}", part)?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for RenameTemplate {
+  type Err = rhg_engine_core::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut ch_it = s.chars().peekable();
+    while let Some(ch) = ch_it.next() {
+      match ch {
+        '{' if ch_it.next_if_eq(&'{').is_some() => literal.push('{'),
+        '}' if ch_it.next_if_eq(&'}').is_some() => literal.push('}'),
+        '{' => {
+          let mut key = String::new();
+          loop {
+            match ch_it.next() {
+              Some('}') => break,
+              Some(ch) => key.push(ch),
+              None => {
+                return err!(
+                  ErrorKind::InvalidInput,
+                  format!("invalid rename template, unclosed '{{' in '{}'", s)
+                )
+              }
+            }
+          }
+          let part = match key.parse::<usize>() {
+            Ok(i) => RenamePart::Index(i),
+            Err(_) if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+              RenamePart::Name(key)
+            }
+            Err(_) => {
+              return err!(
+                ErrorKind::InvalidInput,
+                format!("invalid rename template capture '{{{}}}' in '{}'", key, s)
+              )
+            }
+          };
+          if !literal.is_empty() {
+            parts.push(RenamePart::Literal(std::mem::take(&mut literal)));
+          }
+          parts.push(part);
+        }
+        '}' => {
+          return err!(
+            ErrorKind::InvalidInput,
+            format!("invalid rename template, unmatched '}}' in '{}'", s)
+          )
+        }
+        ch => literal.push(ch),
+      }
+    }
+    if !literal.is_empty() {
+      parts.push(RenamePart::Literal(literal));
+    }
+    Ok(Self(parts))
+  }
+}
+
+pub fn parse_rename_template(value: &str) -> std::result::Result<RenameTemplate, std::io::Error> {
+  value
+    .parse::<RenameTemplate>()
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+/// Destination of every file selected by `set`, failing if two files would end up at the same path
+fn plan_renames<'a>(
+  files: &'a [ArchiveFile],
+  set: &FilterSet,
+  template: &RenameTemplate,
+) -> rhg_engine_core::Result<Vec<(&'a ArchiveFile, PathBuf)>> {
+  let mut ret = vec![];
+  let mut targets = HashMap::new();
+  for file in files {
+    let target = match set.rename(file.path(), template) {
+      Some(target) => target?,
+      None => file.path().clone(),
+    };
+    if let Some(other) = targets.insert(target.clone(), file.path()) {
+      return err!(
+        ErrorKind::InvalidInput,
+        format!(
+          "cannot rename '{}' to '{}', already used by '{}'",
+          file.path().display(),
+          target.display(),
+          other.display()
+        )
+      );
+    }
+    if set.matches_path(file.path()) {
+      ret.push((file, target));
+    }
+  }
+  Ok(ret)
+}
+
+/// Rename the entries selected by `set` inside the archive, leaving it untouched on conflicts.
+/// Returns the old and new paths of the renamed entries.
+pub fn rename_entries(
+  archive: &mut Archive,
+  set: &FilterSet,
+  template: &RenameTemplate,
+) -> rhg_engine_core::Result<Vec<(PathBuf, PathBuf)>> {
+  let renames = plan_renames(archive.files(), set, template)?
+    .into_iter()
+    .filter(|(file, target)| file.path() != target)
+    .map(|(file, target)| (file.path().clone(), target))
+    .collect::<HashMap<_, _>>();
+  let mut ret = vec![];
+  for file in archive.files_mut() {
+    if let Some(target) = renames.get(file.path()) {
+      ret.push((file.path().clone(), target.clone()));
+      *file.path_mut() = target.clone();
+    }
+  }
+  Ok(ret)
+}
+
+#[cfg(test)]
+mod test {
+  use std::{collections::BTreeMap, path::PathBuf};
+
+  use rhg_engine_core::{Archive, ErrorKind};
+
+  use crate::{Filter, FilterSet};
+
+  use super::{rename_entries, PathCaptures, RenamePart, RenameTemplate};
+
+  #[test]
+  fn parse() {
+    let t = "albedo/{1}_{{x}}.{ext}".parse::<RenameTemplate>().unwrap();
+    assert_eq!(
+      t.parts(),
+      &vec![
+        RenamePart::Literal("albedo/".to_string()),
+        RenamePart::Index(1),
+        RenamePart::Literal("_{x}.".to_string()),
+        RenamePart::Name("ext".to_string()),
+      ]
+    );
+    assert_eq!(t.to_string(), "albedo/{1}_{{x}}.{ext}");
+    assert_eq!(
+      "a/{1".parse::<RenameTemplate>().unwrap_err().kind(),
+      ErrorKind::InvalidInput
+    );
+    assert_eq!(
+      "a/}".parse::<RenameTemplate>().unwrap_err().kind(),
+      ErrorKind::InvalidInput
+    );
+    assert_eq!(
+      "a/{a-b}".parse::<RenameTemplate>().unwrap_err().kind(),
+      ErrorKind::InvalidInput
+    );
+  }
+
+  #[test]
+  fn render() {
+    let captures = PathCaptures::new(
+      vec![
+        Some("textures/wall_albedo.png".to_string()),
+        Some("wall".to_string()),
+        None,
+      ],
+      BTreeMap::from([("ext".to_string(), "png".to_string())]),
+    );
+    let render = |t: &str| t.parse::<RenameTemplate>().unwrap().render(&captures);
+    assert_eq!(
+      render("albedo/{1}{2}.{ext}").unwrap(),
+      PathBuf::from("albedo/wall.png")
+    );
+    assert_eq!(render("{3}").unwrap_err().kind(), ErrorKind::InvalidInput);
+    assert_eq!(
+      render("{missing}").unwrap_err().kind(),
+      ErrorKind::InvalidInput
+    );
+    assert_eq!(
+      render("../{1}").unwrap_err().kind(),
+      ErrorKind::InvalidInput
+    );
+    assert_eq!(render("/{1}").unwrap_err().kind(), ErrorKind::InvalidInput);
+  }
+
+  #[test]
+  fn rename_archive_entries() {
+    let mut a = Archive::default();
+    a.add_file("textures/wall_albedo.png", b"w").unwrap();
+    a.add_file("textures/floor_albedo.png", b"f").unwrap();
+    a.add_file("textures/wall_normal.png", b"n").unwrap();
+    let set = FilterSet::default().with_include("textures/*_albedo.png".parse::<Filter>().unwrap());
+    let template = "albedo/{1}.png".parse::<RenameTemplate>().unwrap();
+    let renamed = rename_entries(&mut a, &set, &template).unwrap();
+    assert_eq!(renamed.len(), 2);
+    assert_eq!(
+      a.files()
+        .iter()
+        .map(|f| f.path().clone())
+        .collect::<Vec<_>>(),
+      vec![
+        PathBuf::from("albedo/wall.png"),
+        PathBuf::from("albedo/floor.png"),
+        PathBuf::from("textures/wall_normal.png"),
+      ]
+    );
+
+    // Both entries would be renamed to the same path
+    let set = FilterSet::default().with_include("albedo/*.png".parse::<Filter>().unwrap());
+    let template = "all.png".parse::<RenameTemplate>().unwrap();
+    assert_eq!(
+      rename_entries(&mut a, &set, &template).unwrap_err().kind(),
+      ErrorKind::InvalidInput
+    );
+    assert_eq!(a.files()[0].path(), &PathBuf::from("albedo/wall.png"));
+
+    // Renaming onto an entry left in place
+    let set = FilterSet::default().with_include("albedo/wall.png".parse::<Filter>().unwrap());
+    let template = "textures/wall_normal.png"
+      .parse::<RenameTemplate>()
+      .unwrap();
+    assert_eq!(
+      rename_entries(&mut a, &set, &template).unwrap_err().kind(),
+      ErrorKind::InvalidInput
+    );
+  }
+}
@@ -0,0 +1,321 @@
+use std::{
+  collections::BTreeSet,
+  fmt::Display,
+  path::{Path, PathBuf},
+  sync::mpsc::{channel, RecvTimeoutError},
+  time::{Duration, Instant},
+};
+
+use notify::{RecursiveMode, Watcher as _};
+use rhg_engine_core::{here, Archive, ArchiveFile, Error, ErrorKind};
+
+/// Summary of a single archive rebuild
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RebuildSummary {
+  pub updated: Vec<PathBuf>,
+  pub removed: Vec<PathBuf>,
+  pub elapsed: Duration,
+}
+
+impl RebuildSummary {
+  pub fn is_empty(&self) -> bool {
+    self.updated.is_empty() && self.removed.is_empty()
+  }
+}
+
+impl Display for RebuildSummary {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} updated, {} removed in {}ms",
+      self.updated.len(),
+      self.removed.len(),
+      self.elapsed.as_millis()
+    )
+  }
+}
+
+#[derive(Debug, Clone)]
+struct WatchedSource {
+  path: PathBuf,
+  canonical: PathBuf,
+}
+
+/// Keeps an archive in sync with a set of source directories
+pub struct PackWatcher {
+  archive_path: PathBuf,
+  archive: Archive,
+  sources: Vec<WatchedSource>,
+  debounce: Duration,
+}
+
+impl PackWatcher {
+  pub fn new<P: AsRef<Path>>(
+    archive_path: P,
+    sources: &[PathBuf],
+    debounce: Duration,
+  ) -> rhg_engine_core::Result<Self> {
+    let archive_path = archive_path.as_ref().to_path_buf();
+    let archive = match archive_path.exists() {
+      true => Archive::load_file(&archive_path)?,
+      false => Archive::default(),
+    };
+    let sources = sources
+      .iter()
+      .map(|path| {
+        let canonical = path.canonicalize().map_err(|e| {
+          Error::new(
+            ErrorKind::IO,
+            format!("{}: {}", path.display(), e),
+            None,
+            here!(),
+          )
+        })?;
+        Ok(WatchedSource {
+          path: path.clone(),
+          canonical,
+        })
+      })
+      .collect::<rhg_engine_core::Result<Vec<_>>>()?;
+    Ok(Self {
+      archive_path,
+      archive,
+      sources,
+      debounce,
+    })
+  }
+
+  pub fn archive(&self) -> &Archive {
+    &self.archive
+  }
+
+  pub fn debounce(&self) -> Duration {
+    self.debounce
+  }
+
+  /// Map a path reported by the file system back to its archive entry path
+  pub fn entry_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+    let path = path.as_ref();
+    for src in &self.sources {
+      if let Ok(rest) = path.strip_prefix(&src.canonical) {
+        return Some(src.path.join(rest));
+      }
+      if let Ok(rest) = path.strip_prefix(&src.path) {
+        return Some(src.path.join(rest));
+      }
+    }
+    None
+  }
+
+  fn is_archive<P: AsRef<Path>>(&self, path: P) -> bool {
+    match (
+      path.as_ref().canonicalize(),
+      self.archive_path.canonicalize(),
+    ) {
+      (Ok(path), Ok(archive)) => path == archive,
+      _ => false,
+    }
+  }
+
+  fn put(&mut self, entry: &Path, summary: &mut RebuildSummary) -> rhg_engine_core::Result<()> {
+    if entry.is_dir() {
+      for file in collect_files(entry)? {
+        self.put(&file, summary)?;
+      }
+      return Ok(());
+    }
+    if self.is_archive(entry) {
+      return Ok(());
+    }
+    let file = ArchiveFile::load(entry)?;
+    self.archive.remove_path(entry);
+    self.archive.add(file)?;
+    summary.updated.push(entry.to_path_buf());
+    Ok(())
+  }
+
+  fn discard(&mut self, entry: &Path, summary: &mut RebuildSummary) {
+    self.archive.files_mut().retain(|f| {
+      let gone = f.path().starts_with(entry);
+      if gone {
+        summary.removed.push(f.path().clone());
+      }
+      !gone
+    });
+  }
+
+  /// Add every file found in the watched sources, drop the entries of the sources whose file
+  /// is gone and write the archive
+  pub fn rebuild_all(&mut self) -> rhg_engine_core::Result<RebuildSummary> {
+    let mut changed = self
+      .sources
+      .iter()
+      .map(|src| src.path.clone())
+      .collect::<BTreeSet<_>>();
+    for f in self.archive.files() {
+      let in_source = self
+        .sources
+        .iter()
+        .any(|src| f.path().starts_with(&src.path));
+      if in_source && !f.path().exists() {
+        changed.insert(f.path().clone());
+      }
+    }
+    self.apply(&changed)
+  }
+
+  /// Apply a batch of changed paths to the archive and write it
+  pub fn apply(&mut self, changed: &BTreeSet<PathBuf>) -> rhg_engine_core::Result<RebuildSummary> {
+    let start = Instant::now();
+    let mut summary = RebuildSummary::default();
+    for path in changed {
+      let entry = match self.entry_path(path) {
+        Some(entry) => entry,
+        None => continue,
+      };
+      if entry.exists() {
+        self.put(&entry, &mut summary)?;
+      } else {
+        self.discard(&entry, &mut summary);
+      }
+    }
+    if !summary.is_empty() {
+      self.archive.save_file(&self.archive_path)?;
+    }
+    summary.elapsed = start.elapsed();
+    Ok(summary)
+  }
+
+  /// Watch the sources until the event channel closes, calling `on_rebuild`
+  /// once per debounced batch of changes
+  pub fn run<F: FnMut(rhg_engine_core::Result<RebuildSummary>)>(
+    &mut self,
+    mut on_rebuild: F,
+  ) -> rhg_engine_core::Result<()> {
+    let (tx, rx) = channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(notify_error)?;
+    for src in &self.sources {
+      watcher
+        .watch(&src.canonical, RecursiveMode::Recursive)
+        .map_err(notify_error)?;
+    }
+    let mut pending = BTreeSet::new();
+    loop {
+      let received = match pending.is_empty() {
+        true => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        false => rx.recv_timeout(self.debounce),
+      };
+      match received {
+        Ok(Ok(event)) => {
+          if !event.kind.is_access() {
+            pending.extend(event.paths);
+          }
+        }
+        Ok(Err(e)) => on_rebuild(Err(notify_error(e))),
+        Err(RecvTimeoutError::Timeout) => {
+          let changed = std::mem::take(&mut pending);
+          let summary = self.apply(&changed);
+          if !matches!(summary, Ok(ref summary) if summary.is_empty()) {
+            on_rebuild(summary);
+          }
+        }
+        Err(RecvTimeoutError::Disconnected) => return Ok(()),
+      }
+    }
+  }
+}
+
+fn notify_error(e: notify::Error) -> Error {
+  Error::new(ErrorKind::IO, format!("watch failed, {}", e), None, here!())
+}
+
+/// Recursively list the files found under `dir`
+pub fn collect_files<P: AsRef<Path>>(dir: P) -> rhg_engine_core::Result<Vec<PathBuf>> {
+  let mut ret = vec![];
+  let mut dirs = vec![dir.as_ref().to_path_buf()];
+  while let Some(dir) = dirs.pop() {
+    for entry in std::fs::read_dir(&dir)? {
+      let path = entry?.path();
+      if path.is_dir() {
+        dirs.push(path);
+      } else {
+        ret.push(path);
+      }
+    }
+  }
+  ret.sort();
+  Ok(ret)
+}
+
+#[cfg(test)]
+mod test {
+  use std::{collections::BTreeSet, path::PathBuf, time::Duration};
+
+  use super::PackWatcher;
+
+  #[test]
+  fn incremental() {
+    let root = std::env::temp_dir().join(format!("rhg_pack_watch_{}", std::process::id()));
+    let src = root.join("assets");
+    std::fs::create_dir_all(src.join("textures")).unwrap();
+    std::fs::write(src.join("a.txt"), b"a").unwrap();
+    std::fs::write(src.join("textures/b.txt"), b"b").unwrap();
+
+    let mut w = PackWatcher::new(
+      root.join("out.pack"),
+      std::slice::from_ref(&src),
+      Duration::from_millis(10),
+    )
+    .unwrap();
+    let summary = w.rebuild_all().unwrap();
+    assert_eq!(summary.updated.len(), 2);
+    assert_eq!(w.archive().files().len(), 2);
+
+    std::fs::write(src.join("a.txt"), b"aa").unwrap();
+    std::fs::remove_file(src.join("textures/b.txt")).unwrap();
+    let changed = [
+      src.canonicalize().unwrap().join("a.txt"),
+      src.canonicalize().unwrap().join("textures/b.txt"),
+    ]
+    .into_iter()
+    .collect::<BTreeSet<PathBuf>>();
+    let summary = w.apply(&changed).unwrap();
+    assert_eq!(summary.updated, vec![src.join("a.txt")]);
+    assert_eq!(summary.removed, vec![src.join("textures/b.txt")]);
+    assert_eq!(w.archive().files().len(), 1);
+    assert_eq!(w.archive().files()[0].content(), &b"aa".to_vec());
+
+    std::fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn restart() {
+    let root = std::env::temp_dir().join(format!("rhg_pack_restart_{}", std::process::id()));
+    let src = root.join("assets");
+    for dir in ["textures", "models"] {
+      std::fs::create_dir_all(src.join(dir)).unwrap();
+      std::fs::write(src.join(dir).join("a.png"), dir).unwrap();
+    }
+    let sources = std::slice::from_ref(&src);
+    let mut w = PackWatcher::new(root.join("out.pack"), sources, Duration::ZERO).unwrap();
+    w.rebuild_all().unwrap();
+    assert_eq!(w.archive().files().len(), 2);
+
+    // Same file name in another directory
+    std::fs::write(src.join("models/a.png"), b"model").unwrap();
+    let changed = BTreeSet::from([src.join("models/a.png")]);
+    assert_eq!(w.apply(&changed).unwrap().updated.len(), 1);
+    assert_eq!(w.archive().files().len(), 2);
+
+    // Removed while the watcher was not running
+    drop(w);
+    std::fs::remove_file(src.join("textures/a.png")).unwrap();
+    let mut w = PackWatcher::new(root.join("out.pack"), sources, Duration::ZERO).unwrap();
+    let summary = w.rebuild_all().unwrap();
+    assert_eq!(summary.removed, vec![src.join("textures/a.png")]);
+    assert_eq!(w.archive().files().len(), 1);
+    assert_eq!(w.archive().files()[0].content(), &b"model".to_vec());
+
+    std::fs::remove_dir_all(&root).unwrap();
+  }
+}